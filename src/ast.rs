@@ -1,89 +1,476 @@
 use crate::object::Object;
-use std::fmt;
+use crate::token::Token;
 
+/// A parsed source file: its top level declarations and statements in source order.
 #[derive(Debug)]
-pub struct AstToken {
-    pub ast_type: AstType,
-    pub literal: Option<Object>,
-    lexeme: String,
+pub struct Program {
+    /// `#![...]` attributes at the top of the file, they apply to the whole program.
+    pub attributes: Vec<Attribute>,
+    pub body: Vec<Stmt>,
 }
 
-impl AstToken {
-    pub fn new(ast_type: AstType, lexeme: String, literal: Option<Object>) -> AstToken {
-        AstToken {
-            ast_type,
-            lexeme,
-            literal,
+/// `#[name(arg, ..)]`, such as `#[allow(unused_variables)]`, or `#[name = "value"]`, such as
+/// `#[js_name = "console.error"]`.
+#[derive(Debug)]
+pub struct Attribute {
+    pub name: Token,
+    pub args: Vec<Token>,
+    /// The string after `=`.
+    pub value: Option<Token>,
+}
+
+impl Attribute {
+    /// What the string after `=` says, `console.error` in `#[js_name = "console.error"]`.
+    pub fn string(&self) -> Option<&str> {
+        match self.value.as_ref()?.literal.as_ref()? {
+            Object::Str(value) => Some(value),
+            _ => None,
         }
     }
-    pub fn ast_type(&self) -> AstType {
-        self.ast_type.clone()
+}
+
+#[derive(Debug)]
+pub enum Item {
+    Fn(Box<FnDecl>),
+    Struct(StructDecl),
+    Impl(ImplDecl),
+    Enum(EnumDecl),
+    Trait(TraitDecl),
+    Mod(ModDecl),
+    Use(UseDecl),
+    Extern(ExternDecl),
+}
+
+#[derive(Debug)]
+pub struct FnDecl {
+    pub attributes: Vec<Attribute>,
+    /// `pub fn`, other modules can use it. Trait methods are always public.
+    pub public: bool,
+    pub name: Token,
+    /// The type parameters of a generic function, `T` in `fn first<T>(v: Vec<T>) -> Option<T>`.
+    pub generics: Vec<GenericParam>,
+    /// The `self` token of a method, `None` for plain and associated functions.
+    pub self_param: Option<Token>,
+    /// Whether the method takes `&mut self` or `mut self`, so it may assign to fields of `self`.
+    pub self_mutable: bool,
+    pub params: Vec<Param>,
+    pub return_type: Option<TypeAnnotation>,
+    pub body: Vec<Stmt>,
+}
+
+/// A function parameter, `name: type`.
+#[derive(Debug)]
+pub struct Param {
+    pub name: Token,
+    /// `mut name: type`, the parameter may be assigned to in the body.
+    pub mutable: bool,
+    pub annotation: TypeAnnotation,
+}
+
+/// A written type.
+#[derive(Debug)]
+pub enum TypeAnnotation {
+    /// A built in type such as `num` or `i64`, or the name of a struct or enum.
+    Named(Token),
+    /// A type with type arguments, `Vec<num>`.
+    Generic {
+        name: Token,
+        args: Vec<TypeAnnotation>,
+    },
+    /// `[num]`, the same type as `Vec<num>`.
+    Array {
+        bracket: Token,
+        element: Box<TypeAnnotation>,
+    },
+    /// `(num, str)`, and `()` for the unit type.
+    Tuple {
+        paren: Token,
+        elements: Vec<TypeAnnotation>,
+    },
+    /// `fn(num, num) -> num`, the return type is `()` when left out.
+    Function {
+        keyword: Token,
+        params: Vec<TypeAnnotation>,
+        return_type: Option<Box<TypeAnnotation>>,
+    },
+}
+
+impl TypeAnnotation {
+    /// The token used to point at this type in error messages.
+    pub fn token(&self) -> &Token {
+        match self {
+            TypeAnnotation::Named(name) => name,
+            TypeAnnotation::Generic { name, .. } => name,
+            TypeAnnotation::Array { bracket, .. } => bracket,
+            TypeAnnotation::Tuple { paren, .. } => paren,
+            TypeAnnotation::Function { keyword, .. } => keyword,
+        }
     }
-    pub fn ast_lexeme(&self) -> &str {
-        &self.lexeme
+}
+
+/// A closure parameter, the annotation can be left out when the closure is passed where a
+/// function type is expected.
+#[derive(Debug)]
+pub struct ClosureParam {
+    pub name: Token,
+    pub mutable: bool,
+    pub annotation: Option<TypeAnnotation>,
+}
+
+/// `struct Point { x: num, y: num }`
+#[derive(Debug)]
+pub struct StructDecl {
+    pub public: bool,
+    pub name: Token,
+    /// The type parameters of a generic struct, `A` and `B` in `struct Pair<A, B>`.
+    pub generics: Vec<GenericParam>,
+    pub fields: Vec<Field>,
+}
+
+/// A struct field, `pub name: type` when other modules can use it.
+#[derive(Debug)]
+pub struct Field {
+    pub public: bool,
+    pub name: Token,
+    pub annotation: TypeAnnotation,
+}
+
+/// A type parameter and the traits the types it stands for must implement, `T: Shape + Named`.
+#[derive(Debug)]
+pub struct GenericParam {
+    pub name: Token,
+    pub bounds: Vec<Token>,
+}
+
+/// `impl Point { ... }`, the methods and associated functions of a struct, or `impl Shape for Circle { ... }`,
+/// its implementation of a trait.
+#[derive(Debug)]
+pub struct ImplDecl {
+    /// The type parameters declared by `impl<A, B> Pair<A, B>`.
+    pub generics: Vec<GenericParam>,
+    /// The trait implemented, `None` for the struct's own methods.
+    pub trait_name: Option<Token>,
+    pub name: Token,
+    /// The type arguments the struct is given in the header, `A` and `B` in `Pair<A, B>`.
+    pub args: Vec<TypeAnnotation>,
+    pub methods: Vec<FnDecl>,
+}
+
+/// `trait Shape { fn area(&self) -> num; fn describe(&self) -> str { .. } }`
+#[derive(Debug)]
+pub struct TraitDecl {
+    pub public: bool,
+    pub name: Token,
+    /// The methods every implementation must define, only their signatures, their bodies are empty.
+    pub required: Vec<FnDecl>,
+    /// The methods with a default body, used where an implementation does not define them.
+    pub provided: Vec<FnDecl>,
+}
+
+/// `mod shapes;`, the module in `shapes.rst` or `shapes/mod.rst` next to the declaring file. A module
+/// declared by the root file or a `mod.rst` is looked for in the same directory, one declared by
+/// `shapes.rst` in `shapes/`.
+#[derive(Debug)]
+pub struct ModDecl {
+    pub public: bool,
+    pub name: Token,
+}
+
+/// `use shapes::Circle;` or `use crate::shapes::{Circle, area};`, items of another module used by name.
+#[derive(Debug)]
+pub struct UseDecl {
+    pub keyword: Token,
+    /// The module imported from: `crate` for the root, `super` for the parent, or a child module, then
+    /// the modules inside it.
+    pub path: Vec<Token>,
+    pub names: Vec<Token>,
+}
+
+/// `extern "js" { fn setTimeout(f: fn(), ms: num); }`, functions Javascript already has, which are
+/// called with the signatures declared for them. `#[js_name = "console.error"]` on a function calls
+/// it by another name, `#[js_module = "node:fs"]` on the block imports its functions from a module.
+#[derive(Debug)]
+pub struct ExternDecl {
+    pub attributes: Vec<Attribute>,
+    /// The `"js"` string after `extern`.
+    pub abi: Token,
+    /// Signatures only, their bodies are empty.
+    pub functions: Vec<FnDecl>,
+}
+
+/// `enum Shape { Circle(num), Rect(num, num), Empty }`
+#[derive(Debug)]
+pub struct EnumDecl {
+    pub public: bool,
+    pub name: Token,
+    pub variants: Vec<Variant>,
+}
+
+/// An enum variant and the type annotations of its payload, empty for C-like variants.
+#[derive(Debug)]
+pub struct Variant {
+    pub name: Token,
+    pub fields: Vec<TypeAnnotation>,
+}
+
+#[derive(Debug)]
+pub enum Stmt {
+    Item(Item),
+    /// `let pattern = value;`, such as `let mut x = 1;` or `let (a, b) = pair;`. A name can only be
+    /// assigned to again when it is bound with `mut`.
+    Let {
+        pattern: Pattern,
+        /// `let name: type = value;`, the type is taken from the value when left out.
+        annotation: Option<TypeAnnotation>,
+        initializer: Expr,
+    },
+    Expression(Expr),
+    /// An expression without a trailing `;` at the end of a block, it is the block's value.
+    Tail(Expr),
+    Print {
+        keyword: Token,
+        format: String,
+        args: Vec<Expr>,
+    },
+    Block(Vec<Stmt>),
+    /// `else_branch` is either another `If` (an `else if` chain) or a `Block`.
+    If {
+        condition: Expr,
+        then_branch: Vec<Stmt>,
+        else_branch: Option<Box<Stmt>>,
+    },
+    While {
+        condition: Expr,
+        body: Vec<Stmt>,
+    },
+    /// `for variable in iterable { body }`, the iterable is a `Range` or a vector.
+    For {
+        variable: Token,
+        iterable: Expr,
+        body: Vec<Stmt>,
+    },
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+    Break(Token),
+}
+
+#[derive(Debug)]
+pub enum Expr {
+    Literal {
+        value: Object,
+        token: Token,
+    },
+    Variable(Token),
+    /// `Shape::Circle`, each segment is an identifier token. `Some`, `None`, `Ok` and `Err` are
+    /// paths of a single segment. A path may start with the modules an item is in, `util::twice`
+    /// or `crate::util::twice`, then its first segments may also be `crate` or `super`.
+    Path(Vec<Token>),
+    SelfValue(Token),
+    /// `Point { x: 1, y: 2 }`, the name may also be `Self` inside an impl.
+    StructLiteral {
+        name: Token,
+        fields: Vec<(Token, Expr)>,
+    },
+    /// Field access, `object.name`, or `tuple.0` where the name is a number token.
+    Get {
+        object: Box<Expr>,
+        name: Token,
+    },
+    /// Field assignment, `object.name = value` or `tuple.0 = value`.
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+    },
+    Assign {
+        name: Token,
+        value: Box<Expr>,
+    },
+    Binary {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
+    Unary {
+        operator: Token,
+        right: Box<Expr>,
+    },
+    /// `value as i64`, converts between numeric types.
+    Cast {
+        value: Box<Expr>,
+        keyword: Token,
+        target: TypeAnnotation,
+    },
+    Grouping(Box<Expr>),
+    /// `(a, b)`, lowered to a Javascript array.
+    Tuple {
+        paren: Token,
+        elements: Vec<Expr>,
+    },
+    /// `[1, 2, 3]`, a vector literal lowered to a Javascript array.
+    Array {
+        bracket: Token,
+        elements: Vec<Expr>,
+    },
+    /// `object[index]`
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    /// Element assignment, `object[index] = value`.
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    Match {
+        keyword: Token,
+        scrutinee: Box<Expr>,
+        arms: Vec<MatchArm>,
+    },
+    /// `start..end`, or `start..=end` when inclusive. Only the iterable of a `for` loop is a range.
+    Range {
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+    },
+    Call {
+        callee: Box<Expr>,
+        paren: Token,
+        args: Vec<Expr>,
+    },
+    /// `value?`, unwraps a `Some` or `Ok` and returns a `None` or `Err` from the enclosing function.
+    Try {
+        value: Box<Expr>,
+        question: Token,
+    },
+    /// `|a: num, b: num| a + b`, lowered to a Javascript arrow function. A return type
+    /// annotation requires a block body, `|x: num| -> num { x * 2 }`.
+    Closure {
+        pipe: Token,
+        params: Vec<ClosureParam>,
+        return_type: Option<TypeAnnotation>,
+        body: Box<ArmBody>,
+    },
+}
+
+impl Expr {
+    /// The token used to point at this expression in error messages.
+    pub fn token(&self) -> &Token {
+        match self {
+            Expr::Literal { token, .. } => token,
+            Expr::Variable(name) => name,
+            Expr::Path(segments) => segments.last().expect("paths have at least one segment"),
+            Expr::SelfValue(keyword) => keyword,
+            Expr::StructLiteral { name, .. } => name,
+            Expr::Get { name, .. } => name,
+            Expr::Set { name, .. } => name,
+            Expr::Assign { name, .. } => name,
+            Expr::Binary { operator, .. } => operator,
+            Expr::Unary { operator, .. } => operator,
+            Expr::Cast { keyword, .. } => keyword,
+            Expr::Grouping(expr) => expr.token(),
+            Expr::Tuple { paren, .. } => paren,
+            Expr::Array { bracket, .. } => bracket,
+            Expr::Index { bracket, .. } => bracket,
+            Expr::SetIndex { bracket, .. } => bracket,
+            Expr::Match { keyword, .. } => keyword,
+            Expr::Range { start, .. } => start.token(),
+            Expr::Call { paren, .. } => paren,
+            Expr::Try { question, .. } => question,
+            Expr::Closure { pipe, .. } => pipe,
+        }
     }
+
+    /// The first token of the expression, `token` points at the operator of a binary expression.
+    pub fn leftmost(&self) -> &Token {
+        match self {
+            Expr::Binary { left, .. } => left.leftmost(),
+            Expr::Get { object, .. } | Expr::Set { object, .. } => object.leftmost(),
+            Expr::Index { object, .. } | Expr::SetIndex { object, .. } => object.leftmost(),
+            Expr::Call { callee, .. } => callee.leftmost(),
+            Expr::Try { value, .. } | Expr::Cast { value, .. } => value.leftmost(),
+            Expr::Range { start, .. } => start.leftmost(),
+            _ => self.token(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: ArmBody,
 }
 
-impl fmt::Display for AstToken {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{:?} {}",
-            self.ast_type,
-            if let Some(literal) = &self.literal {
-                literal.to_string()
-            } else {
-                "None".to_string()
+/// The right hand side of `pattern => ...` or the body of a closure, a single expression or a
+/// braced block.
+#[derive(Debug)]
+pub enum ArmBody {
+    Expr(Expr),
+    Block(Vec<Stmt>),
+}
+
+#[derive(Debug)]
+pub enum Pattern {
+    /// `_`
+    Wildcard(Token),
+    Literal {
+        value: Object,
+        token: Token,
+    },
+    /// A bare name, matches anything and binds it. `mut name` binds it mutably.
+    Binding {
+        name: Token,
+        mutable: bool,
+    },
+    /// `Shape::Circle(r)` or `Color::Red`, and `Some(x)` or `None` for the built in enums.
+    Variant {
+        path: Vec<Token>,
+        fields: Vec<Pattern>,
+    },
+    Tuple {
+        paren: Token,
+        elements: Vec<Pattern>,
+    },
+    /// `Point { x, y: py, .. }`, a field without a pattern binds a variable of the same name.
+    /// `rest` is set when the pattern ends with `..` and may leave fields out.
+    Struct {
+        name: Token,
+        fields: Vec<(Token, Pattern)>,
+        rest: bool,
+    },
+}
+
+impl Pattern {
+    pub fn token(&self) -> &Token {
+        match self {
+            Pattern::Wildcard(token) => token,
+            Pattern::Literal { token, .. } => token,
+            Pattern::Binding { name, .. } => name,
+            Pattern::Variant { path, .. } => path.last().expect("paths have at least one segment"),
+            Pattern::Tuple { paren, .. } => paren,
+            Pattern::Struct { name, .. } => name,
+        }
+    }
+
+    /// Wildcards and bindings match every value.
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard(_) | Pattern::Binding { .. })
+    }
+
+    /// The names this pattern binds, in source order.
+    pub fn bindings(&self) -> Vec<&Token> {
+        match self {
+            Pattern::Binding { name, .. } => vec![name],
+            Pattern::Wildcard(_) | Pattern::Literal { .. } => Vec::new(),
+            Pattern::Variant { fields: elements, .. } | Pattern::Tuple { elements, .. } => {
+                elements.iter().flat_map(|element| element.bindings()).collect()
             }
-        )
+            Pattern::Struct { fields, .. } => fields.iter().flat_map(|(_, field)| field.bindings()).collect(),
+        }
     }
 }
-
-#[derive(Debug, PartialEq, Clone, Hash, Eq)]
-pub enum AstType {
-    Set,
-    Break,
-    LeftParen,
-    RightParen,
-    LeftBrace,
-    RightBrace,
-    Comma,
-    Dot,
-    Minus,
-    Plus,
-    SemiColon,
-    Slash,
-    Star,
-    Bang,
-    BangEqual,
-    Assign, // ('=')
-    Equals, // ('==')
-    Greater,
-    GreaterEqual,
-    Less,
-    LessEqual,
-    Identifier,
-    String,
-    Number,
-    And,
-    Class,
-    Else,
-    False,
-    Fn,
-    For,
-    If,
-    Nil,
-    Or,
-    Print,
-    Return,
-    Super,
-    This,
-    True,
-    Let,
-    While,
-    Eof,
-    Main,
-    NumberType(String),
-    StringType(String),
-    UnKnown,
-}
\ No newline at end of file
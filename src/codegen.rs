@@ -0,0 +1,1316 @@
+use std::collections::HashMap;
+
+use crate::ast::*;
+use crate::modules::Module;
+use crate::object::Object;
+use crate::token::Token;
+use crate::token_type::TokenType;
+use crate::typechecker::{is_vec_new, prelude_variant};
+use crate::types::{Builtin, Cast, TypeTable};
+
+/// How a program of several modules is emitted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Output {
+    /// One script, each module but the root runs in a function that returns its public items.
+    Bundle,
+    /// An ES module per source file, importing from each other.
+    EsModules,
+}
+
+/// Walks a parsed `Program` and lowers it to Javascript source.
+pub struct CodeGen<'a> {
+    code: String,
+    tabs: usize,
+    //the module being emitted and how it is linked to the others, `None` for a program of one file
+    module: Option<(&'a Module, Output)>,
+    //a library has no entry point, `main` is a function like any other
+    library: bool,
+    //field names of each struct in declaration order, the order of its constructor arguments
+    struct_fields: HashMap<String, Vec<String>>,
+    //the methods of every impl block, emitted inside the class of their struct
+    impls: HashMap<String, Vec<&'a FnDecl>>,
+    //the traits each struct implements, their provided methods are copied onto its class
+    trait_impls: HashMap<String, Vec<&'a Token>>,
+    //the struct `Self` refers to while emitting its methods
+    self_type: Option<String>,
+    //the Javascript name of each variable in the scopes being emitted, innermost last
+    scopes: Vec<HashMap<String, String>>,
+    //how many names have been renamed so far, keeps renamed names unique
+    renamed: usize,
+    //the Javascript modules extern blocks import from, each with the names imported and what they are called here
+    extern_modules: Vec<(String, Vec<(String, String)>)>,
+    //what the typechecker found out, such as which calls are of built in methods
+    types: &'a TypeTable,
+}
+
+//What the statement in tail position of a block does with the block's value
+#[derive(Clone, Copy)]
+enum Tail<'t> {
+    //nothing, the block is run for its effects
+    Discard,
+    Return,
+    //assigned to the temporary of a match used as a value
+    Assign(&'t str),
+}
+
+impl<'a> CodeGen<'a> {
+    pub fn new(types: &'a TypeTable) -> CodeGen<'a> {
+        CodeGen {
+            code: String::new(),
+            tabs: 0,
+            module: None,
+            library: false,
+            struct_fields: HashMap::new(),
+            impls: HashMap::new(),
+            trait_impls: HashMap::new(),
+            self_type: None,
+            scopes: vec![HashMap::new()],
+            renamed: 0,
+            extern_modules: Vec::new(),
+            types,
+        }
+    }
+
+    /// Emit `program` as the module `module` of a program of several modules.
+    pub fn module(mut self, module: &'a Module, output: Output) -> CodeGen<'a> {
+        self.module = Some((module, output));
+        self
+    }
+
+    /// Emit the program as a library, which only defines its items for the modules that import it.
+    pub fn library(mut self, library: bool) -> CodeGen<'a> {
+        self.library = library;
+        self
+    }
+
+    pub fn generate(mut self, program: &'a Program) -> String {
+        self.struct_fields = self.types.imported_fields.clone();
+        for stmt in &program.body {
+            match stmt {
+                Stmt::Item(Item::Struct(decl)) => {
+                    let fields = decl.fields.iter().map(|f| f.name.as_string()).collect();
+                    self.struct_fields.insert(decl.name.as_string(), fields);
+                }
+                Stmt::Item(Item::Impl(decl)) => {
+                    self.impls.entry(decl.name.as_string()).or_default().extend(&decl.methods);
+                    if let Some(trait_name) = &decl.trait_name {
+                        self.trait_impls.entry(decl.name.as_string()).or_default().push(trait_name);
+                    }
+                }
+                //the module a late import is from is not defined yet, its functions are looked up when called
+                Stmt::Item(Item::Use(decl)) => {
+                    let Some((module, Output::Bundle)) = self.module else {
+                        continue;
+                    };
+                    let imported = &module.imports[&decl.keyword.offset];
+                    if !module.late_imports.contains(&decl.keyword.offset) || imported.is_empty() {
+                        continue;
+                    }
+                    for name in &decl.names {
+                        let qualified = format!("{}.{}", module_variable(imported), js_name(&name.as_string()));
+                        self.scopes[0].insert(name.as_string(), qualified);
+                    }
+                }
+                _ => {}
+            }
+        }
+        //a `let` of the name of a top level item gets a new name, so the item can still be used
+        //before it in the block
+        for stmt in &program.body {
+            let names = match stmt {
+                Stmt::Item(Item::Use(decl)) => decl.names.iter().collect(),
+                stmt => item_name(stmt).into_iter().collect::<Vec<&Token>>(),
+            };
+            for name in names {
+                self.scopes[0].entry(name.as_string()).or_insert_with(|| js_name(&name.as_string()));
+            }
+        }
+        self.bind_externs(program);
+
+        let wrapper = match self.module {
+            Some((module, Output::Bundle)) if !module.path.is_empty() => Some(module_variable(&module.path)),
+            _ => None,
+        };
+        if let Some(variable) = &wrapper {
+            self.line(&format!("const {variable} = (() => {{"));
+            self.tabs += 1;
+        }
+
+        self.runtime();
+        self.imports(program);
+        self.path_imports();
+        self.extern_imports();
+
+        //the classes of the structs that implement a trait copy its provided methods, so traits go
+        //first, and main runs as soon as it is emitted, so it goes last to see every other top level item
+        let (main, rest): (Vec<&Stmt>, Vec<&Stmt>) =
+            program.body.iter().partition(|stmt| self.has_entry_point() && is_main(stmt));
+        let (traits, rest): (Vec<&Stmt>, Vec<&Stmt>) =
+            rest.into_iter().partition(|stmt| matches!(stmt, Stmt::Item(Item::Trait(_))));
+        for stmt in traits.into_iter().chain(rest) {
+            self.stmt(stmt);
+        }
+        self.named_items();
+        for stmt in main {
+            self.stmt(stmt);
+        }
+
+        if wrapper.is_some() {
+            let exported: Vec<String> = program
+                .body
+                .iter()
+                .filter_map(|stmt| public_item(stmt).or(item_name(stmt).filter(|name| self.imported_elsewhere(name))))
+                .map(|name| js_name(&name.as_string()))
+                .collect();
+            match exported.is_empty() {
+                true => self.line("return {};"),
+                false => self.line(&format!("return {{ {} }};", exported.join(", "))),
+            }
+            self.tabs -= 1;
+            self.line("})();");
+        }
+        self.code
+    }
+
+    //A module's `use` declarations, a bundled module takes the items from the value its function
+    //returned. The root module's items are already in scope of every module in a bundle
+    fn imports(&mut self, program: &Program) {
+        let Some((module, output)) = self.module else {
+            return;
+        };
+        for stmt in &program.body {
+            let Stmt::Item(Item::Use(decl)) = stmt else {
+                continue;
+            };
+            let imported = &module.imports[&decl.keyword.offset];
+            let names: Vec<String> = decl.names.iter().map(|name| js_name(&name.as_string())).collect();
+            match output {
+                Output::Bundle if imported.is_empty() || module.late_imports.contains(&decl.keyword.offset) => {}
+                Output::Bundle => {
+                    self.line(&format!("const {{ {} }} = {};", names.join(", "), module_variable(imported)));
+                }
+                Output::EsModules => {
+                    let from = relative_file(&module.path, imported);
+                    self.line(&format!("import {{ {} }} from \"{}\";", names.join(", "), from));
+                }
+            }
+        }
+    }
+
+    //The modules whose items a path names, `util::twice`. An ES module imports all of a module's
+    //exports as one object, a bundled module already has the object its function returned
+    fn path_imports(&mut self) {
+        let Some((module, Output::EsModules)) = self.module else {
+            return;
+        };
+        let mut paths: Vec<(&usize, &String)> = module.paths.iter().map(|(offset, (path, _))| (offset, path)).collect();
+        paths.sort();
+        let mut imported: Vec<&String> = Vec::new();
+        for (_, path) in paths {
+            if *path != module.path && !imported.contains(&path) {
+                imported.push(path);
+                let from = relative_file(&module.path, path);
+                self.line(&format!("import * as {} from \"{}\";", module_variable(path), from));
+            }
+        }
+    }
+
+    //The root of a bundle is not run in a function, the items other modules name by a path are
+    //gathered for them like the ones a function returns
+    fn named_items(&mut self) {
+        let Some((module, Output::Bundle)) = self.module else {
+            return;
+        };
+        if !module.path.is_empty() || module.named.is_empty() {
+            return;
+        }
+        let mut named: Vec<String> = module.named.iter().map(|name| js_name(name)).collect();
+        named.sort();
+        self.line(&format!("const $crate = {{ {} }};", named.join(", ")));
+    }
+
+    //An item of another module named by the modules it is in, `util::twice`, or of this one, `crate::twice`
+    fn module_item(&self, path: &str, name: &Token) -> String {
+        match self.module {
+            Some((module, _)) if module.path == path => self.global(&name.as_string()),
+            _ => format!("{}.{}", module_variable(path), js_name(&name.as_string())),
+        }
+    }
+
+    //An extern block with `#[js_module = ".."]` imports the functions it declares, in a bundle with
+    //`require`, as a script cannot import. A function called `promises.readFile` imports `promises`
+    fn extern_imports(&mut self) {
+        let output = self.module.map_or(Output::Bundle, |(_, output)| output);
+        for (from, names) in std::mem::take(&mut self.extern_modules) {
+            let names: Vec<String> = names
+                .iter()
+                .map(|(imported, local)| match output {
+                    _ if imported == local => imported.clone(),
+                    Output::Bundle => format!("{imported}: {local}"),
+                    Output::EsModules => format!("{imported} as {local}"),
+                })
+                .collect();
+            let (names, from) = (names.join(", "), js_string(&from));
+            match output {
+                Output::Bundle => self.line(&format!("const {{ {names} }} = require({from});")),
+                Output::EsModules => self.line(&format!("import {{ {names} }} from {from};")),
+            }
+        }
+    }
+
+    //Calls to an extern function are calls to what Javascript calls it. The first part of that name is
+    //imported once for all the blocks that import it from the same module, and gets a new name when the
+    //program or the compiled code already uses it. A global the program hides with an item of its own is
+    //found on `globalThis`.
+    //Either way a `let` of the name gets a new one, so the function can still be called
+    fn bind_externs(&mut self, program: &Program) {
+        let mut imported: HashMap<(String, String), String> = HashMap::new();
+        for stmt in &program.body {
+            let Stmt::Item(Item::Extern(decl)) = stmt else {
+                continue;
+            };
+            let from = decl.attributes.iter().find_map(|attribute| attribute.string());
+            for function in &decl.functions {
+                let js_name = extern_name(function);
+                let (first, rest) = js_name.split_once('.').map_or((js_name.as_str(), ""), |(first, _)| {
+                    (first, &js_name[first.len()..])
+                });
+                let taken = self.scopes[0].values().any(|name| name == first);
+                let local = match from {
+                    Some(from) => {
+                        let key = (from.to_string(), first.to_string());
+                        match imported.get(&key) {
+                            Some(local) => local.clone(),
+                            None => {
+                                let local = if taken || JS_GLOBALS.contains(&first) {
+                                    self.renamed += 1;
+                                    format!("{first}${}", self.renamed)
+                                } else {
+                                    first.to_string()
+                                };
+                                match self.extern_modules.iter_mut().find(|(module, _)| module == from) {
+                                    Some((_, names)) => names.push((first.to_string(), local.clone())),
+                                    None => self.extern_modules.push((from.to_string(), vec![(first.to_string(), local.clone())])),
+                                }
+                                imported.insert(key, local.clone());
+                                local
+                            }
+                        }
+                    }
+                    None if taken => format!("globalThis.{first}"),
+                    None => first.to_string(),
+                };
+                self.scopes[0].entry(first.to_string()).or_insert_with(|| local.clone());
+                self.scopes[0].insert(function.name.as_string(), format!("{local}{rest}"));
+            }
+        }
+    }
+
+    //Only the root module of a program has an entry point, a library is run by what imports it
+    fn has_entry_point(&self) -> bool {
+        !self.library && self.module.is_none_or(|(module, _)| module.path.is_empty())
+    }
+
+    //`export ` in front of a public top level item of an ES module, the root's can be imported from Javascript.
+    //A private item that modules inside this one import is exported too
+    fn export(&self, public: bool, name: &Token) -> &'static str {
+        let top_level = self.scopes.len() == 1;
+        match self.module {
+            Some((_, Output::EsModules)) if (public || self.imported_elsewhere(name)) && top_level => "export ",
+            _ => "",
+        }
+    }
+
+    fn imported_elsewhere(&self, name: &Token) -> bool {
+        self.module.is_some_and(|(module, _)| module.imported.contains(&name.as_string()))
+    }
+
+    //Option and Result are emitted like user defined enums, and only when the program uses them. An ES
+    //module exports them, so the Javascript that calls its functions can build their arguments
+    fn runtime(&mut self) {
+        if self.types.prelude {
+            let export = match self.module {
+                Some((_, Output::EsModules)) => "export ",
+                _ => "",
+            };
+            self.enum_object(export, "Option", &[("Some".to_string(), true), ("None".to_string(), false)]);
+            self.enum_object(export, "Result", &[("Ok".to_string(), true), ("Err".to_string(), true)]);
+        }
+        if !self.types.propagates.is_empty() {
+            self.code += PROPAGATE;
+        }
+        if !self.types.structural_comparisons.is_empty() {
+            self.code += EQUALS;
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        self.code += "\t".repeat(self.tabs).as_str();
+        self.code += text;
+        self.code += "\n";
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Item(item) => self.item(item),
+            Stmt::Let { pattern: Pattern::Wildcard(_), initializer, .. } => {
+                let value = self.expr(initializer);
+                self.line(&format!("{value};"));
+            }
+            Stmt::Let { pattern, initializer, .. } => {
+                //the value is emitted first, so it still refers to any name the pattern shadows
+                let value = self.expr(initializer);
+                let target = self.destructure(pattern);
+                let keyword = if binds_mutably(pattern) { "let" } else { "const" };
+                self.line(&format!("{keyword} {target} = {value};"));
+            }
+            Stmt::Expression(Expr::Match { scrutinee, arms, .. }) | Stmt::Tail(Expr::Match { scrutinee, arms, .. }) => {
+                self.line("{");
+                self.tabs += 1;
+                self.match_arms(scrutinee, arms, Tail::Discard);
+                self.tabs -= 1;
+                self.line("}");
+            }
+            Stmt::Expression(expr) | Stmt::Tail(expr) => {
+                let value = self.expr(expr);
+                self.line(&format!("{value};"));
+            }
+            Stmt::Print { format, args, .. } => {
+                let value = self.template(format, args);
+                self.line(&format!("console.log({value});"));
+            }
+            Stmt::Block(statements) => {
+                self.line("{");
+                self.body(statements);
+                self.line("}");
+            }
+            Stmt::If { .. } => {
+                self.if_chain(stmt, "if", Tail::Discard);
+                self.line("}");
+            }
+            //the statements a match in the condition needs run before each test
+            Stmt::While { condition, body } if hoists(condition) => {
+                self.line("while (true) {");
+                self.tabs += 1;
+                let condition = self.expr(condition);
+                self.line(&format!("if (!({condition})) {{"));
+                self.line("\tbreak;");
+                self.line("}");
+                self.tabs -= 1;
+                self.body(body);
+                self.line("}");
+            }
+            Stmt::While { condition, body } => {
+                let condition = self.expr(condition);
+                self.line(&format!("while ({condition}) {{"));
+                self.body(body);
+                self.line("}");
+            }
+            Stmt::For { variable, iterable, body } => {
+                self.scopes.push(HashMap::new());
+                let variable = self.declare(&variable.as_string());
+                let header = self.for_header(&variable, iterable);
+                self.line(&format!("for ({header}) {{"));
+                self.body(body);
+                self.line("}");
+                self.scopes.pop();
+            }
+            Stmt::Return { value, .. } => match value {
+                Some(value) => {
+                    let value = self.expr(value);
+                    self.line(&format!("return {value};"));
+                }
+                None => self.line("return;"),
+            },
+            Stmt::Break(_) => self.line("break;"),
+        }
+    }
+
+    //Emits `if (..) {` and its body, then any `} else if (..) {` / `} else {` that follow.
+    //The caller closes the final brace
+    fn if_chain(&mut self, stmt: &Stmt, keyword: &str, tail: Tail) {
+        match stmt {
+            //the statements a match in the condition needs cannot go between `}` and `else if`
+            Stmt::If { condition, .. } if keyword != "if" && hoists(condition) => {
+                self.line("} else {");
+                self.tabs += 1;
+                self.if_chain(stmt, "if", tail);
+                self.line("}");
+                self.tabs -= 1;
+            }
+            Stmt::If { condition, then_branch, else_branch } => {
+                let condition = self.expr(condition);
+                self.line(&format!("{keyword} ({condition}) {{"));
+                self.branch(then_branch, tail);
+                if let Some(else_branch) = else_branch {
+                    self.if_chain(else_branch, "} else if", tail);
+                }
+            }
+            Stmt::Block(statements) => {
+                self.line("} else {");
+                self.branch(statements, tail);
+            }
+            _ => unreachable!("else branch must be an if or a block"),
+        }
+    }
+
+    //A range lowers to a counting loop, the end bound is only evaluated once as in Rust
+    fn for_header(&mut self, variable: &str, iterable: &Expr) -> String {
+        match iterable {
+            Expr::Range { start, end, inclusive } => {
+                let start = self.expr(start);
+                let compare = if *inclusive { "<=" } else { "<" };
+                //the loop can assign to a variable bound, so only a literal is read where it is
+                let bound = match end.as_ref() {
+                    Expr::Literal { .. } => self.expr(end),
+                    _ => {
+                        let end = self.expr(end);
+                        return format!("let {variable} = {start}, $end = {end}; {variable} {compare} $end; {variable}++");
+                    }
+                };
+                format!("let {variable} = {start}; {variable} {compare} {bound}; {variable}++")
+            }
+            _ => {
+                let iterable = self.expr(iterable);
+                format!("const {variable} of {iterable}")
+            }
+        }
+    }
+
+    fn body(&mut self, statements: &[Stmt]) {
+        self.tabs += 1;
+        self.scopes.push(HashMap::new());
+        for stmt in statements {
+            self.stmt(stmt);
+        }
+        self.scopes.pop();
+        self.tabs -= 1;
+    }
+
+    //The body of a function or branch whose value is used, its last statement becomes a return or
+    //an assignment
+    fn returning_body(&mut self, statements: &[Stmt], tail: Tail) {
+        self.tabs += 1;
+        self.scopes.push(HashMap::new());
+        self.returning_stmts(statements, tail);
+        self.scopes.pop();
+        self.tabs -= 1;
+    }
+
+    //Rust lets `let` re-bind a name in the same scope but Javascript does not, and a Javascript
+    //`const` shadows the outer name in the whole block, even in its own initializer and before it.
+    //So a name any scope already has gets a fresh Javascript name. `$` cannot appear in Rustscript names
+    fn declare(&mut self, name: &str) -> String {
+        let renamed = if self.scopes.iter().any(|scope| scope.contains_key(name)) {
+            self.renamed += 1;
+            format!("{name}${}", self.renamed)
+        } else {
+            js_name(name)
+        };
+        let scope = self.scopes.last_mut().expect("there is always a global scope");
+        scope.insert(name.to_string(), renamed.clone());
+        renamed
+    }
+
+    //A fresh name for a value the emitted code holds on to
+    fn temporary(&mut self) -> String {
+        self.renamed += 1;
+        format!("$value{}", self.renamed)
+    }
+
+    //Operands run left to right. When a later one runs statements first, for a match in it, the
+    //earlier ones are kept in temporaries so they are still evaluated before those statements
+    fn operands(&mut self, exprs: &[&Expr]) -> Vec<String> {
+        let last = exprs.iter().rposition(|expr| hoists(expr));
+        let mut values = Vec::new();
+        for (index, expr) in exprs.iter().enumerate() {
+            let value = self.expr(expr);
+            let settled =
+                matches!(expr, Expr::Literal { .. } | Expr::Path(_) | Expr::SelfValue(_) | Expr::Closure { .. });
+            if last.is_some_and(|last| index < last) && !settled {
+                let temporary = self.temporary();
+                self.line(&format!("const {temporary} = {value};"));
+                values.push(temporary);
+            } else {
+                values.push(value);
+            }
+        }
+        values
+    }
+
+    fn resolve(&self, name: &str) -> String {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name))
+            .cloned()
+            .unwrap_or_else(|| js_name(name))
+    }
+
+    //A struct, enum or trait by name, which a `let` of the same name does not hide
+    fn global(&self, name: &str) -> String {
+        self.scopes[0].get(name).cloned().unwrap_or_else(|| js_name(name))
+    }
+
+    fn branch(&mut self, statements: &[Stmt], tail: Tail) {
+        match tail {
+            Tail::Discard => self.body(statements),
+            _ => self.returning_body(statements, tail),
+        }
+    }
+
+    fn returning_stmts(&mut self, statements: &[Stmt], tail: Tail) {
+        let Some((last, rest)) = statements.split_last() else {
+            return;
+        };
+        for stmt in rest {
+            self.stmt(stmt);
+        }
+        self.tail(last, tail);
+    }
+
+    //A statement in tail position. The value of a tail expression is returned or assigned, and
+    //ifs, blocks and matches pass the tail position on to each of their branches
+    fn tail(&mut self, stmt: &Stmt, tail: Tail) {
+        match stmt {
+            Stmt::Tail(Expr::Match { scrutinee, arms, .. }) => self.match_arms(scrutinee, arms, tail),
+            Stmt::Tail(expr) => {
+                let value = self.expr(expr);
+                self.finish(&value, tail);
+            }
+            Stmt::If { .. } => {
+                self.if_chain(stmt, "if", tail);
+                self.line("}");
+            }
+            Stmt::Block(statements) => {
+                self.line("{");
+                self.returning_body(statements, tail);
+                self.line("}");
+            }
+            _ => self.stmt(stmt),
+        }
+    }
+
+    fn finish(&mut self, value: &str, tail: Tail) {
+        match tail {
+            Tail::Discard => self.line(&format!("{value};")),
+            Tail::Return => self.line(&format!("return {value};")),
+            Tail::Assign(temporary) => self.line(&format!("{temporary} = {value};")),
+        }
+    }
+
+    fn item(&mut self, item: &Item) {
+        match item {
+            Item::Fn(decl) => {
+                // main is the entry point, so it is invoked as soon as it is defined
+                let is_main = decl.name.is_named("main") && self.has_entry_point();
+                let signature = self.signature(&js_name(&decl.name.as_string()), decl);
+                if is_main {
+                    self.line(&format!("(function {signature} {{"));
+                } else {
+                    let export = self.export(decl.public, &decl.name);
+                    self.line(&format!("{export}function {signature} {{"));
+                }
+                self.function_body(decl);
+                if is_main {
+                    self.line("})();");
+                } else {
+                    self.line("}");
+                }
+            }
+            Item::Struct(decl) => {
+                // a struct and all of its impl blocks become one class
+                let name = decl.name.as_string();
+                let class = js_name(&name);
+                let fields: Vec<String> = decl.fields.iter().map(|f| f.name.as_string()).collect();
+                let params: Vec<String> = fields.iter().map(|field| js_name(field)).collect();
+                let export = self.export(decl.public, &decl.name);
+                self.line(&format!("{export}class {class} {{"));
+                self.tabs += 1;
+                self.line(&format!("constructor({}) {{", params.join(", ")));
+                self.tabs += 1;
+                for (field, param) in fields.iter().zip(&params) {
+                    self.line(&format!("this.{} = {param};", member_name(field)));
+                }
+                self.tabs -= 1;
+                self.line("}");
+
+                self.self_type = Some(name.clone());
+                let methods = self.impls.get(&name).cloned().unwrap_or_default();
+                for method in methods {
+                    let signature = self.signature(&member_name(&method.name.as_string()), method);
+                    if method.self_param.is_some() {
+                        self.line(&format!("{signature} {{"));
+                    } else {
+                        self.line(&format!("static {signature} {{"));
+                    }
+                    self.function_body(method);
+                    self.line("}");
+                }
+                self.self_type = None;
+
+                self.tabs -= 1;
+                self.line("}");
+
+                //the provided methods of its traits that its impl blocks leave out
+                for trait_name in self.trait_impls.get(&name).cloned().unwrap_or_default() {
+                    let provided = self.types.provided.get(&trait_name.offset).cloned().unwrap_or_default();
+                    for (method, takes_self) in provided {
+                        let method = member_name(&method);
+                        let target = if takes_self { format!("{class}.prototype") } else { class.clone() };
+                        self.line(&format!("{target}.{method} = {}.{method};", self.global(&trait_name.as_string())));
+                    }
+                }
+            }
+            // emitted as part of the struct's class
+            Item::Impl(_) => {}
+            // an object of the provided methods, the structs that implement the trait copy them
+            Item::Trait(decl) => {
+                let export = self.export(decl.public, &decl.name);
+                self.line(&format!("{export}const {} = {{", js_name(&decl.name.as_string())));
+                self.tabs += 1;
+                for method in &decl.provided {
+                    let signature = self.signature(&member_name(&method.name.as_string()), method);
+                    self.line(&format!("{signature} {{"));
+                    self.function_body(method);
+                    self.line("},");
+                }
+                self.tabs -= 1;
+                self.line("};");
+            }
+            Item::Enum(decl) => {
+                let variants: Vec<(String, bool)> =
+                    decl.variants.iter().map(|v| (v.name.as_string(), !v.fields.is_empty())).collect();
+                let export = self.export(decl.public, &decl.name);
+                self.enum_object(export, &js_name(&decl.name.as_string()), &variants);
+            }
+            // imports are emitted first, and a module's items are emitted with it
+            Item::Mod(_) | Item::Use(_) => {}
+            // Javascript already has the functions
+            Item::Extern(_) => {}
+        }
+    }
+
+    //C-like variants are frozen singletons, so they can be compared with ===. Variants with a
+    //payload are constructors for tagged { tag, values } objects
+    fn enum_object(&mut self, export: &str, name: &str, variants: &[(String, bool)]) {
+        self.line(&format!("{export}const {name} = Object.freeze({{"));
+        self.tabs += 1;
+        for (tag, has_payload) in variants {
+            let key = member_name(tag);
+            if *has_payload {
+                self.line(&format!("{key}: (...values) => Object.freeze({{ tag: \"{tag}\", values }}),"));
+            } else {
+                self.line(&format!("{key}: Object.freeze({{ tag: \"{tag}\" }}),"));
+            }
+        }
+        self.tabs -= 1;
+        self.line("});");
+    }
+
+    //Only functions with a declared return type hand back the value of their tail expression
+    fn function_body(&mut self, decl: &FnDecl) {
+        //parameters share a scope with the body, a `let` of the same name re-binds them
+        self.tabs += 1;
+        self.scopes.push(HashMap::new());
+        //parameters are called what the signature calls them
+        let scope = self.scopes.last_mut().expect("the function's scope was just pushed");
+        for param in &decl.params {
+            scope.insert(param.name.as_string(), js_name(&param.name.as_string()));
+        }
+        let propagates = self.types.propagates.contains(&decl.name.offset);
+        self.catch_propagated(propagates, |gen| {
+            if decl.return_type.is_some() {
+                gen.returning_stmts(&decl.body, Tail::Return);
+            } else {
+                for stmt in &decl.body {
+                    gen.stmt(stmt);
+                }
+            }
+        });
+        self.scopes.pop();
+        self.tabs -= 1;
+    }
+
+    //The body of a function that uses `?` runs in a try block, a `None` or `Err` thrown by `$try`
+    //is caught and returned
+    fn catch_propagated(&mut self, propagates: bool, emit: impl FnOnce(&mut CodeGen)) {
+        if !propagates {
+            emit(self);
+            return;
+        }
+        self.line("try {");
+        self.tabs += 1;
+        emit(self);
+        self.tabs -= 1;
+        self.line("} catch ($e) {");
+        self.tabs += 1;
+        self.line("if ($e instanceof $Propagate) {");
+        self.tabs += 1;
+        self.line("return $e.residual;");
+        self.tabs -= 1;
+        self.line("}");
+        self.line("throw $e;");
+        self.tabs -= 1;
+        self.line("}");
+    }
+
+    //A method is a property, so it keeps its name whatever it is, a function is given its Javascript name
+    fn signature(&self, name: &str, decl: &FnDecl) -> String {
+        // `self` is `this` in Javascript, so it is never passed as a parameter
+        let params: Vec<String> = decl.params.iter().map(|p| js_name(&p.name.as_string())).collect();
+        format!("{}({})", name, params.join(", "))
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::Literal { value, token } => match value {
+                Object::Str(s) => js_string(s),
+                //the source text keeps the digits of 64 bit integers that a float cannot hold
+                Object::Num(_) if self.types.bigints.contains(&token.offset) => format!("{}n", token.as_string()),
+                _ => value.to_string(),
+            },
+            Expr::Variable(name) => self.resolve(&name.as_string()),
+            Expr::SelfValue(_) => "this".to_string(),
+            Expr::StructLiteral { name, fields } => {
+                let name = match (&self.self_type, name.is(TokenType::SelfType)) {
+                    (Some(self_type), true) => self_type.clone(),
+                    _ => name.as_string(),
+                };
+                let class = self.global(&name);
+                let order = self
+                    .struct_fields
+                    .get(&name)
+                    .cloned()
+                    .unwrap_or_else(|| fields.iter().map(|(field, _)| field.as_string()).collect());
+
+                let values: Vec<&Expr> = fields.iter().map(|(_, value)| value).collect();
+                let values = self.operands(&values);
+                let args: Vec<String> = order
+                    .iter()
+                    .map(|field| match fields.iter().position(|(f, _)| f.is_named(field)) {
+                        Some(index) => values[index].clone(),
+                        None => "undefined".to_string(),
+                    })
+                    .collect();
+                format!("new {}({})", class, args.join(", "))
+            }
+            Expr::Get { object, name } => {
+                let object = self.expr(object);
+                format!("{}{}", object, field_access(name))
+            }
+            Expr::Set { object, name, value } => {
+                let operands = self.operands(&[object, value]);
+                format!("{}{} = {}", operands[0], field_access(name), operands[1])
+            }
+            Expr::Path(segments) => {
+                if let Some((path, named)) = self.module.and_then(|(module, _)| module.paths.get(&segments[0].offset)) {
+                    return self.module_item(path, &segments[*named]);
+                }
+                //`Option::Some` is the program's own enum when it declares or imports one called `Option`
+                let declared = segments.len() > 1 && self.scopes[0].contains_key(&segments[0].as_string());
+                if let Some((enum_name, variant)) = prelude_variant(segments).filter(|_| !declared) {
+                    return format!("{enum_name}.{}", variant.as_string());
+                }
+                let members = segments[1..].iter().map(|segment| format!(".{}", member_name(&segment.as_string())));
+                self.global(&segments[0].as_string()) + &members.collect::<String>()
+            }
+            Expr::Assign { name, value } => {
+                let value = self.expr(value);
+                format!("{} = {}", self.resolve(&name.as_string()), value)
+            }
+            //the right operand, and the statements it needs, only run when the left does not decide the result
+            Expr::Binary { left, operator, right }
+                if matches!(operator.token_type(), TokenType::And | TokenType::Or) && hoists(right) =>
+            {
+                let left = self.expr(left);
+                let temporary = self.temporary();
+                self.line(&format!("let {temporary} = {left};"));
+                let test = match operator.token_type() {
+                    TokenType::And => temporary.clone(),
+                    _ => format!("!{temporary}"),
+                };
+                self.line(&format!("if ({test}) {{"));
+                self.tabs += 1;
+                let right = self.expr(right);
+                self.line(&format!("{temporary} = {right};"));
+                self.tabs -= 1;
+                self.line("}");
+                temporary
+            }
+            Expr::Binary { left, operator, right } => {
+                let operands = self.operands(&[left, right]);
+                let (left, right) = (&operands[0], &operands[1]);
+                //Javascript numbers are doubles, `| 0` wraps a sum back to 32 bits and `Math.imul`
+                //multiplies without losing the low bits a double cannot hold
+                if self.types.int_arithmetic.contains(&operator.offset) {
+                    return match operator.token_type() {
+                        TokenType::Star => format!("Math.imul({left}, {right})"),
+                        TokenType::Slash => format!("Math.trunc({left} / {right})"),
+                        operator => format!("({left} {} {right} | 0)", js_operator(operator)),
+                    };
+                }
+                if self.types.structural_comparisons.contains(&operator.offset) {
+                    let negation = if operator.is(TokenType::BangEqual) { "!" } else { "" };
+                    return format!("{negation}$equals({left}, {right})");
+                }
+                format!("{} {} {}", left, js_operator(operator.token_type()), right)
+            }
+            Expr::Unary { operator, right } => {
+                let right = self.expr(right);
+                //`- -x` would read as a decrement
+                if operator.is(TokenType::Minus) && right.starts_with('-') {
+                    return format!("-({right})");
+                }
+                format!("{}{}", js_operator(operator.token_type()), right)
+            }
+            Expr::Cast { value, keyword, .. } => {
+                let value = self.expr(value);
+                match self.types.casts.get(&keyword.offset) {
+                    Some(Cast::Truncate) => format!("Math.trunc({value})"),
+                    Some(Cast::ToBigInt) => format!("BigInt({value})"),
+                    Some(Cast::TruncateToBigInt) => format!("BigInt(Math.trunc({value}))"),
+                    Some(Cast::WrapToI32) => format!("Number(BigInt.asIntN(32, {value}))"),
+                    Some(Cast::ToNumber) => format!("Number({value})"),
+                    None => value,
+                }
+            }
+            Expr::Grouping(expr) => {
+                let wrapped = matches!(&**expr, Expr::Binary { operator, .. }
+                    if self.types.int_arithmetic.contains(&operator.offset));
+                let expr = self.expr(expr);
+                //`i32` arithmetic is already emitted as one term
+                match wrapped {
+                    true => expr,
+                    false => format!("({expr})"),
+                }
+            }
+            Expr::Tuple { elements, .. } | Expr::Array { elements, .. } => {
+                let elements = self.operands(&elements.iter().collect::<Vec<_>>());
+                format!("[{}]", elements.join(", "))
+            }
+            Expr::Index { object, index, .. } => {
+                let operands = self.operands(&[object, index]);
+                format!("{}[{}]", operands[0], operands[1])
+            }
+            Expr::SetIndex { object, index, value, .. } => {
+                let operands = self.operands(&[object, index, value]);
+                format!("{}[{}] = {}", operands[0], operands[1], operands[2])
+            }
+            Expr::Match { scrutinee, arms, .. } => {
+                // a match used as a value runs before the statement it is in and leaves its value in a
+                // temporary, so an arm can still return from the function or break out of a loop
+                let temporary = self.temporary();
+                self.line(&format!("let {temporary};"));
+                self.line("{");
+                self.tabs += 1;
+                self.match_arms(scrutinee, arms, Tail::Assign(&temporary));
+                self.tabs -= 1;
+                self.line("}");
+                temporary
+            }
+            Expr::Range { .. } => unreachable!("a range is only parsed as the iterable of a for loop"),
+            Expr::Call { callee, args, .. } => {
+                match callee.as_ref() {
+                    Expr::Path(segments) if is_vec_new(segments) => return "[]".to_string(),
+                    Expr::Get { object, name } => {
+                        if let Some(builtin) = self.types.builtins.get(&name.offset) {
+                            return self.builtin(*builtin, object, args);
+                        }
+                    }
+                    _ => {}
+                }
+                //a method is called on the value its object evaluates to, whatever its arguments do
+                if let Expr::Get { object, name } = callee.as_ref() {
+                    let mut operands = self.operands(&std::iter::once(object.as_ref()).chain(args).collect::<Vec<_>>());
+                    let object = operands.remove(0);
+                    return format!("{}{}({})", object, field_access(name), operands.join(", "));
+                }
+                let callee = self.expr(callee);
+                let args = self.operands(&args.iter().collect::<Vec<_>>());
+                format!("{}({})", callee, args.join(", "))
+            }
+            Expr::Try { value, .. } => {
+                let value = self.expr(value);
+                format!("$try({value})")
+            }
+            Expr::Closure { pipe, params, body, .. } => {
+                //arrow functions keep the `this` of the method they are created in, as Rust closures keep `self`
+                self.scopes.push(HashMap::new());
+                let params: Vec<String> = params.iter().map(|p| self.declare(&p.name.as_string())).collect();
+                let propagates = self.types.propagates.contains(&pipe.offset);
+                let body = match body.as_ref() {
+                    ArmBody::Expr(expr) if !propagates && !hoists(expr) => self.expr(expr),
+                    body => {
+                        let body = self.nested(|gen| {
+                            gen.catch_propagated(propagates, |gen| match body {
+                                ArmBody::Expr(expr) => {
+                                    let value = gen.expr(expr);
+                                    gen.line(&format!("return {value};"));
+                                }
+                                ArmBody::Block(statements) => gen.returning_stmts(statements, Tail::Return),
+                            })
+                        });
+                        format!("{{\n{}{}}}", body, "\t".repeat(self.tabs))
+                    }
+                };
+                self.scopes.pop();
+                format!("({}) => {}", params.join(", "), body)
+            }
+        }
+    }
+
+    fn builtin(&mut self, builtin: Builtin, object: &Expr, args: &[Expr]) -> String {
+        let mut args = self.operands(&std::iter::once(object).chain(args).collect::<Vec<_>>());
+        let object = args.remove(0);
+        match builtin {
+            Builtin::Len => format!("{object}.length"),
+            Builtin::Push => format!("{object}.push({})", args.join(", ")),
+        }
+    }
+
+    //Emit statements into a separate buffer one level deeper, for code nested inside an expression
+    fn nested(&mut self, emit: impl FnOnce(&mut CodeGen)) -> String {
+        let outer = std::mem::take(&mut self.code);
+        self.tabs += 1;
+        emit(self);
+        self.tabs -= 1;
+        std::mem::replace(&mut self.code, outer)
+    }
+
+    //A match lowers to an if chain over a temporary holding the matched value. Each arm tests
+    //its pattern, binds the names it introduces and runs its body, returning the value when asked
+    fn match_arms(&mut self, scrutinee: &Expr, arms: &[MatchArm], tail: Tail) {
+        let value = self.expr(scrutinee);
+        self.line(&format!("const $match = {value};"));
+
+        for (index, arm) in arms.iter().enumerate() {
+            let mut tests = Vec::new();
+            let mut bindings = Vec::new();
+            self.pattern(&arm.pattern, "$match", &mut tests, &mut bindings);
+
+            let condition = if tests.is_empty() { "true".to_string() } else { tests.join(" && ") };
+            let keyword = if index == 0 { "if" } else { "} else if" };
+            self.line(&format!("{keyword} ({condition}) {{"));
+            self.tabs += 1;
+            self.scopes.push(HashMap::new());
+            for (name, access) in bindings {
+                let name = self.declare(&name);
+                self.line(&format!("const {name} = {access};"));
+            }
+            match &arm.body {
+                ArmBody::Expr(expr) => {
+                    let value = self.expr(expr);
+                    self.finish(&value, tail);
+                }
+                ArmBody::Block(statements) => match tail {
+                    Tail::Discard => {
+                        for stmt in statements {
+                            self.stmt(stmt);
+                        }
+                    }
+                    _ => self.returning_stmts(statements, tail),
+                },
+            }
+            self.scopes.pop();
+            self.tabs -= 1;
+        }
+        if !arms.is_empty() {
+            self.line("}");
+        }
+    }
+
+    //Collect the checks a value must pass to match a pattern, and the names it binds
+    fn pattern(&mut self, pattern: &Pattern, value: &str, tests: &mut Vec<String>, bindings: &mut Vec<(String, String)>) {
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding { name, .. } => bindings.push((name.as_string(), value.to_string())),
+            Pattern::Literal { value: literal, token } => {
+                let literal = match literal {
+                    Object::Str(s) => js_string(s),
+                    Object::Num(n) if self.types.bigints.contains(&token.offset) => {
+                        let sign = if *n < 0.0 { "-" } else { "" };
+                        format!("{sign}{}n", token.as_string())
+                    }
+                    _ => literal.to_string(),
+                };
+                tests.push(format!("{value} === {literal}"));
+            }
+            Pattern::Variant { path, fields } => {
+                let tag = path.last().expect("paths have at least one segment").as_string();
+                tests.push(format!("{value}.tag === \"{tag}\""));
+                for (index, field) in fields.iter().enumerate() {
+                    self.pattern(field, &format!("{value}.values[{index}]"), tests, bindings);
+                }
+            }
+            Pattern::Tuple { elements, .. } => {
+                for (index, element) in elements.iter().enumerate() {
+                    self.pattern(element, &format!("{value}[{index}]"), tests, bindings);
+                }
+            }
+            Pattern::Struct { fields, .. } => {
+                for (field, pattern) in fields {
+                    self.pattern(pattern, &format!("{value}.{}", member_name(&field.as_string())), tests, bindings);
+                }
+            }
+        }
+    }
+
+    //The target of a destructuring declaration: tuples become array patterns and structs object
+    //patterns, wildcards are left as holes
+    fn destructure(&mut self, pattern: &Pattern) -> String {
+        match pattern {
+            Pattern::Binding { name, .. } => self.declare(&name.as_string()),
+            Pattern::Wildcard(_) => String::new(),
+            Pattern::Tuple { elements, .. } => {
+                let mut elements: Vec<String> = elements.iter().map(|e| self.destructure(e)).collect();
+                while elements.last().is_some_and(|e| e.is_empty()) {
+                    elements.pop();
+                }
+                format!("[{}]", elements.join(", "))
+            }
+            Pattern::Struct { fields, .. } => {
+                let mut targets = Vec::new();
+                for (field, pattern) in fields {
+                    let field = member_name(&field.as_string());
+                    match self.destructure(pattern) {
+                        target if target.is_empty() => {}
+                        target if target == field => targets.push(target),
+                        target => targets.push(format!("{field}: {target}")),
+                    }
+                }
+                if targets.is_empty() {
+                    "{}".to_string()
+                } else {
+                    format!("{{ {} }}", targets.join(", "))
+                }
+            }
+            Pattern::Literal { .. } | Pattern::Variant { .. } => {
+                unreachable!("refutable patterns in a let are rejected by the typechecker")
+            }
+        }
+    }
+
+    //println format strings become template literals, each {} is replaced by the next argument
+    fn template(&mut self, format: &str, args: &[Expr]) -> String {
+        let mut out = String::from("`");
+        let mut values = self.operands(&args.iter().collect::<Vec<_>>()).into_iter();
+        let mut pieces = format.split("{}").peekable();
+        while let Some(piece) = pieces.next() {
+            out += &escape_template(piece);
+            if pieces.peek().is_some() {
+                match values.next() {
+                    Some(value) => out += &format!("${{{value}}}"),
+                    None => out += "{}",
+                }
+            }
+        }
+        out.push('`');
+        out
+    }
+}
+
+//`value?` lowers to `$try(value)`, which unwraps a `Some` or `Ok` and throws anything else for the
+//enclosing function to return
+const PROPAGATE: &str = "class $Propagate {
+\tconstructor(residual) {
+\t\tthis.residual = residual;
+\t}
+}
+function $try(value) {
+\tif (value.tag === \"None\" || value.tag === \"Err\") {
+\t\tthrow new $Propagate(value);
+\t}
+\treturn value.values[0];
+}
+";
+
+//Arrays, struct objects and enum values are equal when they are of the same kind and their fields are
+const EQUALS: &str = "function $equals(a, b) {
+\tif (a === b) {
+\t\treturn true;
+\t}
+\tif (typeof a !== \"object\" || typeof b !== \"object\" || Object.getPrototypeOf(a) !== Object.getPrototypeOf(b)) {
+\t\treturn false;
+\t}
+\tconst keys = Object.keys(a);
+\treturn keys.length === Object.keys(b).length && keys.every((key) => $equals(a[key], b[key]));
+}
+";
+
+//Whether emitting an expression emits statements before it, for a match in it used as a value. A
+//closure's body is emitted inside the closure
+fn hoists(expr: &Expr) -> bool {
+    match expr {
+        Expr::Match { .. } => true,
+        Expr::Literal { .. } | Expr::Variable(_) | Expr::Path(_) | Expr::SelfValue(_) | Expr::Closure { .. } => false,
+        Expr::StructLiteral { fields, .. } => fields.iter().any(|(_, value)| hoists(value)),
+        Expr::Get { object, .. } => hoists(object),
+        Expr::Set { object, value, .. } => hoists(object) || hoists(value),
+        Expr::Assign { value, .. } | Expr::Cast { value, .. } | Expr::Try { value, .. } => hoists(value),
+        Expr::Binary { left, right, .. } => hoists(left) || hoists(right),
+        Expr::Unary { right, .. } => hoists(right),
+        Expr::Grouping(expr) => hoists(expr),
+        Expr::Tuple { elements, .. } | Expr::Array { elements, .. } => elements.iter().any(hoists),
+        Expr::Index { object, index, .. } => hoists(object) || hoists(index),
+        Expr::SetIndex { object, index, value, .. } => hoists(object) || hoists(index) || hoists(value),
+        Expr::Range { .. } => unreachable!("a range is only parsed as the iterable of a for loop"),
+        Expr::Call { callee, args, .. } => hoists(callee) || args.iter().any(hoists),
+    }
+}
+
+//Tuples are arrays, so `t.0` is `t[0]`
+fn field_access(name: &Token) -> String {
+    if name.is(TokenType::Number) {
+        format!("[{}]", name.as_string())
+    } else {
+        format!(".{}", member_name(&name.as_string()))
+    }
+}
+
+//A declaration is `let` rather than `const` when any name it binds is `mut`
+fn binds_mutably(pattern: &Pattern) -> bool {
+    match pattern {
+        Pattern::Binding { mutable, .. } => *mutable,
+        Pattern::Wildcard(_) | Pattern::Literal { .. } => false,
+        Pattern::Variant { fields: elements, .. } | Pattern::Tuple { elements, .. } => {
+            elements.iter().any(binds_mutably)
+        }
+        Pattern::Struct { fields, .. } => fields.iter().any(|(_, pattern)| binds_mutably(pattern)),
+    }
+}
+
+//`#[js_name = "console.error"] fn error(message: str);` is called as `console.error`
+fn extern_name(function: &FnDecl) -> String {
+    match function.attributes.iter().find_map(|attribute| attribute.string()) {
+        Some(js_name) => js_name.to_string(),
+        None => function.name.as_string(),
+    }
+}
+
+fn item_name(stmt: &Stmt) -> Option<&Token> {
+    match stmt {
+        Stmt::Item(Item::Fn(decl)) => Some(&decl.name),
+        Stmt::Item(Item::Struct(StructDecl { name, .. }))
+        | Stmt::Item(Item::Enum(EnumDecl { name, .. }))
+        | Stmt::Item(Item::Trait(TraitDecl { name, .. })) => Some(name),
+        _ => None,
+    }
+}
+
+fn is_main(stmt: &Stmt) -> bool {
+    matches!(stmt, Stmt::Item(Item::Fn(decl)) if decl.name.is_named("main"))
+}
+
+fn public_item(stmt: &Stmt) -> Option<&Token> {
+    match stmt {
+        Stmt::Item(Item::Fn(decl)) if decl.public => Some(&decl.name),
+        Stmt::Item(Item::Struct(StructDecl { public: true, name, .. }))
+        | Stmt::Item(Item::Enum(EnumDecl { public: true, name, .. }))
+        | Stmt::Item(Item::Trait(TraitDecl { public: true, name, .. })) => Some(name),
+        _ => None,
+    }
+}
+
+//The variable a bundled module's items are returned into, `$` keeps it apart from Rustscript names.
+//The root's is `$crate`, which only holds the items other modules name by a path
+fn module_variable(path: &str) -> String {
+    match path {
+        "" => "$crate".to_string(),
+        _ => format!("${}", path.replace("::", "$")),
+    }
+}
+
+/// The file an ES module is emitted to, relative to the output directory.
+pub fn module_file(path: &str) -> String {
+    match path {
+        "" => "index.mjs".to_string(),
+        _ => format!("{}.mjs", path.replace("::", "/")),
+    }
+}
+
+/// The import specifier of module `to` in module `from`.
+pub fn relative_file(from: &str, to: &str) -> String {
+    let from_file = module_file(from);
+    let to_file = module_file(to);
+    let from_dirs: Vec<&str> = from_file.split('/').collect();
+    let from_dirs = &from_dirs[..from_dirs.len() - 1];
+    let to_parts: Vec<&str> = to_file.split('/').collect();
+    let common = from_dirs.iter().zip(&to_parts[..to_parts.len() - 1]).take_while(|(a, b)| a == b).count();
+    let up = match from_dirs.len() - common {
+        0 => "./".to_string(),
+        levels => "../".repeat(levels),
+    };
+    format!("{up}{}", to_parts[common..].join("/"))
+}
+
+//The words Javascript reserves, or that strict mode and modules do not let a binding take
+const JS_KEYWORDS: &[&str] = &[
+    "arguments", "await", "break", "case", "catch", "class", "const", "continue", "debugger", "default", "delete",
+    "do", "else", "enum", "eval", "export", "extends", "false", "finally", "for", "function", "if", "implements",
+    "import", "in", "instanceof", "interface", "let", "new", "null", "package", "private", "protected", "public",
+    "return", "static", "super", "switch", "this", "throw", "true", "try", "typeof", "undefined", "var", "void",
+    "while", "with", "yield",
+];
+
+//The properties classes and objects already have, which a member of the same name would replace
+const JS_MEMBERS: &[&str] = &["constructor", "prototype", "__proto__"];
+
+//The runtime's objects and the Javascript globals the compiled code uses, which no name may hide
+const JS_GLOBALS: &[&str] =
+    &["Option", "Result", "Array", "BigInt", "Math", "Number", "Object", "console", "globalThis", "require"];
+
+/// The name an item, parameter or binding is given in Javascript. A keyword, or a global the
+/// compiled code uses, is followed by a `$`, which cannot appear in Rustscript names.
+pub fn js_name(name: &str) -> String {
+    if JS_KEYWORDS.contains(&name) || JS_GLOBALS.contains(&name) {
+        format!("{name}$")
+    } else {
+        name.to_string()
+    }
+}
+
+/// The property a field, method or enum variant is stored under. A class's `constructor` and
+/// `prototype` and an object's `__proto__` mean something to Javascript, they are followed by a `$`.
+pub fn member_name(name: &str) -> String {
+    if JS_MEMBERS.contains(&name) {
+        format!("{name}$")
+    } else {
+        name.to_string()
+    }
+}
+
+//Rustscript and Javascript operator precedence agree, so only the spelling differs
+fn js_operator(ttype: TokenType) -> &'static str {
+    match ttype {
+        TokenType::Plus => "+",
+        TokenType::Minus => "-",
+        TokenType::Star => "*",
+        TokenType::Slash => "/",
+        TokenType::Bang => "!",
+        TokenType::Equals => "===",
+        TokenType::BangEqual => "!==",
+        TokenType::Less => "<",
+        TokenType::LessEqual => "<=",
+        TokenType::Greater => ">",
+        TokenType::GreaterEqual => ">=",
+        TokenType::And => "&&",
+        TokenType::Or => "||",
+        _ => unreachable!("{:?} is not an operator", ttype),
+    }
+}
+
+fn js_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+fn escape_template(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('`', "\\`")
+        .replace("${", "\\${")
+}
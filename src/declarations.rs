@@ -0,0 +1,442 @@
+use crate::ast::*;
+use crate::codegen::{js_name, member_name, module_file, relative_file, Output};
+use crate::modules::Module;
+use crate::token::Token;
+use crate::typechecker::Items;
+use crate::types::{MethodType, RustScriptType, StructType, TypeParam, Types};
+
+/// Describes the items of a checked `Module` to TypeScript, as a declaration file next to its
+/// Javascript. Public functions are declared with the types the typechecker gave them, structs as
+/// classes, enums as unions of their variants told apart by `tag`, and traits as interfaces with the
+/// object of their provided methods.
+pub struct Declarations<'a> {
+    code: String,
+    tabs: usize,
+    module: &'a Module,
+    output: Output,
+    items: &'a Items,
+    //the types of other modules named so far and the module each is in, imported by an ES module
+    imports: Vec<(String, String)>,
+    //whether the module's Javascript defines the runtime's `Option` and `Result`, which are then declared
+    prelude: bool,
+}
+
+impl<'a> Declarations<'a> {
+    pub fn new(module: &'a Module, output: Output, items: &'a Items, prelude: bool) -> Declarations<'a> {
+        Declarations {
+            code: String::new(),
+            tabs: 0,
+            module,
+            output,
+            items,
+            imports: Vec::new(),
+            prelude,
+        }
+    }
+
+    pub fn generate(mut self) -> String {
+        let program = &self.module.program;
+        //structs, enums and traits are declared whether they are public or not, a public function
+        //may still take or return them
+        for stmt in &program.body {
+            match stmt {
+                Stmt::Item(Item::Fn(decl)) if decl.public => self.function(decl),
+                Stmt::Item(Item::Struct(decl)) => self.class(decl, program),
+                Stmt::Item(Item::Enum(decl)) => self.union(decl),
+                Stmt::Item(Item::Trait(decl)) => self.interface(decl),
+                _ => {}
+            }
+        }
+
+        let mut header = String::new();
+        let mut modules: Vec<&String> = Vec::new();
+        for (module, _) in &self.imports {
+            if !modules.contains(&module) {
+                modules.push(module);
+            }
+        }
+        for module in modules {
+            let names: Vec<&str> =
+                self.imports.iter().filter(|(from, _)| from == module).map(|(_, name)| name.as_str()).collect();
+            let from = relative_file(&self.module.path, module);
+            header += &format!("import type {{ {} }} from \"{}\";\n", names.join(", "), from);
+        }
+        //they are exported so what imports the module can name the values its functions return, and
+        //build them with the runtime's objects
+        if self.prelude {
+            header += self.export(true);
+            header += OPTION;
+            header += self.export(true);
+            header += OPTION_OBJECT;
+            header += self.export(true);
+            header += RESULT;
+            header += self.export(true);
+            header += RESULT_OBJECT;
+        }
+
+        //an ES module's declarations are only exported when they say so
+        if self.output == Output::EsModules {
+            self.line("export {};");
+        }
+        header + &self.code
+    }
+
+    fn line(&mut self, text: &str) {
+        self.code += "\t".repeat(self.tabs).as_str();
+        self.code += text;
+        self.code += "\n";
+    }
+
+    //Only public items of an ES module are exported, a bundle's items are all globals of the script
+    fn export(&self, public: bool) -> &'static str {
+        match self.output {
+            Output::EsModules if public => "export ",
+            _ => "",
+        }
+    }
+
+    //The path of an item declared in the module
+    fn qualify(&self, name: &Token) -> String {
+        match self.module.path.as_str() {
+            "" => name.as_string(),
+            module => format!("{}::{}", module, name.as_string()),
+        }
+    }
+
+    fn function(&mut self, decl: &FnDecl) {
+        let items = self.items;
+        let name = decl.name.as_string();
+        let Some(fn_type) = items.function(&self.module.path, &name) else {
+            return;
+        };
+        let RustScriptType::Function { params, return_type } = &fn_type.name else {
+            return;
+        };
+        //the order they are declared in, the bounds the typechecker resolved
+        let mentioned = fn_type.type_params();
+        let generics: Vec<TypeParam> = decl
+            .generics
+            .iter()
+            .map(|generic| {
+                let name = generic.name.as_string();
+                let bounds = mentioned.iter().find(|param| param.name == name).map(|param| param.bounds.clone());
+                TypeParam { name, bounds: bounds.unwrap_or_default() }
+            })
+            .collect();
+        let generics = self.generics(&generics);
+        let names: Vec<&Token> = decl.params.iter().map(|param| &param.name).collect();
+        let params = self.params(&names, params);
+        let return_type = self.ts_type(return_type);
+        let export = self.export(decl.public);
+        let name = js_name(&name);
+        self.line(&format!("{export}declare function {name}{generics}({params}): {return_type};"));
+    }
+
+    //A struct is a class, constructed with its fields in order, with the public methods of its impl
+    //blocks and the provided methods of its traits
+    fn class(&mut self, decl: &StructDecl, program: &Program) {
+        let items = self.items;
+        let path = self.qualify(&decl.name);
+        let Some(struct_type) = items.struct_type(&path) else {
+            return;
+        };
+        let name = decl.name.as_string();
+        let generics = self.generics(&struct_type.generics);
+        let export = self.export(decl.public);
+        self.line(&format!("{export}declare class {}{generics} {{", js_name(&name)));
+        self.tabs += 1;
+
+        let names: Vec<&Token> = decl.fields.iter().map(|field| &field.name).collect();
+        let types: Vec<Types> = struct_type.fields.iter().map(|(_, ty)| ty.clone()).collect();
+        let params = self.params(&names, &types);
+        self.line(&format!("constructor({params});"));
+        for (field, ty) in &struct_type.fields {
+            let private = if struct_type.private_fields.contains(field) { "private " } else { "" };
+            let ty = self.ts_type(ty);
+            self.line(&format!("{private}{}: {ty};", member_name(field)));
+        }
+
+        let methods: Vec<&FnDecl> = program
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Item(Item::Impl(impl_decl)) if impl_decl.name.is_named(&name) => Some(&impl_decl.methods),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        for method in &methods {
+            let Some(method_type) = struct_type.methods.get(&method.name.as_string()) else {
+                continue;
+            };
+            if method_type.public {
+                let generics = method_generics(struct_type, method_type);
+                let names: Vec<&Token> = method.params.iter().map(|param| &param.name).collect();
+                let (params, return_type) = (&method_type.params, &method_type.return_type);
+                self.method(&method.name.as_string(), method_type.takes_self, &generics, &names, params, return_type);
+            }
+        }
+
+        //the provided methods the impl blocks leave out are copied onto the class
+        let mut traits: Vec<&String> = struct_type.traits.keys().collect();
+        traits.sort();
+        let this = Types::new(RustScriptType::Struct(
+            path.clone(),
+            struct_type.generics.iter().map(|param| Types::new(RustScriptType::Param(param.clone()))).collect(),
+        ));
+        let substitution = [("Self".to_string(), this)].into_iter().collect();
+        for trait_type in traits.into_iter().filter_map(|trait_name| items.trait_type(trait_name)) {
+            let mut provided: Vec<(&String, _)> = trait_type
+                .methods
+                .iter()
+                .filter(|(method, _)| {
+                    !trait_type.required.contains(method) && !methods.iter().any(|decl| decl.name.is_named(method))
+                })
+                .collect();
+            provided.sort_by_key(|(method, _)| *method);
+            for (method, method_type) in provided {
+                let generics = method_generics(struct_type, method_type);
+                let params: Vec<Types> = method_type.params.iter().map(|ty| ty.substitute(&substitution)).collect();
+                let return_type = method_type.return_type.substitute(&substitution);
+                self.method(method, method_type.takes_self, &generics, &[], &params, &return_type);
+            }
+        }
+
+        self.tabs -= 1;
+        self.line("}");
+    }
+
+    fn method(
+        &mut self,
+        name: &str,
+        takes_self: bool,
+        generics: &[TypeParam],
+        names: &[&Token],
+        params: &[Types],
+        return_type: &Types,
+    ) {
+        let prefix = if takes_self { "" } else { "static " };
+        let generics = self.generics(generics);
+        let params = self.params(names, params);
+        let return_type = self.ts_type(return_type);
+        self.line(&format!("{prefix}{}{generics}({params}): {return_type};", member_name(name)));
+    }
+
+    //An enum's values are `{ tag, values }` objects, built by the functions of the enum's object
+    fn union(&mut self, decl: &EnumDecl) {
+        let items = self.items;
+        let Some(enum_type) = items.enum_type(&self.qualify(&decl.name)) else {
+            return;
+        };
+        let name = js_name(&decl.name.as_string());
+        let export = self.export(decl.public);
+        if enum_type.variants.is_empty() {
+            self.line(&format!("{export}type {name} = never;"));
+        } else {
+            self.line(&format!("{export}type {name} ="));
+            self.tabs += 1;
+            let count = enum_type.variants.len();
+            for (i, (tag, fields)) in enum_type.variants.iter().enumerate() {
+                let end = if i + 1 == count { ";" } else { "" };
+                if fields.is_empty() {
+                    self.line(&format!("| {{ readonly tag: \"{tag}\" }}{end}"));
+                } else {
+                    let values: Vec<String> = fields.iter().map(|field| self.ts_type(field)).collect();
+                    let values = values.join(", ");
+                    self.line(&format!("| {{ readonly tag: \"{tag}\"; readonly values: readonly [{values}] }}{end}"));
+                }
+            }
+            self.tabs -= 1;
+        }
+
+        self.line(&format!("{export}declare const {name}: {{"));
+        self.tabs += 1;
+        for (tag, fields) in &enum_type.variants {
+            if fields.is_empty() {
+                self.line(&format!("readonly {}: {name};", member_name(tag)));
+            } else {
+                let params = self.params(&[], fields);
+                self.line(&format!("{}({params}): {name};", member_name(tag)));
+            }
+        }
+        self.tabs -= 1;
+        self.line("};");
+    }
+
+    //The methods a trait's implementations are called with, `Self` is the implementing type. The
+    //trait is also the object of its provided methods, which take the implementing value as `this`
+    fn interface(&mut self, decl: &TraitDecl) {
+        let items = self.items;
+        let Some(trait_type) = items.trait_type(&self.qualify(&decl.name)) else {
+            return;
+        };
+        let name = js_name(&decl.name.as_string());
+        let export = self.export(decl.public);
+        self.line(&format!("{export}interface {name} {{"));
+        self.tabs += 1;
+        for method in decl.required.iter().chain(&decl.provided) {
+            let Some(method_type) = trait_type.methods.get(&method.name.as_string()) else {
+                continue;
+            };
+            //an associated function is not called on a value, an interface cannot describe it
+            if method_type.takes_self {
+                let names: Vec<&Token> = method.params.iter().map(|param| &param.name).collect();
+                let (params, return_type) = (&method_type.params, &method_type.return_type);
+                self.method(&method.name.as_string(), true, &method_type.generics, &names, params, return_type);
+            }
+        }
+        self.tabs -= 1;
+        self.line("}");
+
+        let this = Types::new(RustScriptType::Param(TypeParam { name: name.clone(), bounds: Vec::new() }));
+        let substitution = [("Self".to_string(), this)].into_iter().collect();
+        self.line(&format!("{export}declare const {name}: {{"));
+        self.tabs += 1;
+        for method in &decl.provided {
+            let Some(method_type) = trait_type.methods.get(&method.name.as_string()) else {
+                continue;
+            };
+            let generics = self.generics(&method_type.generics);
+            let names: Vec<&Token> = method.params.iter().map(|param| &param.name).collect();
+            let params: Vec<Types> = method_type.params.iter().map(|ty| ty.substitute(&substitution)).collect();
+            let params = self.params(&names, &params);
+            let params = match (method_type.takes_self, params.is_empty()) {
+                (false, _) => params,
+                (true, true) => format!("this: {name}"),
+                (true, false) => format!("this: {name}, {params}"),
+            };
+            let return_type = self.ts_type(&method_type.return_type.substitute(&substitution));
+            self.line(&format!("{}{generics}({params}): {return_type};", member_name(&method.name.as_string())));
+        }
+        self.tabs -= 1;
+        self.line("};");
+    }
+
+    //`<T extends Shape & Named>`, nothing for none
+    fn generics(&mut self, generics: &[TypeParam]) -> String {
+        if generics.is_empty() {
+            return String::new();
+        }
+        let params: Vec<String> = generics
+            .iter()
+            .map(|param| {
+                let bounds: Vec<String> = param.bounds.iter().map(|bound| self.named(bound)).collect();
+                match bounds.is_empty() {
+                    true => js_name(&param.name),
+                    false => format!("{} extends {}", js_name(&param.name), bounds.join(" & ")),
+                }
+            })
+            .collect();
+        format!("<{}>", params.join(", "))
+    }
+
+    //Parameters without a name in the source, of variants and function types, are numbered
+    fn params(&mut self, names: &[&Token], types: &[Types]) -> String {
+        let params: Vec<String> = types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| {
+                let name = names.get(i).map_or_else(|| format!("arg{i}"), |name| js_name(&name.as_string()));
+                format!("{name}: {}", self.ts_type(ty))
+            })
+            .collect();
+        params.join(", ")
+    }
+
+    fn ts_type(&mut self, ty: &Types) -> String {
+        match &ty.name {
+            RustScriptType::I32 | RustScriptType::F64 | RustScriptType::IntVar(_) => "number".to_string(),
+            RustScriptType::I64 => "bigint".to_string(),
+            RustScriptType::String => "string".to_string(),
+            RustScriptType::Bool => "boolean".to_string(),
+            RustScriptType::Enum(path) => self.named(path),
+            RustScriptType::Struct(path, args) if args.is_empty() => self.named(path),
+            RustScriptType::Struct(path, args) => {
+                let name = self.named(path);
+                let args: Vec<String> = args.iter().map(|arg| self.ts_type(arg)).collect();
+                format!("{name}<{}>", args.join(", "))
+            }
+            RustScriptType::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|element| self.ts_type(element)).collect();
+                format!("[{}]", elements.join(", "))
+            }
+            RustScriptType::Vec(element) => {
+                let element = self.ts_type(element);
+                //`number[]`, but `Array<number | string>` where `[]` would bind tighter than the element type
+                match element.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$') {
+                    true => format!("{element}[]"),
+                    false => format!("Array<{element}>"),
+                }
+            }
+            RustScriptType::Option(value) => format!("Option<{}>", self.ts_type(value)),
+            RustScriptType::Result(value, error) => format!("Result<{}, {}>", self.ts_type(value), self.ts_type(error)),
+            RustScriptType::Function { params, return_type } => {
+                let params = self.params(&[], params);
+                format!("({params}) => {}", self.ts_type(return_type))
+            }
+            //in a trait, the type that implements it
+            RustScriptType::Param(TypeParam { name, .. }) if name == "Self" => "this".to_string(),
+            RustScriptType::Param(TypeParam { name, .. }) => js_name(name),
+            RustScriptType::Unit => "void".to_string(),
+            RustScriptType::Never => "never".to_string(),
+            RustScriptType::Var(_) | RustScriptType::UnKnown => "unknown".to_string(),
+        }
+    }
+
+    //A struct, enum or trait by its path. Those of other modules are imported by an ES module, the
+    //modules of a bundle are hidden inside it and only its root module is declared
+    fn named(&mut self, path: &str) -> String {
+        let (module, name) = path.rsplit_once("::").unwrap_or(("", path));
+        let name = js_name(name);
+        if module == self.module.path {
+            return name;
+        }
+        match self.output {
+            Output::EsModules => {
+                let import = (module.to_string(), name.clone());
+                if !self.imports.contains(&import) {
+                    self.imports.push(import);
+                }
+                name
+            }
+            Output::Bundle => "unknown".to_string(),
+        }
+    }
+}
+
+//A static method cannot use the type parameters of its class, it declares them itself
+fn method_generics(struct_type: &StructType, method_type: &MethodType) -> Vec<TypeParam> {
+    match method_type.takes_self {
+        true => method_type.generics.clone(),
+        false => [struct_type.generics.clone(), method_type.generics.clone()].concat(),
+    }
+}
+
+/// The declaration file of an ES module, TypeScript looks for `index.d.mts` next to `index.mjs`.
+pub fn declaration_file(path: &str) -> String {
+    let file = module_file(path);
+    format!("{}.d.mts", file.strip_suffix(".mjs").unwrap_or(&file))
+}
+
+//Option and Result values are built like those of user defined enums
+const OPTION: &str = "type Option<T> =
+\t| { readonly tag: \"Some\"; readonly values: readonly [T] }
+\t| { readonly tag: \"None\" };
+";
+
+const OPTION_OBJECT: &str = "declare const Option: {
+\tSome<T>(value: T): Option<T>;
+\treadonly None: Option<never>;
+};
+";
+
+const RESULT: &str = "type Result<T, E> =
+\t| { readonly tag: \"Ok\"; readonly values: readonly [T] }
+\t| { readonly tag: \"Err\"; readonly values: readonly [E] };
+";
+
+const RESULT_OBJECT: &str = "declare const Result: {
+\tOk<T>(value: T): Result<T, never>;
+\tErr<E>(error: E): Result<never, E>;
+};
+";
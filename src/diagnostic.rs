@@ -0,0 +1,196 @@
+use crate::error::Problem;
+
+/// Where a token sits in the source. `offset` is in bytes, `column` counts characters from 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// A span underlined in the rendered snippet, with an optional message printed after the underline.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Errors stop the program from being emitted, warnings are only reported.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A diagnostic rendered like rustc: the message, the source line of the primary span underlined
+/// with `^`, any secondary spans underlined with `-`, then `= note:` lines.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub primary: Label,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+/// Every error and warning found while compiling one source file. Each stage records what it
+/// finds and carries on, and they are all reported together at the end.
+#[derive(Debug)]
+pub struct Diagnostics {
+    name: String,
+    source: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new(name: &str, source: &str) -> Diagnostics {
+        Diagnostics {
+            name: name.to_string(),
+            source: source.to_string(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    pub fn error(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(Diagnostic { severity: Severity::Error, ..diagnostic });
+    }
+
+    pub fn warning(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(Diagnostic { severity: Severity::Warning, ..diagnostic });
+    }
+
+    //Keep the diagnostic of a failed stage, a `Fail` has already been recorded
+    pub fn record(&mut self, problem: Problem) {
+        if let Problem::Error(diagnostic) = problem {
+            self.error(*diagnostic);
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == severity).count()
+    }
+
+    //Print every diagnostic
+    pub fn report(&self) {
+        for diagnostic in &self.diagnostics {
+            eprintln!("{}", diagnostic.render(&self.name, &self.source));
+        }
+    }
+
+    //Print one summary for the diagnostics of every file compiled, such as "3 errors, 1 warning"
+    pub fn summary<'d>(all: impl Iterator<Item = &'d Diagnostics> + Clone) {
+        let mut summary = Vec::new();
+        for (severity, noun) in [(Severity::Error, "error"), (Severity::Warning, "warning")] {
+            match all.clone().map(|diagnostics| diagnostics.count(severity)).sum() {
+                0 => {}
+                count => summary.push(plural(count, noun)),
+            }
+        }
+        if !summary.is_empty() {
+            eprintln!("{}", summary.join(", "));
+        }
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+impl Diagnostic {
+    pub fn new(message: &str, span: Span) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.to_string(),
+            primary: Label { span, message: String::new() },
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn label(mut self, message: &str) -> Diagnostic {
+        self.primary.message = message.to_string();
+        self
+    }
+
+    pub fn note(mut self, span: Span, message: &str) -> Diagnostic {
+        self.secondary.push(Label { span, message: message.to_string() });
+        self
+    }
+
+    //A note printed after the snippet, for context that has no place in the source
+    pub fn footer(mut self, message: &str) -> Diagnostic {
+        self.notes.push(message.to_string());
+        self
+    }
+
+    pub fn render(&self, name: &str, source: &str) -> String {
+        let span = self.primary.span;
+        let labels: Vec<(&Label, char)> =
+            [(&self.primary, '^')].into_iter().chain(self.secondary.iter().map(|label| (label, '-'))).collect();
+        let gutter = labels.iter().map(|(label, _)| label.span.line).max().unwrap_or(1).to_string().len();
+        let blank = " ".repeat(gutter);
+
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        let mut out = format!("{severity}: {}\n", self.message);
+        out += &format!("{blank}--> {}:{}:{}\n", name, span.line, span.column);
+
+        //each line is quoted once in source order, followed by the underline of every label on it
+        let mut lines: Vec<usize> = labels.iter().map(|(label, _)| label.span.line).collect();
+        lines.sort();
+        lines.dedup();
+        out += &format!("{blank} |\n");
+        let mut previous: Option<usize> = None;
+        for line_number in lines {
+            //lines that are not next to each other are separated by `...`
+            if previous.is_some_and(|previous| line_number > previous + 1) {
+                out += "...\n";
+            }
+            previous = Some(line_number);
+
+            let line = source.lines().nth(line_number - 1).unwrap_or("");
+            out += &format!("{line_number:>gutter$} | {line}\n");
+            let mut on_line: Vec<&(&Label, char)> =
+                labels.iter().filter(|(label, _)| label.span.line == line_number).collect();
+            on_line.sort_by_key(|(label, _)| label.span.column);
+            for (label, underline) in on_line {
+                out += format!("{blank} | {}", underline_label(line, label, *underline)).trim_end();
+                out.push('\n');
+            }
+        }
+        if !self.notes.is_empty() {
+            out += &format!("{blank} |\n");
+        }
+        for note in &self.notes {
+            out += &format!("{blank} = note: {note}\n");
+        }
+        out
+    }
+}
+
+//Spaces up to the label's column, then the underline and its message. A span running past the
+//end of its line is cut short there
+fn underline_label(line: &str, label: &Label, underline: char) -> String {
+    let width = line.chars().count();
+    let start = label.span.column.saturating_sub(1).min(width);
+    let len = label.span.len.min(width - start).max(1);
+
+    //tabs keep their width in the underline so it lines up with the source
+    let indent: String = line.chars().take(start).map(|ch| if ch == '\t' { '\t' } else { ' ' }).collect();
+    format!("{}{} {}", indent, underline.to_string().repeat(len), label.message)
+}
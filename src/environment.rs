@@ -1,51 +1,164 @@
 use std::collections::HashMap;
-use crate::{ types::Types};
+use std::fmt;
+
+use crate::diagnostic::Span;
 use crate::error::*;
+use crate::token::Token;
+use crate::types::Types;
+
+/// Where a name was found, relative to the scope it was looked up from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    Local,
+    /// A scope between the current one and the global scope, `usize` levels up.
+    Enclosing(usize),
+    Global,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Scope::Local => write!(f, "current"),
+            Scope::Enclosing(_) => write!(f, "enclosing"),
+            Scope::Global => write!(f, "global"),
+        }
+    }
+}
 
+/// How a name was introduced, which decides whether it may be assigned to again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BindingKind {
+    /// A name bound by a `let` or match pattern, `x` or `mut x`
+    Variable { mutable: bool },
+    /// A function parameter, `mut name: type`, or `self`, `&mut self`
+    Parameter { mutable: bool },
+    Function,
+    /// A `for` loop variable
+    LoopVariable,
+}
+
+/// A name in scope: its type, how it was declared and where.
 #[derive(Debug, Clone)]
+pub struct Binding {
+    pub vtype: Types,
+    pub kind: BindingKind,
+    pub span: Span,
+    /// For a closure, a captured variable it assigns to. Like Rust's `FnMut`, calling it then
+    /// needs a mutable binding.
+    pub mutates: Option<String>,
+}
+
+impl Binding {
+    pub fn is_mutable(&self) -> bool {
+        matches!(self.kind, BindingKind::Variable { mutable: true } | BindingKind::Parameter { mutable: true })
+    }
+}
+
+/// The types of the names visible at a point in the program. Each nested block gets its own
+/// scope linked to the one it is nested in, so lookups walk outwards until the global scope.
+#[derive(Debug, Clone, Default)]
 pub struct TypeEnvironment {
-    pub record: HashMap<String, Types>,
-    pub parent: HashMap<String, Types>,
+    pub record: HashMap<String, Binding>,
+    pub parent: Option<Box<TypeEnvironment>>,
 }
 
 impl TypeEnvironment {
     pub fn new() -> TypeEnvironment {
-        TypeEnvironment {
+        TypeEnvironment::default()
+    }
+
+    //Run `check` in a new child scope. Names it defines are dropped when it returns,
+    //which brings back any outer name they shadowed
+    pub fn scoped<R>(&mut self, check: impl FnOnce(&mut TypeEnvironment) -> R) -> R {
+        let parent = std::mem::take(self);
+        let mut child = TypeEnvironment {
             record: HashMap::new(),
-            parent: HashMap::new(),
-        }
+            parent: Some(Box::new(parent)),
+        };
+        let result = check(&mut child);
+        *self = *child.parent.expect("child scopes always have a parent");
+        result
     }
 
-    pub fn branch_env(parent: HashMap<String, Types>) -> TypeEnvironment {
-        TypeEnvironment { record: HashMap::new(), parent, }
+    //Like Rust's `let`, defining a name again in the same scope re-binds it with the new type
+    //and mutability
+    pub fn define(&mut self, name: &Token, vtype: Types, kind: BindingKind) -> Types {
+        let binding = Binding {
+            vtype: vtype.to_owned(),
+            kind,
+            span: name.span(),
+            mutates: None,
+        };
+        self.record.insert(name.as_string(), binding);
+        vtype
     }
 
+    //What the body of a function nested in this scope sees: the global scope and the functions of
+    //the scopes around it, but not their variables, which belong to the function being run
+    pub fn items(&self) -> TypeEnvironment {
+        let mut functions: HashMap<String, Binding> = HashMap::new();
+        let mut env = self;
+        while let Some(parent) = &env.parent {
+            for (name, binding) in &env.record {
+                if binding.kind == BindingKind::Function {
+                    functions.entry(name.clone()).or_insert_with(|| binding.clone());
+                }
+            }
+            env = parent;
+        }
+        TypeEnvironment {
+            record: functions,
+            parent: Some(Box::new(env.clone())),
+        }
+    }
+
+    //The variables of the scopes around this one, the global scope's aside
+    pub fn locals(&self) -> Vec<String> {
+        let mut locals = Vec::new();
+        let mut env = self;
+        while let Some(parent) = &env.parent {
+            let variables = env.record.iter().filter(|(_, binding)| binding.kind != BindingKind::Function);
+            locals.extend(variables.map(|(name, _)| name.clone()));
+            env = parent;
+        }
+        locals
+    }
 
-    pub fn define(&mut self, vname: String, vtype: Types) -> Types {
-        self.record.insert(vname, vtype.to_owned());
-        vtype.to_owned()
+    //How many scopes this one is nested in, the global scope is 0
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut env = self;
+        while let Some(parent) = &env.parent {
+            depth += 1;
+            env = parent;
+        }
+        depth
     }
 
-    pub fn lookup(&mut self, vname: String) -> Result<&Types, Problem> {
-        self.resolve(vname)
+    pub fn lookup(&self, vname: String) -> Result<&Types, Problem> {
+        self.resolve(&vname).map(|(binding, _)| &binding.vtype)
     }
 
-    //Problem Very nature of adding record env to scope underneath and not 2 scopes
-    //down means scope 3 branches down will not have access to global scope
-    //May have to copy envs .records + .parent to child environment
-    //Alternatively create self-referentials structs using Box perhaps? thus 
-    //parents is contains a TypeEnvironment copy of its parent, then recursively
-    //go up parent environements to find variables 
-    pub fn resolve(&mut self, vname: String) -> Result<&Types, Problem> {
-        if self.record.contains_key(&vname) {
-            Ok(self.record.get(&vname).expect("Couln't get type from records for variable"))
-        } else if self.parent.is_empty() {
-            println!("Variable not defined {}", &vname);
-            Err(Problem::fail())
-        } else if self.parent.contains_key(&vname) {
-            Ok(self.parent.get(&vname).expect("Couln't get type from parent for variable"))
-        } else {
-            Err(Problem::fail())
+    //Find the innermost definition of a name and the scope it was defined in
+    pub fn resolve(&self, vname: &str) -> Result<(&Binding, Scope), Problem> {
+        let mut env = self;
+        let mut distance = 0;
+        loop {
+            if let Some(binding) = env.record.get(vname) {
+                let scope = match (&env.parent, distance) {
+                    (None, _) => Scope::Global,
+                    (Some(_), 0) => Scope::Local,
+                    (Some(_), distance) => Scope::Enclosing(distance),
+                };
+                return Ok((binding, scope));
+            }
+            match &env.parent {
+                Some(parent) => {
+                    env = parent;
+                    distance += 1;
+                }
+                None => return Err(Problem::fail()),
+            }
         }
     }
-}
\ No newline at end of file
+}
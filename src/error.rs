@@ -1,15 +1,13 @@
-use crate::object::*;
+use crate::diagnostic::*;
 use crate::token::*;
 use crate::token_type::*;
 
+/// Why a stage stopped. Errors are recorded in `Diagnostics` and reported once compiling ends,
+/// `Fail` marks a failure whose diagnostic has already been recorded.
 #[derive(Debug)]
 pub enum Problem {
-    ParseError { token: Token, message: String },
-    RuntimeError { token: Token, message: String },
-    Error { line: usize, message: String },
+    Error(Box<Diagnostic>),
     SystemError { message: String },
-    ReturnValue { value: Object },
-    Break,
     Fail,
 }
 
@@ -18,76 +16,23 @@ impl Problem {
         Problem::Fail
     }
 
-    pub fn return_value(value: Object) -> Problem {
-        Problem::ReturnValue { value }
-    }
-
-    pub fn error(line: usize, message: &str) -> Problem {
-        let err = Problem::Error {
-            line,
-            message: message.to_string(),
-        };
-        err.report("");
-        err
+    pub fn error(span: Span, message: &str) -> Problem {
+        Problem::Error(Box::new(Diagnostic::new(message, span)))
     }
 
     pub fn parse_error(token: &Token, message: &str) -> Problem {
-        let err = Problem::ParseError {
-            token: token.dup(),
-            message: message.to_string(),
+        let found = if token.is(TokenType::Eof) {
+            "found end of file".to_string()
+        } else {
+            format!("found '{}'", token.as_string())
         };
-        err.report("");
-        err
-    }
-
-    pub fn runtime_error(token: &Token, message: &str) -> Problem {
-        let err = Problem::RuntimeError {
-            token: token.dup(),
-            message: message.to_string(),
-        };
-        err.report("");
-        err
+        Problem::Error(Box::new(Diagnostic::new(message, token.span()).label(&found)))
     }
 
+    //Problems outside the source being compiled, such as failing to write the output
     pub fn system_error(message: &str) -> Problem {
-        let err = Problem::SystemError {
+        Problem::SystemError {
             message: message.to_string(),
-        };
-        err.report("");
-        err
-    }
-
-    fn report(&self, loc: &str) {
-        match self {
-            Problem::ParseError { token, message } => {
-                if token.is(TokenType::Eof) {
-                    eprintln!("[line {}] Error at end: {}", token.line, message);
-                } else {
-                    eprintln!(
-                        "[line {}] Error at '{}': {}",
-                        token.line,
-                        token.as_string(),
-                        message
-                    );
-                }
-            }
-            Problem::RuntimeError { token, message } => {
-                if token.is(TokenType::Eof) {
-                    eprintln!("[line {}] Error at end: {}", token.line, message);
-                } else {
-                    eprintln!("{}\n[line {}]", message, token.line);
-                }
-            }
-            Problem::Error { line, message } => {
-                eprintln!("[line {}] Error{}: {}", line, loc, message);
-            }
-            Problem::SystemError { message } => {
-                eprintln!("System Error: {message}");
-            }
-            Problem::Break | Problem::ReturnValue { .. } => {}
-            Problem::Fail => {
-                panic!("should not get here")
-            }
-        };
+        }
     }
 }
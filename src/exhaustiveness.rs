@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::ast::Pattern;
+use crate::diagnostic::Span;
+use crate::object::Object;
+use crate::token::Token;
+use crate::token_type::TokenType;
+use crate::types::*;
+
+//Exhaustiveness checking for match arms.
+//
+//The arms are treated as a matrix of patterns, one row per arm and one column per value still
+//to be matched. Types with a finite set of constructors (enums, Option, Result, bool and tuples) are
+//split into one sub-problem per constructor, everything else (num, str) can only be covered by a wildcard.
+//Structs and tuples have a single constructor, so only the patterns for their fields can fall short.
+//When a constructor is not covered, the recursion builds an example of a missing value.
+
+/// The user defined types a pattern can destructure.
+pub struct Declared<'a> {
+    pub enums: &'a HashMap<String, EnumType>,
+    pub structs: &'a HashMap<String, StructType>,
+}
+
+/// Returns an example of a value of type `scrutinee` that no pattern matches, if there is one.
+pub fn missing_pattern(patterns: &[&Pattern], scrutinee: &Types, declared: &Declared) -> Option<String> {
+    let rows: Vec<Vec<&Pattern>> = patterns.iter().map(|p| vec![*p]).collect();
+    let mut witness = missing(&rows, std::slice::from_ref(scrutinee), declared)?;
+    Some(witness.remove(0))
+}
+
+struct Constructor {
+    name: String,
+    fields: Vec<Types>,
+    //the names of a struct's fields, empty for other constructors
+    field_names: Vec<String>,
+}
+
+impl Constructor {
+    fn new(name: String, fields: Vec<Types>) -> Constructor {
+        Constructor { name, fields, field_names: Vec::new() }
+    }
+}
+
+fn constructors(ty: &Types, declared: &Declared) -> Option<Vec<Constructor>> {
+    match &ty.name {
+        RustScriptType::Bool => Some(vec![
+            Constructor::new("true".to_string(), Vec::new()),
+            Constructor::new("false".to_string(), Vec::new()),
+        ]),
+        RustScriptType::Enum(name) => declared.enums.get(name).map(|enum_type| {
+            enum_type
+                .variants
+                .iter()
+                .map(|(variant, fields)| Constructor::new(format!("{name}::{variant}"), fields.clone()))
+                .collect()
+        }),
+        RustScriptType::Option(value) => Some(vec![
+            Constructor::new("Some".to_string(), vec![*value.clone()]),
+            Constructor::new("None".to_string(), Vec::new()),
+        ]),
+        RustScriptType::Result(value, error) => Some(vec![
+            Constructor::new("Ok".to_string(), vec![*value.clone()]),
+            Constructor::new("Err".to_string(), vec![*error.clone()]),
+        ]),
+        RustScriptType::Tuple(elements) => Some(vec![Constructor::new(String::new(), elements.clone())]),
+        RustScriptType::Struct(name, args) => declared.structs.get(name).map(|struct_type| {
+            let struct_type = struct_type.instantiate(args);
+            vec![Constructor {
+                name: name.clone(),
+                fields: struct_type.fields.iter().map(|(_, field_type)| field_type.clone()).collect(),
+                field_names: struct_type.fields.iter().map(|(field, _)| field.clone()).collect(),
+            }]
+        }),
+        _ => None,
+    }
+}
+
+//Stands in for the fields a struct pattern leaves out with `..`
+fn wildcard() -> &'static Pattern {
+    static WILDCARD: OnceLock<Pattern> = OnceLock::new();
+    WILDCARD.get_or_init(|| {
+        let span = Span { line: 0, column: 0, offset: 0, len: 0 };
+        Pattern::Wildcard(Token::new(TokenType::Identifier, "_".to_string(), None, span))
+    })
+}
+
+//Does `pattern` match values built with `constructor`? If so, the patterns for its fields
+fn specialize<'p>(pattern: &'p Pattern, constructor: &Constructor) -> Option<Vec<&'p Pattern>> {
+    match pattern {
+        Pattern::Wildcard(_) | Pattern::Binding { .. } => Some(vec![pattern; constructor.fields.len()]),
+        Pattern::Literal { value: Object::Bool(b), .. } => (b.to_string() == constructor.name).then(Vec::new),
+        Pattern::Variant { path, fields } => {
+            let variant = path.last().expect("paths have at least one segment").as_string();
+            let matches = constructor.name.rsplit("::").next() == Some(variant.as_str());
+            //a field count mismatch is reported by the typechecker, treat it as matching nothing
+            (matches && fields.len() == constructor.fields.len()).then(|| fields.iter().collect())
+        }
+        Pattern::Tuple { elements, .. } => (elements.len() == constructor.fields.len()).then(|| elements.iter().collect()),
+        //a struct pattern on another type is reported by the typechecker, treat it as matching nothing
+        Pattern::Struct { fields, .. } => (constructor.field_names.len() == constructor.fields.len()).then(|| {
+            constructor
+                .field_names
+                .iter()
+                .map(|name| match fields.iter().find(|(field, _)| field.is_named(name)) {
+                    Some((_, pattern)) => pattern,
+                    None => wildcard(),
+                })
+                .collect()
+        }),
+        Pattern::Literal { .. } => None,
+    }
+}
+
+fn missing(rows: &[Vec<&Pattern>], types: &[Types], declared: &Declared) -> Option<Vec<String>> {
+    let Some((first, rest)) = types.split_first() else {
+        // no columns left: covered if any row survived
+        return if rows.is_empty() { Some(Vec::new()) } else { None };
+    };
+
+    match constructors(first, declared) {
+        Some(constructors) => {
+            for constructor in constructors {
+                let specialized: Vec<Vec<&Pattern>> = rows
+                    .iter()
+                    .filter_map(|row| {
+                        let mut fields = specialize(row[0], &constructor)?;
+                        fields.extend_from_slice(&row[1..]);
+                        Some(fields)
+                    })
+                    .collect();
+
+                let mut column_types = constructor.fields.clone();
+                column_types.extend_from_slice(rest);
+
+                if let Some(mut witness) = missing(&specialized, &column_types, declared) {
+                    let remaining = witness.split_off(constructor.fields.len());
+                    let mut result = vec![describe(&constructor, &witness)];
+                    result.extend(remaining);
+                    return Some(result);
+                }
+            }
+            None
+        }
+        None => {
+            //nothing is known about an unknown type, so assume its patterns cover it
+            let defaults: Vec<Vec<&Pattern>> = rows
+                .iter()
+                .filter(|row| row[0].is_irrefutable() || first.is_unknown())
+                .map(|row| row[1..].to_vec())
+                .collect();
+
+            let mut witness = missing(&defaults, rest, declared)?;
+            witness.insert(0, "_".to_string());
+            Some(witness)
+        }
+    }
+}
+
+fn describe(constructor: &Constructor, fields: &[String]) -> String {
+    if !constructor.field_names.is_empty() {
+        let fields: Vec<String> =
+            constructor.field_names.iter().zip(fields).map(|(name, field)| format!("{name}: {field}")).collect();
+        format!("{} {{ {} }}", constructor.name, fields.join(", "))
+    } else if constructor.name.is_empty() {
+        format!("({})", fields.join(", "))
+    } else if fields.is_empty() {
+        constructor.name.clone()
+    } else {
+        format!("{}({})", constructor.name, fields.join(", "))
+    }
+}
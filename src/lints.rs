@@ -0,0 +1,497 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::*;
+use crate::diagnostic::{Diagnostic, Diagnostics, Span};
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+/// Code that compiles but is probably a mistake. Each lint has a stable name used to change
+/// its level with `#[allow(..)]`, `#[warn(..)]`, `#[deny(..)]` or `-A`, `-W`, `-D` on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariables,
+    UnusedParameters,
+    UnreachableCode,
+    DeadCode,
+    Shadowing,
+}
+
+const LINTS: [Lint; 5] = [
+    Lint::UnusedVariables,
+    Lint::UnusedParameters,
+    Lint::UnreachableCode,
+    Lint::DeadCode,
+    Lint::Shadowing,
+];
+
+impl Lint {
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::UnusedParameters => "unused_parameters",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::DeadCode => "dead_code",
+            Lint::Shadowing => "shadowing",
+        }
+    }
+
+    //Attributes spell lint names with `_`, the command line also accepts `-`
+    pub fn from_name(name: &str) -> Option<Lint> {
+        let name = name.replace('-', "_");
+        LINTS.into_iter().find(|lint| lint.name() == name)
+    }
+
+    //Shadowing is how Rust code re-binds a name, so it is only reported when asked for
+    fn default_level(self) -> Level {
+        match self {
+            Lint::Shadowing => Level::Allow,
+            _ => Level::Warn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Level {
+    fn from_attribute(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+
+    fn attribute(self) -> &'static str {
+        match self {
+            Level::Allow => "allow",
+            Level::Warn => "warn",
+            Level::Deny => "deny",
+        }
+    }
+
+    fn flag(self) -> &'static str {
+        match self {
+            Level::Allow => "-A",
+            Level::Warn => "-W",
+            Level::Deny => "-D",
+        }
+    }
+}
+
+/// Lint levels given on the command line, attributes in the source take precedence over them.
+#[derive(Debug, Clone, Default)]
+pub struct LintLevels {
+    levels: HashMap<Lint, Level>,
+}
+
+impl LintLevels {
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels.insert(lint, level);
+    }
+}
+
+//Where a lint got its level, the diagnostic says so to show how to change it
+#[derive(Debug, Clone, Copy)]
+enum Origin {
+    Default,
+    CommandLine,
+    Attribute(Span),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BindingKind {
+    Variable,
+    Parameter,
+    Function,
+}
+
+#[derive(Debug)]
+struct Binding {
+    name: String,
+    span: Span,
+    kind: BindingKind,
+    used: bool,
+    //the level of the lint that reports this binding unused, where it was declared
+    level: (Level, Origin),
+}
+
+/// Check a program that parsed for unused bindings, unreachable statements and shadowing.
+pub fn lint(program: &Program, imported: &HashSet<String>, command_line: &LintLevels, diagnostics: &mut Diagnostics) {
+    let flags = command_line.levels.iter().map(|(lint, level)| (*lint, (*level, Origin::CommandLine))).collect();
+    let mut linter = Linter {
+        diagnostics,
+        imported,
+        levels: vec![flags],
+        scopes: Vec::new(),
+    };
+
+    linter.check_attributes(&program.attributes);
+    let levels = linter.levels_of(&program.attributes);
+    linter.levels.push(levels);
+    linter.scoped(|linter| linter.stmts(&program.body));
+}
+
+/// A library only defines items for the Javascript that imports it. Code at the top level of one of
+/// its modules would run on import, and the root's `main` would never be called, so both are errors.
+pub fn check_library(program: &Program, root: bool, diagnostics: &mut Diagnostics) {
+    for stmt in &program.body {
+        match stmt {
+            Stmt::Item(Item::Fn(decl)) if root && !decl.public && decl.name.is_named("main") => {
+                let diagnostic = Diagnostic::new("A library has no entry point", decl.name.span())
+                    .label("'main' is never called")
+                    .footer("remove it, or make it 'pub' so the Javascript that imports the library can call it");
+                diagnostics.error(diagnostic);
+            }
+            stmt => {
+                if let Some(span) = stmt_span(stmt) {
+                    let diagnostic = Diagnostic::new("A library cannot run code at the top level", span)
+                        .label("this would run when the library is imported")
+                        .footer("move it into a function");
+                    diagnostics.error(diagnostic);
+                }
+            }
+        }
+    }
+}
+
+struct Linter<'a> {
+    diagnostics: &'a mut Diagnostics,
+    //the top level items of the module that other modules import
+    imported: &'a HashSet<String>,
+    //levels set by the command line and then by the attributes around the code being checked
+    levels: Vec<HashMap<Lint, (Level, Origin)>>,
+    scopes: Vec<Vec<Binding>>,
+}
+
+impl Linter<'_> {
+    fn level(&self, lint: Lint) -> (Level, Origin) {
+        self.levels
+            .iter()
+            .rev()
+            .find_map(|levels| levels.get(&lint))
+            .copied()
+            .unwrap_or((lint.default_level(), Origin::Default))
+    }
+
+    fn levels_of(&self, attributes: &[Attribute]) -> HashMap<Lint, (Level, Origin)> {
+        let mut levels = HashMap::new();
+        for attribute in attributes {
+            if let Some(level) = Level::from_attribute(&attribute.name.as_string()) {
+                for lint in attribute.args.iter().filter_map(|arg| Lint::from_name(&arg.as_string())) {
+                    levels.insert(lint, (level, Origin::Attribute(attribute.name.span())));
+                }
+            }
+        }
+        levels
+    }
+
+    fn check_attributes(&mut self, attributes: &[Attribute]) {
+        for attribute in attributes {
+            let name = attribute.name.as_string();
+            if Level::from_attribute(&name).is_none() {
+                let message = format!("Unknown attribute '{name}'");
+                self.diagnostics.error(Diagnostic::new(&message, attribute.name.span()));
+                continue;
+            }
+            if attribute.value.is_some() {
+                let message = format!("Malformed '{name}' attribute");
+                let label = format!("expected '#[{name}(lint, ..)]'");
+                self.diagnostics.error(Diagnostic::new(&message, attribute.name.span()).label(&label));
+                continue;
+            }
+            for arg in &attribute.args {
+                if Lint::from_name(&arg.as_string()).is_none() {
+                    let message = format!("Unknown lint '{}'", arg.as_string());
+                    self.diagnostics.warning(Diagnostic::new(&message, arg.span()));
+                }
+            }
+        }
+    }
+
+    fn emit(&mut self, lint: Lint, (level, origin): (Level, Origin), diagnostic: Diagnostic) {
+        let setting = match level {
+            Level::Allow => return,
+            _ => format!("#[{}({})]", level.attribute(), lint.name()),
+        };
+        let diagnostic = match origin {
+            Origin::Default => diagnostic.footer(&format!("'{setting}' on by default")),
+            Origin::CommandLine => {
+                diagnostic.footer(&format!("'{} {}' given on the command line", level.flag(), lint.name()))
+            }
+            Origin::Attribute(span) => diagnostic.note(span, "the lint level is defined here"),
+        };
+        match level {
+            Level::Deny => self.diagnostics.error(diagnostic),
+            _ => self.diagnostics.warning(diagnostic),
+        }
+    }
+
+    fn scoped(&mut self, check: impl FnOnce(&mut Self)) {
+        self.scopes.push(Vec::new());
+        check(self);
+        let scope = self.scopes.pop().expect("scopes are pushed and popped in pairs");
+        for binding in scope {
+            self.report_unused(binding);
+        }
+    }
+
+    //Names starting with `_` are meant to be unused, and main is used by running the program
+    fn report_unused(&mut self, binding: Binding) {
+        if binding.used || binding.name.starts_with('_') {
+            return;
+        }
+        let (lint, message) = match binding.kind {
+            BindingKind::Variable => (Lint::UnusedVariables, format!("Unused variable '{}'", binding.name)),
+            BindingKind::Parameter => (Lint::UnusedParameters, format!("Unused parameter '{}'", binding.name)),
+            BindingKind::Function if binding.name == "main" => return,
+            BindingKind::Function => (Lint::DeadCode, format!("Function '{}' is never used", binding.name)),
+        };
+        let mut diagnostic = Diagnostic::new(&message, binding.span);
+        if binding.kind != BindingKind::Function {
+            let hint = format!("if this is intentional, prefix it with an underscore: '_{}'", binding.name);
+            diagnostic = diagnostic.footer(&hint);
+        }
+        self.emit(lint, binding.level, diagnostic);
+    }
+
+    fn declare(&mut self, name: &Token, kind: BindingKind, level: (Level, Origin)) {
+        let name_string = name.as_string();
+        if kind == BindingKind::Variable {
+            if let Some(earlier) = self.lookup(&name_string).filter(|b| b.kind != BindingKind::Function) {
+                let earlier = earlier.span;
+                let message = format!("'{name_string}' shadows an earlier binding");
+                let diagnostic = Diagnostic::new(&message, name.span())
+                    .note(earlier, &format!("previous binding of '{name_string}'"));
+                let shadowing = self.level(Lint::Shadowing);
+                self.emit(Lint::Shadowing, shadowing, diagnostic);
+            }
+        }
+
+        let scope = self.scopes.last_mut().expect("there is always a global scope");
+        scope.push(Binding { name: name_string, span: name.span(), kind, used: false, level });
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<&mut Binding> {
+        self.scopes.iter_mut().rev().flat_map(|scope| scope.iter_mut().rev()).find(|binding| binding.name == name)
+    }
+
+    //Functions can be called before they are declared, so a block's functions are declared first.
+    //A `pub` function, or one another module imports, is used by other modules, it is never dead code
+    fn hoist(&mut self, statements: &[Stmt]) {
+        for stmt in statements {
+            if let Stmt::Item(Item::Fn(decl)) = stmt {
+                let levels = self.levels_of(&decl.attributes);
+                let imported = self.scopes.len() == 1 && self.imported.contains(&decl.name.as_string());
+                let level = match decl.public || imported {
+                    true => (Level::Allow, Origin::Default),
+                    false => levels.get(&Lint::DeadCode).copied().unwrap_or_else(|| self.level(Lint::DeadCode)),
+                };
+                self.declare(&decl.name, BindingKind::Function, level);
+            }
+        }
+    }
+
+    //Statements after a `return` or `break` never run, the first of them is reported
+    fn stmts(&mut self, statements: &[Stmt]) {
+        self.hoist(statements);
+        let mut exit: Option<Span> = None;
+        let mut reported = false;
+        for stmt in statements {
+            if let (Some(exit), false, Some(span)) = (exit, reported, stmt_span(stmt)) {
+                let diagnostic = Diagnostic::new("Unreachable statement", span)
+                    .note(exit, "any code following this is unreachable");
+                let level = self.level(Lint::UnreachableCode);
+                self.emit(Lint::UnreachableCode, level, diagnostic);
+                reported = true;
+            }
+            self.stmt(stmt);
+            match stmt {
+                Stmt::Return { keyword, .. } | Stmt::Break(keyword) if exit.is_none() => exit = Some(keyword.span()),
+                _ => {}
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Item(Item::Fn(decl)) => self.function(decl),
+            Stmt::Item(Item::Impl(decl)) => {
+                for method in &decl.methods {
+                    self.function(method);
+                }
+            }
+            //required methods have no body to check
+            Stmt::Item(Item::Trait(decl)) => {
+                for method in &decl.provided {
+                    self.function(method);
+                }
+            }
+            //extern functions have no body, and are not dead code when unused
+            Stmt::Item(Item::Struct(_) | Item::Enum(_) | Item::Mod(_) | Item::Use(_) | Item::Extern(_)) => {}
+            Stmt::Let { pattern, initializer, .. } => {
+                self.expr(initializer);
+                self.pattern(pattern);
+            }
+            Stmt::Expression(expr) | Stmt::Tail(expr) => self.expr(expr),
+            Stmt::Print { args, .. } => {
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Stmt::Block(statements) => self.scoped(|linter| linter.stmts(statements)),
+            Stmt::If { condition, then_branch, else_branch } => {
+                self.expr(condition);
+                self.scoped(|linter| linter.stmts(then_branch));
+                if let Some(else_branch) = else_branch {
+                    self.stmt(else_branch);
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.expr(condition);
+                self.scoped(|linter| linter.stmts(body));
+            }
+            Stmt::For { variable, iterable, body } => {
+                self.expr(iterable);
+                self.scoped(|linter| {
+                    let level = linter.level(Lint::UnusedVariables);
+                    linter.declare(variable, BindingKind::Variable, level);
+                    linter.stmts(body);
+                });
+            }
+            Stmt::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            Stmt::Break(_) => {}
+        }
+    }
+
+    fn function(&mut self, decl: &FnDecl) {
+        self.check_attributes(&decl.attributes);
+        let levels = self.levels_of(&decl.attributes);
+        self.levels.push(levels);
+        self.scoped(|linter| {
+            let level = linter.level(Lint::UnusedParameters);
+            for param in &decl.params {
+                linter.declare(&param.name, BindingKind::Parameter, level);
+            }
+            linter.stmts(&decl.body);
+        });
+        self.levels.pop();
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable(name) => {
+                if let Some(binding) = self.lookup(&name.as_string()) {
+                    binding.used = true;
+                }
+            }
+            //`crate::helper` may name a function of this module, paths are not resolved here so a
+            //top level function of that name counts as used
+            Expr::Path(segments) if segments[0].is(TokenType::Crate) || segments[0].is(TokenType::Super) => {
+                let name = segments.last().expect("paths have at least one segment").as_string();
+                let function = self.scopes[0].iter_mut().find(|binding| binding.name == name);
+                if let Some(binding) = function.filter(|binding| binding.kind == BindingKind::Function) {
+                    binding.used = true;
+                }
+            }
+            Expr::Literal { .. } | Expr::Path(_) | Expr::SelfValue(_) => {}
+            Expr::StructLiteral { fields, .. } => {
+                for (_, value) in fields {
+                    self.expr(value);
+                }
+            }
+            Expr::Get { object, .. } => self.expr(object),
+            Expr::Set { object, value, .. } => {
+                self.expr(object);
+                self.expr(value);
+            }
+            //assigning to a variable is not a use of it
+            Expr::Assign { value, .. } => self.expr(value),
+            Expr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::Unary { right, .. } => self.expr(right),
+            Expr::Cast { value, .. } => self.expr(value),
+            Expr::Grouping(expr) => self.expr(expr),
+            Expr::Tuple { elements, .. } | Expr::Array { elements, .. } => {
+                for element in elements {
+                    self.expr(element);
+                }
+            }
+            Expr::Index { object, index, .. } => {
+                self.expr(object);
+                self.expr(index);
+            }
+            Expr::SetIndex { object, index, value, .. } => {
+                self.expr(object);
+                self.expr(index);
+                self.expr(value);
+            }
+            Expr::Match { scrutinee, arms, .. } => {
+                self.expr(scrutinee);
+                for arm in arms {
+                    self.scoped(|linter| {
+                        linter.pattern(&arm.pattern);
+                        match &arm.body {
+                            ArmBody::Expr(expr) => linter.expr(expr),
+                            ArmBody::Block(statements) => linter.stmts(statements),
+                        }
+                    });
+                }
+            }
+            Expr::Range { start, end, .. } => {
+                self.expr(start);
+                self.expr(end);
+            }
+            Expr::Call { callee, args, .. } => {
+                self.expr(callee);
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Try { value, .. } => self.expr(value),
+            //using a variable inside a closure counts as a use of it
+            Expr::Closure { params, body, .. } => self.scoped(|linter| {
+                let level = linter.level(Lint::UnusedParameters);
+                for param in params {
+                    linter.declare(&param.name, BindingKind::Parameter, level);
+                }
+                match body.as_ref() {
+                    ArmBody::Expr(expr) => linter.expr(expr),
+                    ArmBody::Block(statements) => linter.stmts(statements),
+                }
+            }),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        let level = self.level(Lint::UnusedVariables);
+        for name in pattern.bindings() {
+            self.declare(name, BindingKind::Variable, level);
+        }
+    }
+}
+
+//Where to point at a statement, items are declarations rather than code that runs
+fn stmt_span(stmt: &Stmt) -> Option<Span> {
+    match stmt {
+        Stmt::Item(_) => None,
+        Stmt::Let { pattern, .. } => Some(pattern.token().span()),
+        Stmt::Expression(expr) | Stmt::Tail(expr) => Some(expr.leftmost().span()),
+        Stmt::Print { keyword, .. } => Some(keyword.span()),
+        Stmt::Block(statements) => statements.iter().find_map(stmt_span),
+        Stmt::If { condition, .. } | Stmt::While { condition, .. } => Some(condition.leftmost().span()),
+        Stmt::For { variable, .. } => Some(variable.span()),
+        Stmt::Return { keyword, .. } | Stmt::Break(keyword) => Some(keyword.span()),
+    }
+}
@@ -1,50 +1,100 @@
 use std::env::args;
 use std::fs::File;
 use std::io::{self, stdout, BufRead, Write};
+use std::path::Path;
 
 mod error;
 use error::*;
 
 mod ast;
-use ast::*;
+mod diagnostic;
+use diagnostic::Diagnostics;
 
-mod parser;
-use parser::*;
+mod codegen;
+use codegen::*;
+mod declarations;
+use declarations::*;
 
+mod parser;
 mod scanner;
-use scanner::*;
 
 mod object;
 mod token;
 mod token_type;
 mod environment;
-mod utility;
+mod lints;
+use lints::*;
+mod exhaustiveness;
+mod modules;
+use modules::*;
 mod typechecker;
+use typechecker::*;
+
 mod types;
 
 pub fn main() {
-    let args: Vec<String> = args().collect();
-    match args.len() {
-        1 => run_prompt(),
-        2 => run_file(&args[1]).expect("Could not run file"),
-        _ => {
-            println!("Usage: rustscript [script]");
-            std::process::exit(64);
+    let mut lint_levels = LintLevels::default();
+    let mut output = Output::Bundle;
+    let mut library = false;
+    let mut script = None;
+
+    let mut args = args().skip(1);
+    while let Some(arg) = args.next() {
+        let level = match arg.as_str() {
+            "--esm" => {
+                output = Output::EsModules;
+                continue;
+            }
+            //a library is imported from Javascript, so it is always emitted as ES modules
+            "--lib" => {
+                output = Output::EsModules;
+                library = true;
+                continue;
+            }
+            "-A" => Level::Allow,
+            "-W" => Level::Warn,
+            "-D" => Level::Deny,
+            _ if script.is_none() && !arg.starts_with('-') => {
+                script = Some(arg);
+                continue;
+            }
+            _ => usage(),
+        };
+        let Some(name) = args.next() else { usage() };
+        match Lint::from_name(&name) {
+            Some(lint) => lint_levels.set(lint, level),
+            None => {
+                println!("Unknown lint '{name}'");
+                usage();
+            }
         }
     }
+
+    match script {
+        None => run_prompt(&lint_levels),
+        Some(path) => run_file(&path, &lint_levels, output, library).expect("Could not run file"),
+    }
 }
 
+fn usage() -> ! {
+    println!("Usage: rustscript [-A lint] [-W lint] [-D lint] [--esm] [--lib] [script]");
+    std::process::exit(64);
+}
 
-fn run_file(path: &str) -> io::Result<()> {
+fn run_file(path: &str, lint_levels: &LintLevels, output: Output, library: bool) -> io::Result<()> {
     let buf = std::fs::read_to_string(path)?;
-    if run(buf).is_err() {
-        // Ignore: error was already reported
-        std::process::exit(65);
+    match run(path, buf, lint_levels, output, library) {
+        Ok(()) => Ok(()),
+        Err(Problem::SystemError { message }) => {
+            eprintln!("System Error: {message}");
+            std::process::exit(74);
+        }
+        // Ignore: errors were already reported
+        Err(_) => std::process::exit(65),
     }
-    Ok(())
 }
 
-fn run_prompt() {
+fn run_prompt(lint_levels: &LintLevels) {
     let stdin = io::stdin();
     print!("> ");
     let _ = stdout().flush();
@@ -53,7 +103,9 @@ fn run_prompt() {
             if line.is_empty() {
                 break;
             }
-            let _ = run(line);
+            if let Err(Problem::SystemError { message }) = run("<stdin>", line, lint_levels, Output::Bundle, false) {
+                eprintln!("System Error: {message}");
+            }
         } else {
             break;
         }
@@ -62,64 +114,80 @@ fn run_prompt() {
     }
 }
 
-fn write_to_file(code: String) -> std::io::Result<()> {
-    let mut file = File::create("target/index.js")?;
-    file.write_all(code.as_bytes())?;
+fn write_to_file(path: &str, code: String) -> Result<(), Problem> {
+    if let Some(dir) = Path::new(path).parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| Problem::system_error(&format!("Could not create {}: {e}", dir.display())))?;
+    }
+    let mut file = File::create(path).map_err(|e| Problem::system_error(&format!("Could not create {path}: {e}")))?;
+    file.write_all(code.as_bytes())
+        .map_err(|e| Problem::system_error(&format!("Could not write {path}: {e}")))?;
     Ok(())
 }
 
-fn run(source: String) -> Result<(), Problem> {
-
-    //Tokenisation
-    let mut scanner = Scanner::new(source);
-    let tokens = scanner.scan_tokens()?;
-
-    // for token in tokens {
-    //     println!("{:?}", token);
-    // }
-
-    //Parse the Ast
-    let mut parser = Parser::new(tokens);
-    let ast = parser.parse()?;
-
-    let mut code: String =  "".to_string();
-    let mut tabs:usize = 0;
-    for ast_tokens in ast {
-        // println!("{:?}", &ast_tokens);
-        match ast_tokens.ast_type {
-            AstType::Fn => code += "function ",
-            AstType::Identifier => code += ast_tokens.ast_lexeme(),
-            AstType::LeftParen => code += "(",
-            AstType::RightParen => code += ")",
-            AstType::Comma => code += ", ",
-            AstType::LeftBrace => {
-                code += " {\n";
-                tabs = tabs + 1;
-                code += "\t".repeat(tabs).as_str();
-            },
-            AstType::Print => code += "console.log",
-            AstType::String => {
-                code += ast_tokens.ast_lexeme();
-            },
-            AstType::SemiColon => {
-                code +=  ";\n";
-                code += "\t".repeat(tabs).as_str();
+fn run(name: &str, source: String, lint_levels: &LintLevels, output: Output, library: bool) -> Result<(), Problem> {
+    //Tokenise and parse the file and the modules it declares, each one comes after those it imports from
+    let mut modules = load(name, source)?;
+
+    //Typecheck and lint, a program with syntax errors is missing pieces and would report confusing errors
+    let mut types = Vec::new();
+    let mut items = Items::default();
+    if !modules.iter().any(|module| module.diagnostics.has_errors()) {
+        //a late import uses the functions of a module checked after it, their signatures are found by
+        //checking every module once beforehand, what is wrong in them is reported the second time
+        if modules.iter().any(|module| !module.late_imports.is_empty()) {
+            for module in &modules {
+                let mut discarded = Diagnostics::new(name, "");
+                let mut checker = TypeChecker::new(&mut discarded, &module.path, &module.imports, &module.paths, items);
+                checker.check(&module.program);
+                items = checker.into_items();
             }
-            AstType::RightBrace => {
-                tabs = tabs - 1;
-                code += "\n";
-                code += "\t".repeat(tabs).as_str();
-                code += "}\n";
+        }
+        for module in &mut modules {
+            let mut checker =
+                TypeChecker::new(&mut module.diagnostics, &module.path, &module.imports, &module.paths, items);
+            types.push(checker.check(&module.program));
+            items = checker.into_items();
+            lint(&module.program, &module.imported, lint_levels, &mut module.diagnostics);
+            if library {
+                check_library(&module.program, module.path.is_empty(), &mut module.diagnostics);
             }
-            AstType::Let => code += "let ",
-            AstType::Number => code += ast_tokens.ast_lexeme(),
-            AstType::Assign => code += " = ",
-            AstType::Main => code += ")()", 
-            _ => println!("Undefined"),
         }
     }
 
-    write_to_file(code);
+    for module in &modules {
+        module.diagnostics.report();
+    }
+    Diagnostics::summary(modules.iter().map(|module| &module.diagnostics));
+    if modules.iter().any(|module| module.diagnostics.has_errors()) {
+        return Err(Problem::fail());
+    }
 
-    Ok(())
+    //Emit Javascript, one file with the modules in order or one ES module for each, each with the
+    //TypeScript declarations of what it defines
+    match output {
+        Output::Bundle => {
+            let code: String = modules
+                .iter()
+                .zip(&types)
+                .map(|(module, types)| {
+                    CodeGen::new(types).module(module, output).library(library).generate(&module.program)
+                })
+                .collect();
+            write_to_file("target/index.js", code)?;
+            let root = modules.iter().find(|module| module.path.is_empty()).expect("the root module is always loaded");
+            //the bundle defines the runtime's `Option` and `Result` once any of its modules uses them
+            let prelude = types.iter().any(|types| types.prelude);
+            write_to_file("target/index.d.ts", Declarations::new(root, output, &items, prelude).generate())
+        }
+        Output::EsModules => {
+            for (module, types) in modules.iter().zip(&types) {
+                let code = CodeGen::new(types).module(module, output).library(library).generate(&module.program);
+                write_to_file(&format!("target/{}", module_file(&module.path)), code)?;
+                let declarations = Declarations::new(module, output, &items, types.prelude).generate();
+                write_to_file(&format!("target/{}", declaration_file(&module.path)), declarations)?;
+            }
+            Ok(())
+        }
+    }
 }
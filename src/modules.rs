@@ -0,0 +1,484 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::ast::*;
+use crate::diagnostic::{Diagnostic, Diagnostics, Span};
+use crate::error::Problem;
+use crate::parser::Parser;
+use crate::scanner::Scanner;
+use crate::token::Token;
+use crate::token_type::TokenType;
+
+/// A source file of the program, the file compilation starts from or one named by a `mod` declaration.
+#[derive(Debug)]
+pub struct Module {
+    /// Where the module sits in the tree, `shapes::circle`, empty for the root.
+    pub path: String,
+    /// `pub mod`, modules outside its parent can import from it.
+    pub public: bool,
+    pub program: Program,
+    pub diagnostics: Diagnostics,
+    /// The module each `use` declaration imports from, keyed by the `use` keyword.
+    pub imports: HashMap<usize, String>,
+    /// The paths in its code that name an item of a module, `util::twice`, keyed by their first
+    /// segment. Each is the module and how many of the path's segments name it.
+    pub paths: HashMap<usize, (String, usize)>,
+    /// The names of its items that other modules import, which they can when they are not public.
+    pub imported: HashSet<String>,
+    /// The names of its items that other modules name by a path.
+    pub named: HashSet<String>,
+    /// The `use` declarations and paths of functions that close a cycle of imports, keyed by the
+    /// `use` keyword or the first segment. They import from a module that is checked and run after
+    /// this one.
+    pub late_imports: HashSet<usize>,
+    //the directory the files of the modules it declares are in
+    dir: PathBuf,
+}
+
+/// Parse the file `name` and the modules it declares, and the modules those declare. They are
+/// returned in the order they are checked and run in, each one after the modules it imports from.
+/// Mistakes are recorded in the diagnostics of the module they are in, only a file that cannot be
+/// read stops loading.
+pub fn load(name: &str, source: String) -> Result<Vec<Module>, Problem> {
+    //the root is like a `mod.rst`, the modules it declares are next to it
+    let dir = Path::new(name).parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut modules = vec![parse(name, source, String::new(), false, dir)];
+
+    let mut next = 0;
+    while next < modules.len() {
+        let declared: Vec<(bool, Token)> = modules[next]
+            .program
+            .body
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::Item(Item::Mod(decl)) => Some((decl.public, decl.name.dup())),
+                _ => None,
+            })
+            .collect();
+        for (public, name) in declared {
+            let path = join(&modules[next].path, &name.as_string());
+            if modules.iter().any(|module| module.path == path) {
+                let message = format!("The name '{}' is defined multiple times", name.as_string());
+                modules[next].diagnostics.error(Diagnostic::new(&message, name.span()));
+                continue;
+            }
+            if let Some(module) = open(&mut modules[next], path, public, &name)? {
+                modules.push(module);
+            }
+        }
+        next += 1;
+    }
+
+    let visibility: HashMap<String, bool> = modules.iter().map(|module| (module.path.clone(), module.public)).collect();
+    for module in &mut modules {
+        let mut imports = HashMap::new();
+        for stmt in &module.program.body {
+            if let Stmt::Item(Item::Use(decl)) = stmt {
+                match resolve(&module.path, &decl.path, &visibility) {
+                    Ok(path) => {
+                        imports.insert(decl.keyword.offset, path);
+                    }
+                    Err(problem) => module.diagnostics.record(problem),
+                }
+            }
+        }
+        module.imports = imports;
+
+        let mut paths = HashMap::new();
+        for segments in item_paths(&module.program.body) {
+            let named = module_segments(&module.path, segments, &visibility);
+            if named == 0 {
+                continue;
+            }
+            match resolve(&module.path, &segments[..named], &visibility) {
+                Ok(path) => {
+                    paths.insert(segments[0].offset, (path, named));
+                }
+                Err(problem) => module.diagnostics.record(problem),
+            }
+        }
+        module.paths = paths;
+    }
+    let named: Vec<HashSet<String>> = modules.iter().map(|module| named_names(&modules, &module.path)).collect();
+    let imported: Vec<HashSet<String>> =
+        modules.iter().map(|module| imported_names(&modules, &module.path)).collect();
+    for ((module, imported), named) in modules.iter_mut().zip(imported).zip(named) {
+        module.imported = imported.union(&named).cloned().collect();
+        module.named = named;
+    }
+    Ok(order(modules))
+}
+
+fn parse(name: &str, source: String, path: String, public: bool, dir: PathBuf) -> Module {
+    let mut diagnostics = Diagnostics::new(name, &source);
+    let mut scanner = Scanner::new(source);
+    let tokens = scanner.scan_tokens(&mut diagnostics);
+    let program = Parser::new(tokens, &mut diagnostics).parse();
+    Module {
+        path,
+        public,
+        program,
+        diagnostics,
+        imports: HashMap::new(),
+        paths: HashMap::new(),
+        imported: HashSet::new(),
+        named: HashSet::new(),
+        late_imports: HashSet::new(),
+        dir,
+    }
+}
+
+//`mod shapes;` is the file `shapes.rst` or `shapes/mod.rst`, either way the modules it declares are in `shapes/`
+fn open(parent: &mut Module, path: String, public: bool, name: &Token) -> Result<Option<Module>, Problem> {
+    let dir = parent.dir.join(name.as_string());
+    let candidates = [parent.dir.join(format!("{}.rst", name.as_string())), dir.join("mod.rst")];
+    let found: Vec<&PathBuf> = candidates.iter().filter(|file| file.is_file()).collect();
+    let file = match found[..] {
+        [file] => file,
+        [] => {
+            let message = format!("File not found for module '{}'", name.as_string());
+            let footer = format!(
+                "to create the module '{}', create file '{}' or '{}'",
+                name.as_string(),
+                candidates[0].display(),
+                candidates[1].display()
+            );
+            parent.diagnostics.error(Diagnostic::new(&message, name.span()).footer(&footer));
+            return Ok(None);
+        }
+        _ => {
+            let message = format!(
+                "File for module '{}' found at both '{}' and '{}'",
+                name.as_string(),
+                candidates[0].display(),
+                candidates[1].display()
+            );
+            let diagnostic = Diagnostic::new(&message, name.span()).footer("delete or rename one of them");
+            parent.diagnostics.error(diagnostic);
+            return Ok(None);
+        }
+    };
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| Problem::system_error(&format!("Could not read {}: {e}", file.display())))?;
+    Ok(Some(parse(&file.display().to_string(), source, path, public, dir)))
+}
+
+//The module a `use` path names. It starts from the root after `crate`, from the parent module after
+//`super`, and from the importing module otherwise
+fn resolve(from: &str, path: &[Token], visibility: &HashMap<String, bool>) -> Result<String, Problem> {
+    let mut current = from.to_string();
+    for segment in path {
+        if segment.is(TokenType::Crate) {
+            current = String::new();
+            continue;
+        }
+        if segment.is(TokenType::Super) {
+            let Some(parent) = parent(&current) else {
+                let diagnostic = Diagnostic::new("There are too many leading 'super' keywords", segment.span())
+                    .label("there are too many leading 'super' keywords");
+                return Err(Problem::Error(Box::new(diagnostic)));
+            };
+            current = parent.to_string();
+            continue;
+        }
+
+        let name = segment.as_string();
+        let child = join(&current, &name);
+        let Some(public) = visibility.get(&child) else {
+            let label = format!("could not find '{}' in '{}'", name, display(&current));
+            return Err(Problem::Error(Box::new(Diagnostic::new("Unresolved import", segment.span()).label(&label))));
+        };
+        if !public && !is_within(from, &current) {
+            let message = format!("Module '{}' is private", name);
+            return Err(Problem::Error(Box::new(Diagnostic::new(&message, segment.span()).label("private module"))));
+        }
+        current = child;
+    }
+    Ok(current)
+}
+
+//How many segments at the start of a path name the module of the item it ends with, none when it does
+//not start with a module. A path starting with `crate` or `super` always does, one starting with a
+//name does when a module by that name is declared in the module the path is in
+fn module_segments(from: &str, path: &[Token], visibility: &HashMap<String, bool>) -> usize {
+    let mut current = from.to_string();
+    for (index, segment) in path[..path.len() - 1].iter().enumerate() {
+        if segment.is(TokenType::Crate) {
+            current = String::new();
+        } else if segment.is(TokenType::Super) {
+            //too many leading `super` keywords are reported by `resolve`
+            let Some(parent) = parent(&current) else { return index + 1 };
+            current = parent.to_string();
+        } else {
+            let child = join(&current, &segment.as_string());
+            if !visibility.contains_key(&child) {
+                return index;
+            }
+            current = child;
+        }
+    }
+    path.len() - 1
+}
+
+//Modules are checked and run after the modules they import from, so their imports can only go round
+//in a cycle when each of them is of functions, which are not used until the program runs. The import
+//that closes such a cycle is late, any other cycle is reported at the import that closes it
+fn order(mut modules: Vec<Module>) -> Vec<Module> {
+    let index: HashMap<&str, usize> =
+        modules.iter().enumerate().map(|(i, module)| (module.path.as_str(), i)).collect();
+    let edges: Vec<Vec<Import>> = modules
+        .iter()
+        .map(|module| {
+            module
+                .program
+                .body
+                .iter()
+                .filter_map(|stmt| match stmt {
+                    Stmt::Item(Item::Use(decl)) => {
+                        let imported = index[module.imports.get(&decl.keyword.offset)?.as_str()];
+                        let functions = decl.names.iter().all(|name| declares_function(&modules[imported], name));
+                        Some(Import { module: imported, keyword: decl.keyword.dup(), functions })
+                    }
+                    _ => None,
+                })
+                //only functions can be named by a path, the typechecker reports a path to anything else
+                .chain(item_paths(&module.program.body).into_iter().filter_map(|segments| {
+                    let (path, _) = module.paths.get(&segments[0].offset)?;
+                    Some(Import { module: index[path.as_str()], keyword: segments[0].dup(), functions: true })
+                }))
+                //a module can name its own items, they are not imported from anywhere
+                .filter(|import| modules[import.module].path != module.path)
+                .collect()
+        })
+        .collect();
+
+    let mut search = Search {
+        edges: &edges,
+        visiting: Vec::new(),
+        done: vec![false; modules.len()],
+        order: Vec::new(),
+    };
+    let mut cycles = Vec::new();
+    let mut late = Vec::new();
+    for module in 0..modules.len() {
+        search.visit(module, true, &mut cycles, &mut late);
+    }
+    for (module, keyword) in late {
+        modules[module].late_imports.insert(keyword);
+    }
+
+    let order = search.order;
+    let mut modules: Vec<Option<Module>> = modules.into_iter().map(Some).collect();
+    for (module, span, cycle) in cycles {
+        let names: Vec<String> = cycle
+            .iter()
+            .map(|&i| format!("'{}'", display(&modules[i].as_ref().expect("not ordered yet").path)))
+            .collect();
+        let label = format!("this import closes the cycle {}", names.join(" -> "));
+        let diagnostic = Diagnostic::new("Modules import each other in a cycle", span)
+            .label(&label)
+            .footer("modules run after the modules they import from, so only imports of functions can form a cycle");
+        modules[module].as_mut().expect("not ordered yet").diagnostics.error(diagnostic);
+    }
+    order.into_iter().map(|i| modules[i].take().expect("each module is ordered once")).collect()
+}
+
+//A `use` declaration or a path through a module, the module it imports from and whether it only
+//imports functions
+struct Import {
+    module: usize,
+    keyword: Token,
+    functions: bool,
+}
+
+//Whether `name` is a function at the top level of `module`
+fn declares_function(module: &Module, name: &Token) -> bool {
+    let name = name.as_string();
+    module.program.body.iter().any(|stmt| matches!(stmt, Stmt::Item(Item::Fn(decl)) if decl.name.is_named(&name)))
+}
+
+//A depth first search of the imports, a module is finished after every module it imports from but
+//the ones it imports from late
+struct Search<'a> {
+    edges: &'a [Vec<Import>],
+    //the modules being visited, each with whether the import it was reached by is of functions
+    visiting: Vec<(usize, bool)>,
+    done: Vec<bool>,
+    order: Vec<usize>,
+}
+
+impl Search<'_> {
+    fn visit(
+        &mut self,
+        module: usize,
+        functions: bool,
+        cycles: &mut Vec<(usize, Span, Vec<usize>)>,
+        late: &mut Vec<(usize, usize)>,
+    ) {
+        if self.done[module] {
+            return;
+        }
+        let edges = self.edges;
+        self.visiting.push((module, functions));
+        for import in &edges[module] {
+            let Some(start) = self.visiting.iter().position(|&(visiting, _)| visiting == import.module) else {
+                self.visit(import.module, import.functions, cycles, late);
+                continue;
+            };
+            //the imports that lead from the imported module back to this one, and this one
+            let cycle = &self.visiting[start..];
+            if import.functions && cycle[1..].iter().all(|&(_, functions)| functions) {
+                late.push((module, import.keyword.offset));
+            } else {
+                let cycle = cycle.iter().map(|&(visiting, _)| visiting).chain([import.module]).collect();
+                cycles.push((module, import.keyword.span(), cycle));
+            }
+        }
+        self.visiting.pop();
+        self.done[module] = true;
+        self.order.push(module);
+    }
+}
+
+//The names the other modules import from the module at `path`
+fn imported_names(modules: &[Module], path: &str) -> HashSet<String> {
+    modules
+        .iter()
+        .filter(|module| module.path != path)
+        .flat_map(|module| {
+            module.program.body.iter().filter_map(|stmt| match stmt {
+                Stmt::Item(Item::Use(decl)) if module.imports.get(&decl.keyword.offset)? == path => Some(&decl.names),
+                _ => None,
+            })
+        })
+        .flatten()
+        .map(|name| name.as_string())
+        .collect()
+}
+
+//The names the other modules name by a path into the module at `path`
+fn named_names(modules: &[Module], path: &str) -> HashSet<String> {
+    modules
+        .iter()
+        .filter(|module| module.path != path)
+        .flat_map(|module| {
+            item_paths(&module.program.body).into_iter().filter_map(|segments| {
+                let (module, named) = module.paths.get(&segments[0].offset)?;
+                (module == path && segments.len() == named + 1).then(|| segments[*named].as_string())
+            })
+        })
+        .collect()
+}
+
+//The paths of more than one segment in `body`, in source order. Those that start with a module name
+//an item of it, `util::twice` in `util::twice(4)`
+fn item_paths(body: &[Stmt]) -> Vec<&[Token]> {
+    let mut paths = Vec::new();
+    for stmt in body {
+        stmt_paths(stmt, &mut paths);
+    }
+    paths
+}
+
+fn stmt_paths<'a>(stmt: &'a Stmt, paths: &mut Vec<&'a [Token]>) {
+    match stmt {
+        Stmt::Item(Item::Fn(decl)) => decl.body.iter().for_each(|stmt| stmt_paths(stmt, paths)),
+        Stmt::Item(Item::Impl(ImplDecl { methods, .. }) | Item::Trait(TraitDecl { provided: methods, .. })) => {
+            for method in methods {
+                method.body.iter().for_each(|stmt| stmt_paths(stmt, paths));
+            }
+        }
+        Stmt::Item(_) | Stmt::Return { value: None, .. } | Stmt::Break(_) => {}
+        Stmt::Let { initializer: expr, .. }
+        | Stmt::Expression(expr)
+        | Stmt::Tail(expr)
+        | Stmt::Return { value: Some(expr), .. } => expr_paths(expr, paths),
+        Stmt::Print { args, .. } => args.iter().for_each(|arg| expr_paths(arg, paths)),
+        Stmt::Block(body) => body.iter().for_each(|stmt| stmt_paths(stmt, paths)),
+        Stmt::If { condition, then_branch, else_branch } => {
+            expr_paths(condition, paths);
+            then_branch.iter().for_each(|stmt| stmt_paths(stmt, paths));
+            if let Some(else_branch) = else_branch {
+                stmt_paths(else_branch, paths);
+            }
+        }
+        Stmt::While { condition: expr, body } | Stmt::For { iterable: expr, body, .. } => {
+            expr_paths(expr, paths);
+            body.iter().for_each(|stmt| stmt_paths(stmt, paths));
+        }
+    }
+}
+
+fn expr_paths<'a>(expr: &'a Expr, paths: &mut Vec<&'a [Token]>) {
+    match expr {
+        Expr::Path(segments) if segments.len() > 1 => paths.push(segments),
+        Expr::Literal { .. } | Expr::Variable(_) | Expr::Path(_) | Expr::SelfValue(_) => {}
+        Expr::StructLiteral { fields, .. } => fields.iter().for_each(|(_, value)| expr_paths(value, paths)),
+        Expr::Get { object: expr, .. }
+        | Expr::Assign { value: expr, .. }
+        | Expr::Unary { right: expr, .. }
+        | Expr::Cast { value: expr, .. }
+        | Expr::Grouping(expr)
+        | Expr::Try { value: expr, .. } => expr_paths(expr, paths),
+        Expr::Set { object: left, value: right, .. }
+        | Expr::Binary { left, right, .. }
+        | Expr::Index { object: left, index: right, .. }
+        | Expr::Range { start: left, end: right, .. } => {
+            expr_paths(left, paths);
+            expr_paths(right, paths);
+        }
+        Expr::SetIndex { object, index, value, .. } => {
+            expr_paths(object, paths);
+            expr_paths(index, paths);
+            expr_paths(value, paths);
+        }
+        Expr::Tuple { elements, .. } | Expr::Array { elements, .. } => {
+            elements.iter().for_each(|element| expr_paths(element, paths))
+        }
+        Expr::Match { scrutinee, arms, .. } => {
+            expr_paths(scrutinee, paths);
+            arms.iter().for_each(|arm| arm_paths(&arm.body, paths));
+        }
+        Expr::Call { callee, args, .. } => {
+            expr_paths(callee, paths);
+            args.iter().for_each(|arg| expr_paths(arg, paths));
+        }
+        Expr::Closure { body, .. } => arm_paths(body, paths),
+    }
+}
+
+fn arm_paths<'a>(body: &'a ArmBody, paths: &mut Vec<&'a [Token]>) {
+    match body {
+        ArmBody::Expr(expr) => expr_paths(expr, paths),
+        ArmBody::Block(body) => body.iter().for_each(|stmt| stmt_paths(stmt, paths)),
+    }
+}
+
+/// Whether `module` is `ancestor` or inside it. What a module does not make public can only be used there.
+pub fn is_within(module: &str, ancestor: &str) -> bool {
+    ancestor.is_empty() || module == ancestor || module.starts_with(&format!("{ancestor}::"))
+}
+
+/// The root module is written `crate` in paths.
+pub fn display(path: &str) -> &str {
+    if path.is_empty() {
+        "crate"
+    } else {
+        path
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}::{name}")
+    }
+}
+
+fn parent(path: &str) -> Option<&str> {
+    if path.is_empty() {
+        return None;
+    }
+    Some(path.rsplit_once("::").map_or("", |(parent, _)| parent))
+}
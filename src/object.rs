@@ -1,4 +1,3 @@
-use std::cmp::*;
 use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -6,9 +5,6 @@ pub enum Object {
     Num(f64),
     Str(String),
     Bool(bool),
-    Nil,
-    ArithmeticError,
-    NumsOrStringsError,
 }
 
 impl fmt::Display for Object {
@@ -23,8 +19,6 @@ impl fmt::Display for Object {
                     write!(f, "false")
                 }
             }
-            Object::Nil => write!(f, "nil"),
-            _ => panic!("Should not be trying to print this"),
         }
     }
 }
@@ -1,179 +1,95 @@
 use crate::error::*;
 use crate::ast::*;
+use crate::diagnostic::Diagnostics;
 use crate::token::*;
 use crate::token_type::*;
 use crate::object::*;
-use crate::typechecker::*;
-use crate::types::*;
+use crate::types::prelude_enum;
 
 #[derive(Debug)]
 pub struct Parser<'a> {
     tokens: &'a [Token],
+    diagnostics: &'a mut Diagnostics,
     current: usize,
-    had_error: bool,
-    ast_tokens: Vec<AstToken>,
-    typechecker: TypeChecker,
+    //how many blocks deep the parser is, recovering from an error stops at the end of the block
+    blocks: usize,
+    //set while parsing an if/while/for/match head, where `Name {` starts the body
+    no_struct_literal: bool,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &[Token]) -> Parser {
+    pub fn new(tokens: &'a [Token], diagnostics: &'a mut Diagnostics) -> Parser<'a> {
         Parser {
             tokens,
+            diagnostics,
             current: 0,
-            had_error: false,
-            ast_tokens: Vec::new(),
-            typechecker: TypeChecker::new(),
-        }
-    }
-
-    fn test_last(&mut self) {
-        let string_type = Types::new(RustScriptType::String);
-        let number_type = Types::new(RustScriptType::Number);
-        let num_var_x = AstType::NumberType(String::from("x"));
-        let num_var_y = AstType::NumberType(String::from("y"));
-        let num_var_str_x = AstType::StringType(String::from("x"));
-        
-        // Number, String
-        // self.typechecker.test(vec![AstType::Number], number_type.clone());
-        // self.typechecker.test(vec![AstType::String], string_type.clone());
-        // // Binary
-        // self.typechecker.test(vec![AstType::Number, AstType::Plus, AstType::Number], number_type.clone());
-        // self.typechecker.test(vec![AstType::Number, AstType::Slash, AstType::Number], number_type.clone());
-        // self.typechecker.test(vec![AstType::Number, AstType::Star, AstType::Number], number_type.clone());
-        // self.typechecker.test(vec![AstType::Number, AstType::Minus, AstType::Number], number_type.clone());
-        // self.typechecker.test(vec![AstType::String, AstType::Plus, AstType::String], string_type.clone());
-
-        // // Variable
-        // self.typechecker.test(vec![AstType::Let, num_var_x.clone(), AstType::Number], number_type.clone());
-        // self.typechecker.test(vec![num_var_x.clone()], number_type.clone());
-
-        // self.typechecker.test(
-        //     vec![AstType::LeftBrace,
-        //         //let x = Number;
-        //         AstType::Let,
-        //         num_var_x.clone(),
-        //         AstType::Number,
-        //         AstType::SemiColon,
-        //         //let y = Number;
-        //         AstType::Let,
-        //         num_var_y.clone(),
-        //         AstType::Number,
-        //         AstType::SemiColon,
-        //         //x * Number + y
-        //         num_var_x.clone(),
-        //         AstType::Star,
-        //         AstType::Number,
-        //         AstType::Plus,
-        //         num_var_y.clone(),
-        //         AstType::SemiColon,
-        //         //}
-        //         AstType::RightBrace,
-        //     ], number_type.clone(),
-        // );
-
-        // self.typechecker.test(
-        //     vec![
-        //         //{
-        //         AstType::LeftBrace,
-        //         // //let x Number;
-        //         AstType::Let,
-        //         num_var_x.clone(),
-        //         AstType::Number,
-        //         AstType::SemiColon,
-        //         // //leftBrace
-        //         AstType::LeftBrace,
-        //         //let 'x' = String;
-        //         AstType::Let,
-        //         num_var_str_x.clone(),
-        //         AstType::String,
-        //         AstType::SemiColon,
-        //         //'x' + string;
-        //         num_var_str_x.clone(),
-        //         AstType::Plus,
-        //         AstType::String,
-        //         AstType::SemiColon,
-        //         // }
-        //         AstType::RightBrace,
-        //         //x - Number
-        //         num_var_x.clone(),
-        //         AstType::Minus,
-        //         AstType::Number,
-        //         AstType::SemiColon,
-        //     ], number_type.clone()
-        // );
-
-        // self.typechecker.test(
-        //     vec![
-        //         //{
-        //         AstType::LeftBrace,
-        //         // //let x Number;
-        //         AstType::Let,
-        //         num_var_x.clone(),
-        //         AstType::Number,
-        //         AstType::SemiColon,
-        //         // {
-        //         AstType::LeftBrace,
-        //         //let y = Number;
-        //         AstType::Let,
-        //         num_var_y.clone(),
-        //         AstType::Number,
-        //         AstType::SemiColon,
-        //         //x + y;
-        //         num_var_x.clone(),
-        //         AstType::Plus,
-        //         num_var_y.clone(),
-        //         AstType::SemiColon,
-        //         // }
-        //         AstType::RightBrace,
-        //         AstType::RightBrace,
-        //     ], number_type.clone()
-        // );
-
-        self.typechecker.test(
-            vec![
-                //{
-                AstType::LeftBrace,
-                // //let x Number;
-                AstType::Let,
-                num_var_x.clone(),
-                AstType::Number,
-                AstType::SemiColon,
-                // {
-                AstType::LeftBrace,
-                //let y = Number;
-                AstType::Let,
-                num_var_y.clone(),
-                AstType::Number,
-                AstType::SemiColon,
-                //set x [x + y]
-                AstType::Set,
-                num_var_x.clone(),
-                num_var_x.clone(),
-                AstType::Plus,
-                num_var_y.clone(),
-                AstType::SemiColon,
-                // }
-                AstType::RightBrace,
-                AstType::RightBrace,
-            ], number_type.clone()
-        );
-        
-
-    }
-
-
-    
-    pub fn parse(&mut self) -> Result<&Vec<AstToken>, Problem> { 
+            blocks: 0,
+            no_struct_literal: false,
+        }
+    }
+
+    //Declarations that fail to parse are recorded and left out, so one mistake
+    //does not hide the errors after it
+    pub fn parse(&mut self) -> Program {
+        let attributes = match self.inner_attributes() {
+            Ok(attributes) => attributes,
+            Err(problem) => {
+                self.diagnostics.record(problem);
+                Vec::new()
+            }
+        };
+
+        let mut body = Vec::new();
         while !self.is_at_end() {
-            self.declaration(); 
+            if let Some(stmt) = self.declaration() {
+                body.push(stmt);
+            }
         }
-        if self.had_error {
-            Err(Problem::fail())
-        } else {
-            Ok(&self.ast_tokens)
+        Program { attributes, body }
+    }
+
+    //`#![name(..)]` attributes, they can only come first in a file
+    fn inner_attributes(&mut self) -> Result<Vec<Attribute>, Problem> {
+        let mut attributes = Vec::new();
+        while self.check(TokenType::Hash) && self.check_next(TokenType::Bang) {
+            self.advance();
+            self.advance();
+            attributes.push(self.attribute()?);
+        }
+        Ok(attributes)
+    }
+
+    //`#[name(..)]` attributes in front of a function or an extern block
+    fn attributes(&mut self) -> Result<Vec<Attribute>, Problem> {
+        let mut attributes = Vec::new();
+        while self.is_match(&[TokenType::Hash]) {
+            attributes.push(self.attribute()?);
         }
+        Ok(attributes)
+    }
+
+    fn attribute(&mut self) -> Result<Attribute, Problem> {
+        self.consume(TokenType::LeftBracket, "Jparser: Expect '[' after '#'.")?;
+        let name = self.consume(TokenType::Identifier, "Jparser: Expect attribute name.")?;
+        let mut args = Vec::new();
+        if self.is_match(&[TokenType::LeftParen]) {
+            loop {
+                args.push(self.consume(TokenType::Identifier, "Jparser: Expect name in attribute arguments.")?);
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightParen, "Jparser: Expect ')' after attribute arguments.")?;
+        }
+        let value = if self.is_match(&[TokenType::Assign]) {
+            Some(self.consume(TokenType::String, "Jparser: Expect string after '=' in attribute.")?)
+        } else {
+            None
+        };
+        self.consume(TokenType::RightBracket, "Jparser: Expect ']' after attribute.")?;
+        Ok(Attribute { name, args, value })
     }
-   
+
     fn consume(&mut self, ttype: TokenType, message: &str) -> Result<Token, Problem> {
         if self.check(ttype) {
             Ok(self.advance().dup())
@@ -184,21 +100,33 @@ impl<'a> Parser<'a> {
     }
 
     fn error(&mut self, token: &Token, message: &str) -> Problem {
-        self.had_error = true;
         Problem::parse_error(token, message)
     }
 
     fn synchronize(&mut self) {
-        self.advance();
+        //a block's closing brace is left for the block, so it can carry on after the error
+        if self.blocks == 0 || !self.check(TokenType::RightBrace) {
+            self.advance();
+        }
 
         while !self.is_at_end() {
             if self.previous().is(TokenType::SemiColon) {
                 return;
             }
+            if self.blocks > 0 && self.check(TokenType::RightBrace) {
+                return;
+            }
 
             if matches!(
                 self.peek().token_type(),
-                TokenType::Class
+                TokenType::Struct
+                    | TokenType::Pub
+                    | TokenType::Mod
+                    | TokenType::Use
+                    | TokenType::Extern
+                    | TokenType::Impl
+                    | TokenType::Trait
+                    | TokenType::Enum
                     | TokenType::Fn
                     | TokenType::Let
                     | TokenType::For
@@ -233,12 +161,8 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn check_further(&self, ttype: TokenType) -> bool {
-        if self.is_at_end() {
-            false
-        } else {
-            self.peek_further().is(ttype)
-        }
+    fn check_next(&self, ttype: TokenType) -> bool {
+        self.tokens.get(self.current + 1).is_some_and(|token| token.is(ttype))
     }
 
     fn advance(&mut self) -> &Token {
@@ -252,10 +176,6 @@ impl<'a> Parser<'a> {
         self.peek().is(TokenType::Eof)
     }
 
-    fn peek_further(&self) -> &Token {
-        self.tokens.get(self.current + 1).unwrap()
-    }
-
     fn peek(&self) -> &Token {
         self.tokens.get(self.current).unwrap()
     }
@@ -264,423 +184,1076 @@ impl<'a> Parser<'a> {
         self.tokens.get(self.current - 1).unwrap()
     }
 
-    fn add_token(&mut self, atype: AstType) {
-        self.add_token_object(atype, None);
-    }
-    fn add_token_with_value(&mut self, atype: AstType, lexeme: String) {
-        self.ast_tokens.push(AstToken::new(atype, lexeme, None));
-    }
-
-    fn add_token_object(&mut self, atype: AstType, literal: Option<Object>) {
-        let lexeme = self.previous().as_string();
-        self.ast_tokens.push(AstToken::new(atype, lexeme, literal));
-    }
-
-    fn declaration(&mut self) -> Result<(), Problem> {
-        //go to 3 different functions + statement based on is_match
-        let result = if self.is_match(&[TokenType::Class]) {
-            self.class_declaration()
-        } else if self.is_match(&[TokenType::Fn]) {
-            self.function("function")
+    fn declaration(&mut self) -> Option<Stmt> {
+        let result = if self.check(TokenType::Hash) {
+            self.attributed_item()
+        } else if self.is_match(&[TokenType::Pub]) {
+            self.item(true)
+        } else if self.check(TokenType::Use) || self.is_item(self.peek().token_type()) {
+            self.item(false)
         } else if self.is_match(&[TokenType::Let]) {
             self.var_declaration()
-        } else if self.check(TokenType::For) || 
-            self.check(TokenType::If) || 
-            self.check(TokenType::Print) || 
-            self.check(TokenType::Return) ||
-            self.check(TokenType::While) || 
-            self.check(TokenType::Break) {
-            self.statement()
-        } else if self.check(TokenType::Identifier) && self.check_further(TokenType::LeftParen) {
-            self.function_call()
         } else {
-            Err(Problem::fail())
+            self.statement()
         };
-        
-        if result.is_err() {
-            self.synchronize();
+
+        match result {
+            Ok(stmt) => Some(stmt),
+            Err(problem) => {
+                self.diagnostics.record(problem);
+                self.synchronize();
+                None
+            }
         }
-    
-        result
     }
 
-   
+    fn attributed_item(&mut self) -> Result<Stmt, Problem> {
+        let attributes = self.attributes()?;
+        if self.is_match(&[TokenType::Extern]) {
+            return self.extern_declaration(attributes);
+        }
+        let public = self.is_match(&[TokenType::Pub]);
+        self.consume(TokenType::Fn, "Jparser: Expect 'fn' or 'extern' after attributes.")?;
+        let mut decl = self.function("function")?;
+        decl.attributes = attributes;
+        decl.public = public;
+        Ok(Stmt::Item(Item::Fn(Box::new(decl))))
+    }
+
+    fn is_item(&self, token_type: TokenType) -> bool {
+        matches!(
+            token_type,
+            TokenType::Struct
+                | TokenType::Impl
+                | TokenType::Trait
+                | TokenType::Enum
+                | TokenType::Fn
+                | TokenType::Mod
+                | TokenType::Extern
+        )
+    }
+
+    //An item, `pub` in front of it lets other modules use it
+    fn item(&mut self, public: bool) -> Result<Stmt, Problem> {
+        let token_type = self.peek().token_type();
+        if public && (matches!(token_type, TokenType::Impl | TokenType::Extern) || !self.is_item(token_type)) {
+            let peek = self.peek().dup();
+            return Err(self.error(&peek, "Jparser: Expect 'fn', 'struct', 'enum', 'trait' or 'mod' after 'pub'."));
+        }
+        self.advance();
+        match token_type {
+            TokenType::Struct => self.struct_declaration(public),
+            TokenType::Impl => self.impl_declaration(),
+            TokenType::Trait => self.trait_declaration(public),
+            TokenType::Enum => self.enum_declaration(public),
+            TokenType::Mod => self.mod_declaration(public),
+            TokenType::Use => self.use_declaration(),
+            TokenType::Extern => self.extern_declaration(Vec::new()),
+            _ => {
+                let mut decl = self.function("function")?;
+                decl.public = public;
+                Ok(Stmt::Item(Item::Fn(Box::new(decl))))
+            }
+        }
+    }
 
-    fn param_annotation(&mut self) ->  Result<(), Problem> {
-        self.consume(TokenType::Identifier, "Param type Required")?;
-        self.add_token(AstType::Identifier);
+    fn param_annotation(&mut self) -> Result<Param, Problem> {
+        let mutable = self.is_match(&[TokenType::Mut]);
+        let name = self.consume(TokenType::Identifier, "Param type Required")?;
         self.consume(TokenType::Annotation, "Param type must be annotated with : [variable name: variable type] ")?;
-        
+        let annotation = self.type_annotation()?;
+
+        Ok(Param { name, mutable, annotation })
+    }
+
+    //A type is one of the built in type keywords, the name of a user defined type, possibly with
+    //type arguments, a tuple type `(num, str)`, an array type `[num]` or a function type `fn(num) -> num`
+    fn type_annotation(&mut self) -> Result<TypeAnnotation, Problem> {
         let var_type = self.peek().token_type();
-        if matches!(var_type, TokenType::NumberType | TokenType::StringType | TokenType::BoolType) {
-            self.consume(var_type, "must have type name")?;
+        if matches!(
+            var_type,
+            TokenType::NumberType | TokenType::StringType | TokenType::BoolType | TokenType::Identifier | TokenType::SelfType
+        ) {
+            let name = self.advance().dup();
+            let args = self.type_arguments()?;
+            if args.is_empty() {
+                return Ok(TypeAnnotation::Named(name));
+            }
+            Ok(TypeAnnotation::Generic { name, args })
+        } else if self.is_match(&[TokenType::LeftParen]) {
+            let paren = self.previous().dup();
+            let mut elements = Vec::new();
+            let mut is_tuple = true;
+            while !self.check(TokenType::RightParen) && !self.is_at_end() {
+                elements.push(self.type_annotation()?);
+                is_tuple = self.is_match(&[TokenType::Comma]);
+                if !is_tuple {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightParen, "Jparser: Expect ')' after tuple element types.")?;
+            //`(num)` is just `num`, a one element tuple is written `(num,)`
+            if !is_tuple && elements.len() == 1 {
+                return Ok(elements.remove(0));
+            }
+            Ok(TypeAnnotation::Tuple { paren, elements })
+        } else if self.is_match(&[TokenType::LeftBracket]) {
+            let bracket = self.previous().dup();
+            let element = Box::new(self.type_annotation()?);
+            self.consume(TokenType::RightBracket, "Jparser: Expect ']' after element type.")?;
+            Ok(TypeAnnotation::Array { bracket, element })
+        } else if self.is_match(&[TokenType::Fn]) {
+            let keyword = self.previous().dup();
+            self.consume(TokenType::LeftParen, "Jparser: Expect '(' after 'fn' in function type.")?;
+            let mut params = Vec::new();
+            if !self.check(TokenType::RightParen) {
+                params.push(self.type_annotation()?);
+                while self.is_match(&[TokenType::Comma]) {
+                    params.push(self.type_annotation()?);
+                }
+            }
+            self.consume(TokenType::RightParen, "Jparser: Expect ')' after function type parameters.")?;
+            let return_type = if self.is_match(&[TokenType::Arrow]) {
+                Some(Box::new(self.type_annotation()?))
+            } else {
+                None
+            };
+            Ok(TypeAnnotation::Function { keyword, params, return_type })
+        } else {
+            let peek = self.peek().dup();
+            Err(self.error(&peek, "Jparser: Expect type name."))
         }
+    }
 
-        Ok(())
+    //`<A, B: Shape + Named>`, the type parameters of a generic function, struct or impl block and the
+    //traits they are bound by, none without the `<`
+    //The types a generic type is given, `<num, T>`, none when there is no `<`
+    fn type_arguments(&mut self) -> Result<Vec<TypeAnnotation>, Problem> {
+        let mut args = Vec::new();
+        if !self.is_match(&[TokenType::Less]) {
+            return Ok(args);
+        }
+        args.push(self.type_annotation()?);
+        while self.is_match(&[TokenType::Comma]) {
+            args.push(self.type_annotation()?);
+        }
+        self.consume(TokenType::Greater, "Jparser: Expect '>' after type arguments.")?;
+        Ok(args)
     }
 
+    fn generics(&mut self) -> Result<Vec<GenericParam>, Problem> {
+        let mut generics = Vec::new();
+        if !self.is_match(&[TokenType::Less]) {
+            return Ok(generics);
+        }
+        loop {
+            let name = self.consume(TokenType::Identifier, "Jparser: Expect type parameter name.")?;
+            let mut bounds = Vec::new();
+            if self.is_match(&[TokenType::Annotation]) {
+                bounds.push(self.consume(TokenType::Identifier, "Jparser: Expect trait name after ':'.")?);
+                while self.is_match(&[TokenType::Plus]) {
+                    bounds.push(self.consume(TokenType::Identifier, "Jparser: Expect trait name after '+'.")?);
+                }
+            }
+            generics.push(GenericParam { name, bounds });
+            if !self.is_match(&[TokenType::Comma]) || self.check(TokenType::Greater) {
+                break;
+            }
+        }
+        self.consume(TokenType::Greater, "Jparser: Expect '>' after type parameters.")?;
+        Ok(generics)
+    }
 
-    fn function(&mut self, kind: &str) -> Result<(), Problem> {
+    fn function(&mut self, kind: &str) -> Result<FnDecl, Problem> {
+        let mut decl = self.signature(kind)?;
 
-        let mut fm = false;
-        if self.peek().as_string() == "main" {
-            self.add_token(AstType::LeftParen);
-            fm = true;
-        }
+        //left brace {
+        self.consume(TokenType::LeftBrace, &format!("Jparser: Expect '{{' before {kind} body."))?;
+        decl.body = self.block()?;
+        Ok(decl)
+    }
 
-        self.add_token(AstType::Fn);
+    //Everything of a function up to its body, which is left empty
+    fn signature(&mut self, kind: &str) -> Result<FnDecl, Problem> {
+        let name = self.consume(TokenType::Identifier, &format!("Jparser: Expect {kind} name"))?;
+        let generics = self.generics()?;
 
-        self.consume(TokenType::Identifier, &format!("Jparser: Expect {kind} name"))?;
-        self.add_token(AstType::Identifier);
-    
         self.consume(TokenType::LeftParen, &format!("Jparser: Expect '(' after {kind} name."))?;
-        self.add_token(AstType::LeftParen);
-    
 
+        //methods take &self, &mut self, self or mut self first
+        let takes_self = self.check(TokenType::Ampersand)
+            || self.check(TokenType::SelfValue)
+            || (self.check(TokenType::Mut) && self.check_next(TokenType::SelfValue));
+        let mut self_mutable = false;
+        let self_param = if takes_self {
+            self.is_match(&[TokenType::Ampersand]);
+            self_mutable = self.is_match(&[TokenType::Mut]);
+            let token = self.consume(TokenType::SelfValue, "Jparser: Expect 'self' after '&'.")?;
+            if !self.check(TokenType::RightParen) {
+                self.consume(TokenType::Comma, "Jparser: Expect ',' after 'self'.")?;
+            }
+            Some(token)
+        } else {
+            None
+        };
 
+        let mut params = Vec::new();
         if !self.check(TokenType::RightParen) {
-            self.param_annotation()?;
+            params.push(self.param_annotation()?);
 
             while self.is_match(&[TokenType::Comma]) {
-                self.add_token(AstType::Comma);
-                self.param_annotation()?;
+                params.push(self.param_annotation()?);
             }
         }
-        
+
         //Close function paramaters with )
         self.consume(TokenType::RightParen, "Jparser: Expect ')' after parameters.")?;
-        self.add_token(AstType::RightParen);
 
-        //Returns go here
-    
-        //left brace {
-        self.consume(TokenType::LeftBrace, &format!("Jparser: Expect '{{' before {kind} body."))?;
-    
-        // check for block, ie end paramater
-        self.block()?;
+        let return_type = if self.is_match(&[TokenType::Arrow]) {
+            Some(self.type_annotation()?)
+        } else {
+            None
+        };
 
-        if fm == true {
-            self.add_token(AstType::Main);
+        let body = Vec::new();
+        Ok(FnDecl {
+            attributes: Vec::new(),
+            public: false,
+            name,
+            generics,
+            self_param,
+            self_mutable,
+            params,
+            return_type,
+            body,
+        })
+    }
+
+    fn var_declaration(&mut self) -> Result<Stmt, Problem> {
+        let pattern = self.pattern()?;
+        let annotation = if self.is_match(&[TokenType::Annotation]) {
+            Some(self.type_annotation()?)
+        } else {
+            None
+        };
+
+        self.consume(TokenType::Assign, "Jparser: Expect '=' variable assignment required.")?;
+        let initializer = self.expression()?;
+
+        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after variable declaration.")?;
+
+        Ok(Stmt::Let { pattern, annotation, initializer })
+    }
+
+    fn print_statement(&mut self) -> Result<Stmt, Problem> {
+        let keyword = self.previous().dup();
+        self.consume(TokenType::LeftParen, "Print Requires Opening brace")?;
+
+        // ""
+        let format_token = self.consume(TokenType::String, "No string Value for  print")?;
+        let format = match &format_token.literal {
+            Some(print_val) => print_val.to_string(),
+            None => String::new(),
+        };
+
+        //While there's a comma, consume comma, is_match does that automatically
+        let mut args = Vec::new();
+        while self.is_match(&[TokenType::Comma]) {
+            args.push(self.expression()?);
         }
 
-        Ok(())
+        self.consume(TokenType::RightParen, "Print Requires Closing brace")?;
+        self.consume(TokenType::SemiColon, "Print value must end with ;")?;
+
+        Ok(Stmt::Print { keyword, format, args })
     }
 
-    fn function_call_params(&mut self) -> Result<(), Problem> {
-        match self.peek().token_type() {
-            //replace with variables
-            TokenType::Identifier => {
-                self.consume(TokenType::Identifier, "Expect paramater name")?;
-                self.add_token(AstType::Identifier);
-            }
+    fn block(&mut self) -> Result<Vec<Stmt>, Problem> {
+        let mut statements = Vec::new();
 
-            TokenType::String => {
-                self.consume(TokenType::String, "Expect paramater name")?;
-                self.add_token(AstType::String);
+        self.blocks += 1;
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
             }
-
-            _ => println!("not covered yet"),
         }
+        self.blocks -= 1;
+
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after block.")?;
 
-        Ok(())
+        Ok(statements)
     }
 
-    fn function_call(&mut self) -> Result<(), Problem>  {
-        
-        //Function name 
-        self.consume(TokenType::Identifier, "Function call name not added")?;
-        self.add_token(AstType::Identifier);
-        
-        //Left Paranthesis (
-        self.consume(TokenType::LeftParen, "Function Opening brace not added")?;
-        self.add_token(AstType::LeftParen);
+    fn statement(&mut self) -> Result<Stmt, Problem> {
+        if self.is_match(&[TokenType::Break]) {
+            let keyword = self.previous().dup();
+            self.consume(TokenType::SemiColon, "Jparser: Expect ';' after break statement.")?;
+            return Ok(Stmt::Break(keyword));
+        }
 
+        if self.is_match(&[TokenType::For]) {
+            return self.for_statement();
+        }
 
-        if !self.check(TokenType::RightParen) {
-            //Take function call params and while we get paramaters, keep getting them
-            self.function_call_params()?;
-            while self.is_match(&[TokenType::Comma]) {
-                self.function_call_params()?;
+        if self.is_match(&[TokenType::If]) {
+            return self.if_statement();
+        }
+
+        if self.is_match(&[TokenType::Print]) {
+            return self.print_statement();
+        }
+
+        if self.is_match(&[TokenType::Return]) {
+            return self.return_statement();
+        }
+
+        if self.is_match(&[TokenType::While]) {
+            return self.while_statement();
+        }
+
+        //closure block
+        if self.is_match(&[TokenType::LeftBrace]) {
+            return Ok(Stmt::Block(self.block()?));
+        }
+
+        //like a block, a match statement does not need a trailing ;
+        if self.check(TokenType::Match) {
+            let expr = self.expression()?;
+            if !self.is_match(&[TokenType::SemiColon]) && self.check(TokenType::RightBrace) {
+                return Ok(Stmt::Tail(expr));
             }
+            return Ok(Stmt::Expression(expr));
         }
 
-        //function call end parenthesis )
-        self.consume(TokenType::RightParen, "Close function call body")?;
-        self.add_token(AstType::RightParen);
+        self.expression_statement()
+    }
+
+    // if condition { ... } else if condition { ... } else { ... }
+    fn if_statement(&mut self) -> Result<Stmt, Problem> {
+        let condition = self.condition()?;
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' after if condition.")?;
+        let then_branch = self.block()?;
+
+        let else_branch = if self.is_match(&[TokenType::Else]) {
+            if self.is_match(&[TokenType::If]) {
+                Some(Box::new(self.if_statement()?))
+            } else {
+                self.consume(TokenType::LeftBrace, "Jparser: Expect '{' or 'if' after 'else'.")?;
+                Some(Box::new(Stmt::Block(self.block()?)))
+            }
+        } else {
+            None
+        };
+
+        Ok(Stmt::If { condition, then_branch, else_branch })
+    }
+
+    fn return_statement(&mut self) -> Result<Stmt, Problem> {
+        let keyword = self.previous().dup();
+        let value = if self.check(TokenType::SemiColon) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+
+        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after return value.")?;
+
+        Ok(Stmt::Return { keyword, value })
+    }
 
-        //Check for return environment if not there
-        self.consume(TokenType::SemiColon, "End function call with semi-colon")?;
-        self.add_token(AstType::SemiColon);
+    fn while_statement(&mut self) -> Result<Stmt, Problem> {
+        let condition = self.condition()?;
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' after while condition.")?;
+        let body = self.block()?;
 
-        Ok(())
+        Ok(Stmt::While { condition, body })
     }
 
-    fn var_values(&mut self) -> Result<(), Problem> {
-        if self.is_match(&[TokenType::String]) {
-            self.add_token(AstType::String);
-        } else if self.is_match(&[TokenType::Number]) {
-            self.add_token(AstType::Number);
+    // for i in 0..10 { ... }
+    fn for_statement(&mut self) -> Result<Stmt, Problem> {
+        let variable = self.consume(TokenType::Identifier, "Jparser: Expect loop variable name after 'for'.")?;
+        self.consume(TokenType::In, "Jparser: Expect 'in' after loop variable.")?;
+
+        //a range `start..end` or a vector
+        let start = self.condition()?;
+        let iterable = if self.is_match(&[TokenType::DotDot, TokenType::DotDotEqual]) {
+            let inclusive = self.previous().is(TokenType::DotDotEqual);
+            let end = self.condition()?;
+            Expr::Range { start: Box::new(start), end: Box::new(end), inclusive }
         } else {
-            println!("Unexpect variable name");
+            start
+        };
+
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' after for clauses.")?;
+        let body = self.block()?;
+
+        Ok(Stmt::For { variable, iterable, body })
+    }
+
+    // enum Shape { Circle(num), Rect(num, num) }
+    fn enum_declaration(&mut self, public: bool) -> Result<Stmt, Problem> {
+        let name = self.consume(TokenType::Identifier, "Jparser: Expect enum name.")?;
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' before enum body.")?;
+
+        let mut variants = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let variant = self.consume(TokenType::Identifier, "Jparser: Expect variant name.")?;
+
+            let mut fields = Vec::new();
+            if self.is_match(&[TokenType::LeftParen]) {
+                fields.push(self.type_annotation()?);
+                while self.is_match(&[TokenType::Comma]) {
+                    fields.push(self.type_annotation()?);
+                }
+                self.consume(TokenType::RightParen, "Jparser: Expect ')' after variant fields.")?;
+            }
+            variants.push(Variant { name: variant, fields });
+
+            //trailing comma after the last variant is optional
+            if !self.is_match(&[TokenType::Comma]) {
+                break;
+            }
         }
-        Ok(())
+
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after enum body.")?;
+
+        Ok(Stmt::Item(Item::Enum(EnumDecl { public, name, variants })))
     }
-    
-    fn var_declaration(&mut self) -> Result<(), Problem> {
-        
-        //let
-        self.add_token(AstType::Let);
-    
-        //variable name
-        let name = self.consume(TokenType::Identifier, "Jparser: Expect variable name.")?;
 
-        // let name_info = name.as_string();
-        // println!("{:?}", &name_info);
+    // struct Point { x: num, y: num }
+    fn struct_declaration(&mut self, public: bool) -> Result<Stmt, Problem> {
+        let name = self.consume(TokenType::Identifier, "Jparser: Expect struct name.")?;
+        let generics = self.generics()?;
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' before struct body.")?;
 
-        self.add_token_object(AstType::Identifier, name.literal);
-    
-        self.consume(TokenType::Assign, "Jparser: Expect '=' variable assignment required.")?;
-        self.add_token(AstType::Assign);
+        let mut fields = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            fields.push(self.field()?);
+
+            //trailing comma after the last field is optional
+            if !self.is_match(&[TokenType::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after struct body.")?;
+
+        Ok(Stmt::Item(Item::Struct(StructDecl { public, name, generics, fields })))
+    }
 
-        self.test_last();
+    fn field(&mut self) -> Result<Field, Problem> {
+        let public = self.is_match(&[TokenType::Pub]);
+        let name = self.consume(TokenType::Identifier, "Jparser: Expect field name.")?;
+        self.consume(TokenType::Annotation, "Jparser: Expect ':' after field name.")?;
+        let annotation = self.type_annotation()?;
+        Ok(Field { public, name, annotation })
+    }
 
+    // impl Point { fn new(..) -> Point { .. } fn len(&self) -> num { .. } }
+    // impl<A, B> Pair<A, B> { .. }
+    // impl Shape for Circle { .. }
+    //The methods of a struct are emitted inside its class, so they are all known where the program
+    //starts. An impl block inside a block is reported once it is parsed, the code after it still is
+    fn impl_declaration(&mut self) -> Result<Stmt, Problem> {
+        let keyword = self.previous().dup();
+        let generics = self.generics()?;
+        let mut name = self.consume(TokenType::Identifier, "Jparser: Expect type name after 'impl'.")?;
+        let mut trait_name = None;
+        if self.is_match(&[TokenType::For]) {
+            trait_name = Some(name);
+            name = self.consume(TokenType::Identifier, "Jparser: Expect type name after 'for'.")?;
+        }
+        let args = self.type_arguments()?;
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' before impl body.")?;
 
-        // let test = self.typechecker.test([TokenType::Let, TokenType::Identifier, TokenType::Number].to_vec(), types);
+        let mut methods = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let attributes = self.attributes()?;
+            let public = self.is_match(&[TokenType::Pub]);
+            self.consume(TokenType::Fn, "Jparser: Expect 'fn' in impl body.")?;
+            let mut method = self.function("method")?;
+            method.attributes = attributes;
+            method.public = public;
+            methods.push(method);
+        }
 
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after impl body.")?;
+        if self.blocks > 0 {
+            let problem = self.error(&keyword, "Jparser: Impl blocks can only be declared at the top level of a file.");
+            self.diagnostics.record(problem);
+        }
 
-        
+        Ok(Stmt::Item(Item::Impl(ImplDecl { generics, trait_name, name, args, methods })))
+    }
 
-        self.var_values()?;
+    // trait Shape { fn area(&self) -> num; fn describe(&self) -> str { .. } }
+    fn trait_declaration(&mut self, public: bool) -> Result<Stmt, Problem> {
+        let name = self.consume(TokenType::Identifier, "Jparser: Expect trait name.")?;
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' before trait body.")?;
 
-        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after variable declaration.")?;
-        self.add_token(AstType::SemiColon);
-         
-        Ok(())
+        let mut required = Vec::new();
+        let mut provided = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let attributes = self.attributes()?;
+            self.consume(TokenType::Fn, "Jparser: Expect 'fn' in trait body.")?;
+            let mut method = self.signature("method")?;
+            method.attributes = attributes;
+            method.public = true;
+            //a method without a body must be defined by every implementation
+            if self.is_match(&[TokenType::SemiColon]) {
+                required.push(method);
+            } else {
+                self.consume(TokenType::LeftBrace, "Jparser: Expect '{' or ';' after method signature.")?;
+                method.body = self.block()?;
+                provided.push(method);
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after trait body.")?;
+
+        Ok(Stmt::Item(Item::Trait(TraitDecl { public, name, required, provided })))
     }
 
-    fn print_statement(&mut self) -> Result<(), Problem> {
-        // console.log
-        self.add_token(AstType::Print);
-        
-        // (
-        self.consume(TokenType::LeftParen, "Print Requires Opening brace")?;
-        self.add_token(AstType::LeftParen);
+    // mod shapes;
+    fn mod_declaration(&mut self, public: bool) -> Result<Stmt, Problem> {
+        let keyword = self.previous().dup();
+        if self.blocks > 0 {
+            return Err(self.error(&keyword, "Jparser: Modules can only be declared at the top level of a file."));
+        }
+        let name = self.consume(TokenType::Identifier, "Jparser: Expect module name.")?;
+        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after module name.")?;
+        Ok(Stmt::Item(Item::Mod(ModDecl { public, name })))
+    }
 
-        // ""
-        self.consume(TokenType::String, "No string Value for  print")?;
-        
+    // use shapes::Circle;
+    // use crate::shapes::{Circle, area};
+    fn use_declaration(&mut self) -> Result<Stmt, Problem> {
+        let keyword = self.previous().dup();
+        if self.blocks > 0 {
+            return Err(self.error(&keyword, "Jparser: 'use' is only allowed at the top level of a file."));
+        }
 
-        let mut string_value = String::from("");
+        //`crate` can only start a path and `super` can only follow `super`
+        let mut path = Vec::new();
+        let starts = [TokenType::Crate, TokenType::Super, TokenType::Identifier];
+        if !starts.iter().any(|start| self.check(*start)) {
+            let peek = self.peek().dup();
+            return Err(self.error(&peek, "Jparser: Expect 'crate', 'super' or a module name after 'use'."));
+        }
+        path.push(self.advance().dup());
+        self.consume(TokenType::ColonColon, "Jparser: Expect '::' after module name.")?;
+        let mut names = Vec::new();
+        loop {
+            if self.is_match(&[TokenType::LeftBrace]) {
+                loop {
+                    names.push(self.consume(TokenType::Identifier, "Jparser: Expect name to import.")?);
+                    if !self.is_match(&[TokenType::Comma]) || self.check(TokenType::RightBrace) {
+                        break;
+                    }
+                }
+                self.consume(TokenType::RightBrace, "Jparser: Expect '}' after imported names.")?;
+                break;
+            }
+            let follows_super = path.iter().all(|segment| segment.is(TokenType::Super));
+            let segment = if follows_super && self.check(TokenType::Super) && self.check_next(TokenType::ColonColon) {
+                self.advance().dup()
+            } else {
+                self.consume(TokenType::Identifier, "Jparser: Expect name to import.")?
+            };
+            if !self.is_match(&[TokenType::ColonColon]) {
+                names.push(segment);
+                break;
+            }
+            path.push(segment);
+        }
+        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after use declaration.")?;
+        Ok(Stmt::Item(Item::Use(UseDecl { keyword, path, names })))
+    }
+
+    // extern "js" { fn setTimeout(f: fn(), ms: num); }
+    fn extern_declaration(&mut self, attributes: Vec<Attribute>) -> Result<Stmt, Problem> {
+        let keyword = self.previous().dup();
+        if self.blocks > 0 {
+            return Err(self.error(&keyword, "Jparser: Extern blocks can only be declared at the top level of a file."));
+        }
+        let abi = self.consume(TokenType::String, "Jparser: Expect '\"js\"' after 'extern'.")?;
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' before extern block.")?;
+
+        let mut functions = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let attributes = self.attributes()?;
+            self.consume(TokenType::Fn, "Jparser: Expect 'fn' in extern block.")?;
+            let mut decl = self.signature("function")?;
+            decl.attributes = attributes;
+            if let Some(self_param) = &decl.self_param {
+                return Err(self.error(self_param, "Jparser: Extern functions cannot take 'self'."));
+            }
+            self.consume(TokenType::SemiColon, "Jparser: Expect ';' after extern function signature.")?;
+            functions.push(decl);
+        }
+
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after extern block.")?;
+        Ok(Stmt::Item(Item::Extern(ExternDecl { attributes, abi, functions })))
+    }
+
+    fn expression_statement(&mut self) -> Result<Stmt, Problem> {
+        let expr = self.expression()?;
 
-        if let Some(print_val) = &self.previous().literal {
-            string_value = print_val.to_string();
+        //the last expression of a block may leave out the ; to become the block's value
+        if self.check(TokenType::RightBrace) {
+            return Ok(Stmt::Tail(expr));
         }
+        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after expression.")?;
+
+        Ok(Stmt::Expression(expr))
+    }
+
+    fn expression(&mut self) -> Result<Expr, Problem> {
+        self.assignment()
+    }
+
+    //The head of if/while/for/match, where a `{` after a name opens the body, not a struct literal
+    fn condition(&mut self) -> Result<Expr, Problem> {
+        let outer = std::mem::replace(&mut self.no_struct_literal, true);
+        let expr = self.expression();
+        self.no_struct_literal = outer;
+        expr
+    }
 
+    //`|a: num, b| body` where the opening pipe has been consumed. The body is a single expression
+    //unless a return type is given, which like Rust requires a block
+    fn closure(&mut self) -> Result<Expr, Problem> {
+        let pipe = self.previous().dup();
         let mut params = Vec::new();
+        //`||` scans as the or operator, here it is an empty parameter list
+        if pipe.is(TokenType::Or) {
+            return self.closure_body(pipe, params);
+        }
+        if !self.check(TokenType::Pipe) {
+            loop {
+                let mutable = self.is_match(&[TokenType::Mut]);
+                let name = self.consume(TokenType::Identifier, "Jparser: Expect closure parameter name.")?;
+                let annotation = if self.is_match(&[TokenType::Annotation]) {
+                    Some(self.type_annotation()?)
+                } else {
+                    None
+                };
+                params.push(ClosureParam { name, mutable, annotation });
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+        self.consume(TokenType::Pipe, "Jparser: Expect '|' after closure parameters.")?;
+        self.closure_body(pipe, params)
+    }
 
-        //if right paren not deteced. 
-        if !self.check(TokenType::RightParen) {
-            //While there's a comma, consume comma, is_match does that automatically
-            while self.is_match(&[TokenType::Comma]) {
-                let token_next = self.peek().token_type();
-                params.push(format!("${{{}}}", self.peek().as_string())); // if not closing brace ie "{}, )"
-                self.consume(token_next, "Println: Expect paramter value after comma")?;
+    fn closure_body(&mut self, pipe: Token, params: Vec<ClosureParam>) -> Result<Expr, Problem> {
+        if self.is_match(&[TokenType::Arrow]) {
+            let return_type = Some(self.type_annotation()?);
+            self.consume(TokenType::LeftBrace, "Jparser: Expect '{' before closure body with a return type.")?;
+            let body = Box::new(ArmBody::Block(self.block()?));
+            return Ok(Expr::Closure { pipe, params, return_type, body });
+        }
+        let body = if self.is_match(&[TokenType::LeftBrace]) {
+            ArmBody::Block(self.block()?)
+        } else {
+            ArmBody::Expr(self.expression()?)
+        };
+        Ok(Expr::Closure { pipe, params, return_type: None, body: Box::new(body) })
+    }
+
+    //Inside brackets struct literals are unambiguous again
+    fn nested_expression(&mut self) -> Result<Expr, Problem> {
+        let outer = std::mem::replace(&mut self.no_struct_literal, false);
+        let expr = self.expression();
+        self.no_struct_literal = outer;
+        expr
+    }
+
+    //Assignment is right associative and binds looser than every binary operator
+    fn assignment(&mut self) -> Result<Expr, Problem> {
+        let expr = self.binary(0)?;
+
+        if self.is_match(&[TokenType::Assign]) {
+            let equals = self.previous().dup();
+            let value = self.assignment()?;
+
+            match expr {
+                Expr::Variable(name) => return Ok(Expr::Assign { name, value: Box::new(value) }),
+                Expr::Get { object, name } => return Ok(Expr::Set { object, name, value: Box::new(value) }),
+                Expr::Index { object, bracket, index } => {
+                    return Ok(Expr::SetIndex { object, bracket, index, value: Box::new(value) });
+                }
+                _ => {}
             }
+
+            return Err(self.error(&equals, "Jparser: Invalid assignment target."));
         }
 
-        for (index, p) in params.iter().enumerate() {
-            string_value = string_value.replacen("{}", &p, 1);
+        Ok(expr)
+    }
+
+    // Binding power of each binary operator, higher binds tighter.
+    // Every level is left associative: a - b - c parses as (a - b) - c
+    fn precedence(ttype: TokenType) -> Option<u8> {
+        match ttype {
+            TokenType::Or => Some(1),
+            TokenType::And => Some(2),
+            TokenType::Equals | TokenType::BangEqual => Some(3),
+            TokenType::Less | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual => Some(4),
+            TokenType::Plus | TokenType::Minus => Some(5),
+            TokenType::Star | TokenType::Slash => Some(6),
+            _ => None,
         }
+    }
 
-        string_value = format!("`{}`", string_value);
+    //Precedence climbing: keep folding operators into the left operand while they bind
+    //at least as tightly as min_precedence, parsing each right operand one level higher
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, Problem> {
+        let mut left = self.cast()?;
 
-   
+        while let Some(precedence) = Parser::precedence(self.peek().token_type()) {
+            if precedence < min_precedence {
+                break;
+            }
+            let operator = self.advance().dup();
+            let right = self.binary(precedence + 1)?;
+            left = Expr::Binary { left: Box::new(left), operator, right: Box::new(right) };
+        }
 
-        self.add_token_with_value(AstType::String, string_value);
+        Ok(left)
+    }
 
-        self.consume(TokenType::RightParen, "Print Requires Closing brace")?;
-        self.add_token(AstType::RightParen);
+    //`as` binds tighter than every binary operator but looser than unary ones, `-x as i64` casts `-x`
+    fn cast(&mut self) -> Result<Expr, Problem> {
+        let mut expr = self.unary()?;
+        while self.is_match(&[TokenType::As]) {
+            let keyword = self.previous().dup();
+            let target = self.type_annotation()?;
+            expr = Expr::Cast { value: Box::new(expr), keyword, target };
+        }
+        Ok(expr)
+    }
 
-        self.consume(TokenType::SemiColon, "Print value must end with ;")?;
-        self.add_token(AstType::SemiColon);
-        Ok(())
+    fn unary(&mut self) -> Result<Expr, Problem> {
+        if self.is_match(&[TokenType::Bang, TokenType::Minus]) {
+            let operator = self.previous().dup();
+            let right = self.unary()?;
+            return Ok(Expr::Unary { operator, right: Box::new(right) });
+        }
+
+        self.call()
     }
-    
-    fn block(&mut self) -> Result<(), Problem> {
-        self.add_token(AstType::LeftBrace);
-    
-        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            self.declaration()?;
+
+    fn call(&mut self) -> Result<Expr, Problem> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.is_match(&[TokenType::LeftParen]) {
+                expr = self.finish_call(expr)?;
+            } else if self.is_match(&[TokenType::Dot]) {
+                //a number names a tuple element
+                let name = if self.is_match(&[TokenType::Number]) {
+                    self.previous().dup()
+                } else {
+                    self.consume(TokenType::Identifier, "Jparser: Expect field name after '.'.")?
+                };
+                expr = Expr::Get { object: Box::new(expr), name };
+            } else if self.is_match(&[TokenType::LeftBracket]) {
+                let bracket = self.previous().dup();
+                let index = self.nested_expression()?;
+                self.consume(TokenType::RightBracket, "Jparser: Expect ']' after index.")?;
+                expr = Expr::Index { object: Box::new(expr), bracket, index: Box::new(index) };
+            } else if self.is_match(&[TokenType::Question]) {
+                let question = self.previous().dup();
+                expr = Expr::Try { value: Box::new(expr), question };
+            } else {
+                break;
+            }
         }
-    
-        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after block.")?;
-        self.add_token(AstType::RightBrace);
-    
-        Ok(())
-    }
-    
-    
-    fn statement(&mut self) -> Result<(), Problem> {
-    
-        //if user right breaks, duplicate current token, Jparser: expect ; and return
-        if self.is_match(&[TokenType::Break]) {
-            let token = self.previous().dup();
-            self.consume(TokenType::SemiColon, "Jparser: Expect ';' after break statement.")?;
-            return Ok(())
+
+        Ok(expr)
+    }
+
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, Problem> {
+        let mut args = Vec::new();
+        if !self.check(TokenType::RightParen) {
+            args.push(self.nested_expression()?);
+            while self.is_match(&[TokenType::Comma]) {
+                args.push(self.nested_expression()?);
+            }
         }
-    
-        //if for then for_statement
-        if self.is_match(&[TokenType::For]) {
-            return self.for_statement();
+
+        let paren = self.consume(TokenType::RightParen, "Jparser: Expect ')' after arguments.")?;
+
+        Ok(Expr::Call { callee: Box::new(callee), paren, args })
+    }
+
+    fn primary(&mut self) -> Result<Expr, Problem> {
+        if self.is_match(&[TokenType::False]) {
+            return Ok(self.literal(Object::Bool(false)));
         }
-    
-        //if if then return if statement
-        if self.is_match(&[TokenType::If]) {
-            return Ok(self.if_statement()?);
+
+        if self.is_match(&[TokenType::True]) {
+            return Ok(self.literal(Object::Bool(true)));
         }
-    
-        if self.is_match(&[TokenType::Print]) {
-            return Ok(self.print_statement()?);
+
+        if self.is_match(&[TokenType::Number, TokenType::String]) {
+            let value = self.previous().literal.clone().expect("the scanner gives numbers and strings their value");
+            return Ok(self.literal(value));
         }
-    
-        if self.is_match(&[TokenType::Return]) {
-            return Ok(self.return_statement()?);
+
+        if self.is_match(&[TokenType::SelfValue]) {
+            return Ok(Expr::SelfValue(self.previous().dup()));
         }
-    
-        if self.is_match(&[TokenType::While]) {
-            return Ok(self.while_statement()?);
+
+        if self.is_match(&[TokenType::Pipe, TokenType::Or]) {
+            return self.closure();
         }
-    
-        //closure block
-        if self.is_match(&[TokenType::LeftBrace]) {
-            return Ok(self.block()?);
+
+        if self.is_match(&[TokenType::LeftBracket]) {
+            let bracket = self.previous().dup();
+            let mut elements = Vec::new();
+            while !self.check(TokenType::RightBracket) && !self.is_at_end() {
+                elements.push(self.nested_expression()?);
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+            self.consume(TokenType::RightBracket, "Jparser: Expect ']' after array elements.")?;
+            return Ok(Expr::Array { bracket, elements });
+        }
+
+        if self.is_match(&[TokenType::SelfType]) {
+            let name = self.previous().dup();
+            self.consume(TokenType::LeftBrace, "Jparser: Expect '{' after 'Self'.")?;
+            return self.struct_literal(name);
+        }
+
+        //`crate::util::twice`, a path that starts at the root module, or at the parent after `super`
+        if (self.check(TokenType::Crate) || self.check(TokenType::Super)) && self.check_next(TokenType::ColonColon) {
+            let mut segments = vec![self.advance().dup()];
+            while self.is_match(&[TokenType::ColonColon]) {
+                let follows_super = segments.iter().all(|segment| segment.is(TokenType::Super));
+                let segment = if follows_super && self.check(TokenType::Super) {
+                    self.advance().dup()
+                } else {
+                    self.consume(TokenType::Identifier, "Jparser: Expect name after '::'.")?
+                };
+                segments.push(segment);
+            }
+            return Ok(Expr::Path(segments));
+        }
+
+        if self.is_match(&[TokenType::Identifier]) {
+            let name = self.previous().dup();
+            if !self.no_struct_literal && self.is_match(&[TokenType::LeftBrace]) {
+                return self.struct_literal(name);
+            }
+            //`Some`, `None`, `Ok` and `Err` are variants even without their enum name
+            if prelude_enum(&name.as_string()).is_some() {
+                return Ok(Expr::Path(vec![name]));
+            }
+            if !self.check(TokenType::ColonColon) {
+                return Ok(Expr::Variable(name));
+            }
+
+            let mut segments = vec![name];
+            while self.is_match(&[TokenType::ColonColon]) {
+                segments.push(self.consume(TokenType::Identifier, "Jparser: Expect name after '::'.")?);
+            }
+            return Ok(Expr::Path(segments));
+        }
+
+        if self.is_match(&[TokenType::LeftParen]) {
+            let paren = self.previous().dup();
+            let expr = self.nested_expression()?;
+            if !self.check(TokenType::Comma) {
+                self.consume(TokenType::RightParen, "Jparser: Expect ')' after expression.")?;
+                return Ok(Expr::Grouping(Box::new(expr)));
+            }
+
+            let mut elements = vec![expr];
+            while self.is_match(&[TokenType::Comma]) {
+                if self.check(TokenType::RightParen) {
+                    break;
+                }
+                elements.push(self.nested_expression()?);
+            }
+            self.consume(TokenType::RightParen, "Jparser: Expect ')' after tuple elements.")?;
+            return Ok(Expr::Tuple { paren, elements });
+        }
+
+        if self.is_match(&[TokenType::Match]) {
+            return self.match_expression();
         }
 
-        Ok(())
-    
-        // self.expression_statement()
+        let peek = self.peek().dup();
+        Err(self.error(&peek, "Jparser: Expect expression."))
     }
 
-   
+    fn literal(&self, value: Object) -> Expr {
+        Expr::Literal { value, token: self.previous().dup() }
+    }
 
-    // for (var i = 0; i < 10; i = i + 1) print i;
-    fn for_statement(&mut self) -> Result<(), Problem> {
-    
-        //Opening (
-        self.consume(TokenType::LeftParen, "Jparser: Expect '(' after 'for'.")?;
-    
-        // ; = return.   var goes to var_declaration, else expression statement
-        let initializer = if self.is_match(&[TokenType::SemiColon]) {
-            None
-        } else if self.is_match(&[TokenType::Let]) {
-            Some(self.var_declaration()?)
-        } else {
-            Some(self.expression_statement()?)
-        };
-    
-        //if semicolon return, else another expression
-        let condition = if self.check(TokenType::SemiColon) {
-            None
-        } else {
-            Some(self.expression()?)
-        };
-    
-        //last semi-colon ends for loop params
-        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after loop condition.")?;
-    
-        //now expefct a right parenthesis, if not check for another expression
-        // let increment = if self.check(TokenType::RightParen) {
-        //     None
-        // } else {
-        //     Some(self.expression()?)
-        // };
-    
-        //now check for a right parenthesis
-        self.consume(TokenType::RightParen, "Jparser: Expect ')' after for clauses.")?;
-    
-        //check for statements, maybe block statement goes here
-        // let mut body = self.statement()?;
-    
-        Ok(())
-    }
-    
-    fn if_statement(&mut self) -> Result<(), Problem> {
-    
-        //left param
-        self.consume(TokenType::LeftParen, "Jparser: Expect '(' after 'if'.")?;
-        //expression
-        self.expression()?;
-        //righ param
-        self.consume(TokenType::RightParen, "Jparser: Expect ')' after 'if'.")?;
-    
-        //get the branch {}
-        self.statement()?;
-        //match else then statement again
-        if self.is_match(&[TokenType::Else]) {
-            self.statement()?
-        };
-    
-        Ok(())
+    // Point { x: 1, y } where a field without a value takes the variable of the same name
+    fn struct_literal(&mut self, name: Token) -> Result<Expr, Problem> {
+        let mut fields = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let field = self.consume(TokenType::Identifier, "Jparser: Expect field name.")?;
+            let value = if self.is_match(&[TokenType::Annotation]) {
+                self.nested_expression()?
+            } else {
+                Expr::Variable(field.dup())
+            };
+            fields.push((field, value));
+
+            if !self.is_match(&[TokenType::Comma]) {
+                break;
+            }
+        }
+
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after struct fields.")?;
+
+        Ok(Expr::StructLiteral { name, fields })
     }
-    
-    
-    
-    fn return_statement(&mut self) -> Result<(), Problem> {
+
+    // match value { Pattern => expr, Pattern => { ... } }
+    fn match_expression(&mut self) -> Result<Expr, Problem> {
         let keyword = self.previous().dup();
-        
-        if !self.check(TokenType::SemiColon) {
-            self.expression()?
-        };
-    
-        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after return value.")?;
-    
-        Ok(())
-    }
-    
-    
-    fn while_statement(&mut self) -> Result<(), Problem> {
-        self.consume(TokenType::LeftParen, "Jparser: Expect '(' after 'while'.")?;
-        self.expression()?;
-        self.consume(TokenType::RightParen, "Jparser: Expect ')' after 'while'.")?;
-        self.statement()?;
-    
-        Ok(())
-    }
-    
-    fn class_declaration(&mut self) -> Result<(), Problem> {
-    
-        // if no class name then return error
-        let name = self.consume(TokenType::Identifier, "Jparser: Expect class name.")?;
-    
-        //if there's a < symbol then check for identifier for superclass name    
-        if self.is_match(&[TokenType::Less]) {
-            self.consume(TokenType::Identifier, "Jparser: Expect superclass name.")?;
-        } 
-    
-        // Should be an { body next
-        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' before class body.")?;
-    
-        //while right brace is not mentioned yet, push self.function into methods vec
-        let mut methods = Vec::new();
+        let scrutinee = self.condition()?;
+        self.consume(TokenType::LeftBrace, "Jparser: Expect '{' after match value.")?;
+
+        let mut arms = Vec::new();
         while !self.check(TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.function("method")?);
+            let pattern = self.pattern()?;
+            self.consume(TokenType::FatArrow, "Jparser: Expect '=>' after match pattern.")?;
+
+            if self.is_match(&[TokenType::LeftBrace]) {
+                arms.push(MatchArm { pattern, body: ArmBody::Block(self.block()?) });
+                //the comma after a block arm is optional
+                self.is_match(&[TokenType::Comma]);
+            } else {
+                arms.push(MatchArm { pattern, body: ArmBody::Expr(self.nested_expression()?) });
+                if !self.is_match(&[TokenType::Comma]) {
+                    break;
+                }
+            }
         }
-    
-        // errror check for }
-        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after class body.")?;
-    
-        Ok(())
+
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after match arms.")?;
+
+        Ok(Expr::Match { keyword, scrutinee: Box::new(scrutinee), arms })
     }
 
-    fn expression_statement(&mut self) -> Result<(), Problem> {
-        self.expression()?;
-        self.consume(TokenType::SemiColon, "Jparser: Expect ';' after expression.")?;
-    
-        Ok(())
+    fn pattern(&mut self) -> Result<Pattern, Problem> {
+        if self.is_match(&[TokenType::LeftParen]) {
+            let paren = self.previous().dup();
+            let mut elements = vec![self.pattern()?];
+            let mut is_tuple = false;
+            while self.is_match(&[TokenType::Comma]) {
+                is_tuple = true;
+                if self.check(TokenType::RightParen) {
+                    break;
+                }
+                elements.push(self.pattern()?);
+            }
+            self.consume(TokenType::RightParen, "Jparser: Expect ')' after tuple pattern.")?;
+
+            return Ok(if is_tuple {
+                Pattern::Tuple { paren, elements }
+            } else {
+                elements.remove(0)
+            });
+        }
+
+        if self.is_match(&[TokenType::True]) {
+            return Ok(Pattern::Literal { value: Object::Bool(true), token: self.previous().dup() });
+        }
+
+        if self.is_match(&[TokenType::False]) {
+            return Ok(Pattern::Literal { value: Object::Bool(false), token: self.previous().dup() });
+        }
+
+        if self.is_match(&[TokenType::Number, TokenType::String]) {
+            let token = self.previous().dup();
+            let value = token.literal.clone().expect("the scanner gives numbers and strings their value");
+            return Ok(Pattern::Literal { value, token });
+        }
+
+        // negative number literal
+        if self.is_match(&[TokenType::Minus]) {
+            let token = self.consume(TokenType::Number, "Jparser: Expect number after '-' in pattern.")?;
+            let value = match token.literal {
+                Some(Object::Num(n)) => Object::Num(-n),
+                _ => unreachable!("the scanner gives numbers their value"),
+            };
+            return Ok(Pattern::Literal { value, token });
+        }
+
+        if self.is_match(&[TokenType::Mut]) {
+            let name = self.consume(TokenType::Identifier, "Jparser: Expect name after 'mut'.")?;
+            return Ok(Pattern::Binding { name, mutable: true });
+        }
+
+        if self.is_match(&[TokenType::Identifier]) {
+            let name = self.previous().dup();
+            if name.is_named("_") {
+                return Ok(Pattern::Wildcard(name));
+            }
+            if self.is_match(&[TokenType::LeftBrace]) {
+                return self.struct_pattern(name);
+            }
+            if !self.check(TokenType::ColonColon) && prelude_enum(&name.as_string()).is_none() {
+                return Ok(Pattern::Binding { name, mutable: false });
+            }
+
+            let mut path = vec![name];
+            while self.is_match(&[TokenType::ColonColon]) {
+                path.push(self.consume(TokenType::Identifier, "Jparser: Expect name after '::'.")?);
+            }
+
+            let mut fields = Vec::new();
+            if self.is_match(&[TokenType::LeftParen]) {
+                if !self.check(TokenType::RightParen) {
+                    fields.push(self.pattern()?);
+                    while self.is_match(&[TokenType::Comma]) {
+                        fields.push(self.pattern()?);
+                    }
+                }
+                self.consume(TokenType::RightParen, "Jparser: Expect ')' after variant fields.")?;
+            }
+            return Ok(Pattern::Variant { path, fields });
+        }
+
+        let peek = self.peek().dup();
+        Err(self.error(&peek, "Jparser: Expect pattern."))
     }
-    
-    fn expression(&mut self) -> Result<(), Problem> {
-        // println!("Expression");
-        Ok(())
+
+    // Point { x, y: py, .. } where the opening brace has been consumed
+    fn struct_pattern(&mut self, name: Token) -> Result<Pattern, Problem> {
+        let mut fields = Vec::new();
+        let mut rest = false;
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            if self.is_match(&[TokenType::DotDot]) {
+                rest = true;
+                break;
+            }
+            //`x` and `mut x` are short for `x: x` and `x: mut x`
+            let mutable = self.is_match(&[TokenType::Mut]);
+            let field = self.consume(TokenType::Identifier, "Jparser: Expect field name in struct pattern.")?;
+            let pattern = if !mutable && self.is_match(&[TokenType::Annotation]) {
+                self.pattern()?
+            } else {
+                Pattern::Binding { name: field.dup(), mutable }
+            };
+            fields.push((field, pattern));
+            if !self.is_match(&[TokenType::Comma]) {
+                break;
+            }
+        }
+        self.consume(TokenType::RightBrace, "Jparser: Expect '}' after struct pattern fields.")?;
+        Ok(Pattern::Struct { name, fields, rest })
     }
 }
@@ -1,3 +1,4 @@
+use crate::diagnostic::{Diagnostics, Span};
 use crate::error::*;
 use crate::object::*;
 use crate::token::*;
@@ -9,6 +10,12 @@ pub struct Scanner {
     start: usize,
     current: usize,
     line: usize,
+    //index of the first character of the current line
+    line_start: usize,
+    //bytes consumed so far, `current` counts characters
+    offset: usize,
+    //where the token being scanned begins
+    start_span: Span,
 }
 
 impl Scanner {
@@ -19,28 +26,40 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
+            line_start: 0,
+            offset: 0,
+            start_span: Span { line: 1, column: 1, offset: 0, len: 0 },
         }
     }
 
-    pub fn scan_tokens(&mut self) -> Result<&Vec<Token>, Problem> {
-        let mut had_error: Option<Problem> = None;
-
+    //Characters that cannot start a token are recorded as errors and skipped
+    pub fn scan_tokens(&mut self, diagnostics: &mut Diagnostics) -> &Vec<Token> {
         while !self.is_at_end() {
             self.start = self.current;
-            match self.scan_token() {
-                Ok(_) => {}
-                Err(e) => {
-                    had_error = Some(e);
-                }
+            self.start_span = self.position();
+            if let Err(problem) = self.scan_token() {
+                diagnostics.record(problem);
             }
         }
 
-        self.tokens.push(Token::eof(self.line));
+        self.tokens.push(Token::eof(self.position()));
+        &self.tokens
+    }
 
-        if let Some(e) = had_error {
-            Err(e)
-        } else {
-            Ok(&self.tokens)
+    fn position(&self) -> Span {
+        Span {
+            line: self.line,
+            column: self.current - self.line_start + 1,
+            offset: self.offset,
+            len: 0,
+        }
+    }
+
+    //A span from the start of the current token up to the next character
+    fn token_span(&self) -> Span {
+        Span {
+            len: self.current - self.start,
+            ..self.start_span
         }
     }
 
@@ -55,13 +74,58 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen),
             '{' => self.add_token(TokenType::LeftBrace),
             '}' => self.add_token(TokenType::RightBrace),
+            '[' => self.add_token(TokenType::LeftBracket),
+            ']' => self.add_token(TokenType::RightBracket),
+            '#' => self.add_token(TokenType::Hash),
             ',' => self.add_token(TokenType::Comma),
-            '.' => self.add_token(TokenType::Dot),
-            '-' => self.add_token(TokenType::Minus),
+            '.' => {
+                let tok = if self.is_match('.') {
+                    if self.is_match('=') {
+                        TokenType::DotDotEqual
+                    } else {
+                        TokenType::DotDot
+                    }
+                } else {
+                    TokenType::Dot
+                };
+                self.add_token(tok);
+            }
+            '-' => {
+                let tok = if self.is_match('>') {
+                    TokenType::Arrow
+                } else {
+                    TokenType::Minus
+                };
+                self.add_token(tok);
+            }
+            '&' => {
+                let tok = if self.is_match('&') {
+                    TokenType::And
+                } else {
+                    TokenType::Ampersand
+                };
+                self.add_token(tok);
+            }
+            '|' => {
+                let tok = if self.is_match('|') {
+                    TokenType::Or
+                } else {
+                    TokenType::Pipe
+                };
+                self.add_token(tok);
+            }
+            '?' => self.add_token(TokenType::Question),
             '+' => self.add_token(TokenType::Plus),
             ';' => self.add_token(TokenType::SemiColon),
             '*' => self.add_token(TokenType::Star),
-            ':' => self.add_token(TokenType::Annotation),
+            ':' => {
+                let tok = if self.is_match(':') {
+                    TokenType::ColonColon
+                } else {
+                    TokenType::Annotation
+                };
+                self.add_token(tok);
+            }
             '!' => {
                 let tok = if self.is_match('=') {
                     TokenType::BangEqual
@@ -73,6 +137,8 @@ impl Scanner {
             '=' => {
                 let tok = if self.is_match('=') {
                     TokenType::Equals
+                } else if self.is_match('>') {
+                    TokenType::FatArrow
                 } else {
                     TokenType::Assign
                 };
@@ -111,10 +177,7 @@ impl Scanner {
                     self.add_token(TokenType::Slash);
                 }
             }
-            ' ' | '\r' | '\t' => {}
-            '\n' => {
-                self.line += 1;
-            }
+            ' ' | '\r' | '\t' | '\n' => {}
             '"' => {
                 self.string()?;
             }
@@ -125,7 +188,7 @@ impl Scanner {
                 self.identifier();
             }
             _ => {
-                Problem::error(self.line, "Unexpected character.");
+                return Err(Problem::error(self.token_span(), "Unexpected character."));
             }
         };
 
@@ -147,12 +210,8 @@ impl Scanner {
                         self.scan_comment()?;
                     }
                 }
-                Some('\n') => {
-                    self.advance();
-                    self.line += 1;
-                }
                 None => {
-                    return Err(Problem::error(self.line, "Unterminated comment"));
+                    return Err(Problem::error(self.token_span(), "Unterminated comment"));
                 }
                 _ => {
                     self.advance();
@@ -179,7 +238,9 @@ impl Scanner {
             self.advance();
         }
 
-        if self.peek() == Some('.') && Scanner::is_digit(self.peek_next()) {
+        //`t.0.1` indexes a tuple twice, the second dot is not a decimal point
+        let tuple_index = self.tokens.last().is_some_and(|token| token.is(TokenType::Dot));
+        if !tuple_index && self.peek() == Some('.') && Scanner::is_digit(self.peek_next()) {
             self.advance();
 
             while Scanner::is_digit(self.peek()) {
@@ -210,20 +271,14 @@ impl Scanner {
 
     fn string(&mut self) -> Result<(), Problem> {
         while let Some(ch) = self.peek() {
-            match ch {
-                '"' => {
-                    break;
-                }
-                '\n' => {
-                    self.line += 1;
-                }
-                _ => {}
+            if ch == '"' {
+                break;
             }
             self.advance();
         }
 
         if self.is_at_end() {
-            return Err(Problem::error(self.line, "Unterminated string."));
+            return Err(Problem::error(self.token_span(), "Unterminated string."));
         }
 
         self.advance();
@@ -239,6 +294,11 @@ impl Scanner {
     fn advance(&mut self) -> char {
         let result = *self.source.get(self.current).unwrap();
         self.current += 1;
+        self.offset += result.len_utf8();
+        if result == '\n' {
+            self.line += 1;
+            self.line_start = self.current;
+        }
         result
     }
 
@@ -249,13 +309,13 @@ impl Scanner {
     fn add_token_object(&mut self, ttype: TokenType, literal: Option<Object>) {
         let lexeme: String = self.source[self.start..self.current].iter().collect();
         self.tokens
-            .push(Token::new(ttype, lexeme, literal, self.line));
+            .push(Token::new(ttype, lexeme, literal, self.token_span()));
     }
 
     fn is_match(&mut self, expected: char) -> bool {
         match self.source.get(self.current) {
             Some(ch) if *ch == expected => {
-                self.current += 1;
+                self.advance();
                 true
             }
             _ => false,
@@ -273,25 +333,38 @@ impl Scanner {
     fn keyword(check: &str) -> Option<TokenType> {
         match check {
             "and" => Some(TokenType::And),
-            "class" => Some(TokenType::Class),
+            "as" => Some(TokenType::As),
             "else" => Some(TokenType::Else),
+            "enum" => Some(TokenType::Enum),
             "false" => Some(TokenType::False),
             "for" => Some(TokenType::For),
             "fn" => Some(TokenType::Fn),
             "if" => Some(TokenType::If),
-            "nil" => Some(TokenType::Nil),
+            "impl" => Some(TokenType::Impl),
+            "in" => Some(TokenType::In),
             "or" => Some(TokenType::Or),
             "println" => Some(TokenType::Print),
             "return" => Some(TokenType::Return),
+            "self" => Some(TokenType::SelfValue),
+            "Self" => Some(TokenType::SelfType),
+            "struct" => Some(TokenType::Struct),
             "super" => Some(TokenType::Super),
             "this" => Some(TokenType::This),
+            "trait" => Some(TokenType::Trait),
             "true" => Some(TokenType::True),
             "let" => Some(TokenType::Let),
+            "mut" => Some(TokenType::Mut),
+            "match" => Some(TokenType::Match),
             "while" => Some(TokenType::While),
             "break" => Some(TokenType::Break),
             "num" => Some(TokenType::NumberType),
             "str" => Some(TokenType::StringType),
             "bool" => Some(TokenType::BoolType),
+            "pub" => Some(TokenType::Pub),
+            "mod" => Some(TokenType::Mod),
+            "use" => Some(TokenType::Use),
+            "crate" => Some(TokenType::Crate),
+            "extern" => Some(TokenType::Extern),
             _ => None,
         }
     }
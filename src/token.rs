@@ -1,3 +1,4 @@
+use crate::diagnostic::Span;
 use crate::object::{Object};
 use crate::token_type::*;
 use std::fmt;
@@ -8,15 +9,30 @@ pub struct Token {
     lexeme: String,
     pub literal: Option<Object>,
     pub line: usize,
+    //1 based, in characters
+    pub column: usize,
+    //bytes from the start of the source
+    pub offset: usize,
 }
 
 impl Token {
-    pub fn new(ttype: TokenType, lexeme: String, literal: Option<Object>, line: usize) -> Token {
+    pub fn new(ttype: TokenType, lexeme: String, literal: Option<Object>, span: Span) -> Token {
         Token {
             ttype,
             lexeme,
             literal,
-            line,
+            line: span.line,
+            column: span.column,
+            offset: span.offset,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        Span {
+            line: self.line,
+            column: self.column,
+            offset: self.offset,
+            len: self.lexeme.chars().count(),
         }
     }
 
@@ -28,6 +44,10 @@ impl Token {
         self.ttype
     }
 
+    pub fn is_named(&self, name: &str) -> bool {
+        self.lexeme == name
+    }
+
     pub fn as_string(&self) -> String {
         self.lexeme.clone()
     }
@@ -38,16 +58,13 @@ impl Token {
             lexeme: self.lexeme.to_string(),
             literal: self.literal.clone(),
             line: self.line,
+            column: self.column,
+            offset: self.offset,
         }
     }
 
-    pub fn eof(line: usize) -> Token {
-        Token {
-            ttype: TokenType::Eof,
-            lexeme: "".to_string(),
-            literal: None,
-            line,
-        }
+    pub fn eof(span: Span) -> Token {
+        Token::new(TokenType::Eof, "".to_string(), None, span)
     }
 }
 
@@ -1,14 +1,24 @@
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub enum TokenType {
     Annotation,
+    ColonColon,
     Break,
     LeftParen,
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Hash,
     Comma,
     Dot,
+    DotDot,
+    DotDotEqual,
     Minus,
+    Arrow, // ('->')
+    Ampersand,
+    Pipe,
+    Question,
     Plus,
     SemiColon,
     Slash,
@@ -17,6 +27,7 @@ pub enum TokenType {
     BangEqual,
     Assign, // ('=')
     Equals, // ('==')
+    FatArrow, // ('=>')
     Greater,
     GreaterEqual,
     Less,
@@ -25,20 +36,33 @@ pub enum TokenType {
     String,
     Number,
     And,
-    Class,
+    As,
+    Struct,
+    Impl,
+    Trait,
+    Pub,
+    Mod,
+    Use,
+    Crate,
+    Extern,
     Else,
+    Enum,
     False,
     Fn,
     For,
     If,
-    Nil,
+    In,
     Or,
     Print,
     Return,
     Super,
     This,
+    SelfValue, // ('self')
+    SelfType,  // ('Self')
     True,
     Let,
+    Mut,
+    Match,
     While,
     Eof,
     NumberType,
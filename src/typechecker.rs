@@ -1,217 +1,2557 @@
-use crate::{ast::*};
-use crate::environment::{TypeEnvironment};
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::*;
+use crate::diagnostic::{Diagnostic, Diagnostics, Span};
+use crate::environment::{BindingKind, Scope, TypeEnvironment};
+use crate::exhaustiveness::{missing_pattern, Declared};
+use crate::modules::{display, is_within};
+use crate::object::Object;
+use crate::token::Token;
+use crate::token_type::TokenType;
 use crate::types::*;
 
+/// The items of the modules checked so far, a module is checked after the modules it imports from.
+/// Structs, enums and traits are known by their path, `shapes::Circle`, those of the root module by
+/// their name alone.
+#[derive(Debug, Default)]
+pub struct Items {
+    enums: HashMap<String, EnumType>,
+    structs: HashMap<String, StructType>,
+    traits: HashMap<String, TraitType>,
+    //the items at the top level of each module and whether they are public, by module path and name
+    exports: HashMap<String, HashMap<String, (bool, Export)>>,
+}
+
+impl Items {
+    pub fn struct_type(&self, path: &str) -> Option<&StructType> {
+        self.structs.get(path)
+    }
+
+    pub fn enum_type(&self, path: &str) -> Option<&EnumType> {
+        self.enums.get(path)
+    }
+
+    pub fn trait_type(&self, path: &str) -> Option<&TraitType> {
+        self.traits.get(path)
+    }
+
+    /// The type of the function `name` at the top level of `module`.
+    pub fn function(&self, module: &str, name: &str) -> Option<&Types> {
+        match self.exports.get(module)?.get(name)? {
+            (_, Export::Function(fn_type)) => Some(fn_type),
+            (_, Export::Type(_)) => None,
+        }
+    }
+}
+
+//An item another module can import, a struct, enum or trait by its path or a function by its type
+#[derive(Debug, Clone)]
+enum Export {
+    Type(String),
+    Function(Types),
+}
+
+#[derive(Debug)]
+pub struct TypeChecker<'a> {
+    diagnostics: &'a mut Diagnostics,
+    //the path of the module being checked, empty for the root
+    module: &'a str,
+    //the module each `use` declaration imports from, keyed by the `use` keyword
+    imports: &'a HashMap<usize, String>,
+    //the module each path through a module names and how many of its segments name it, keyed by the
+    //first segment
+    paths: &'a HashMap<usize, (String, usize)>,
+    //the path of each struct, enum and trait the module can name, declared in it or imported
+    names: HashMap<String, String>,
+    enums: HashMap<String, EnumType>,
+    structs: HashMap<String, StructType>,
+    traits: HashMap<String, TraitType>,
+    exports: HashMap<String, HashMap<String, (bool, Export)>>,
+    //the type of the impl block being checked, what `Self` and `self` refer to
+    current_impl: Option<Types>,
+    //the type parameters that can be named where types are being resolved
+    generics: Vec<TypeParam>,
+    //the declared return type of the function being checked, `None` in top level code
+    current_return: Option<Types>,
+    //whether the function or closure being checked uses `?`
+    propagates: bool,
+    //the closures being checked, innermost last
+    closures: Vec<ClosureScope>,
+    //how many loops enclose the code being checked, within its function or closure
+    loops: usize,
+    //the `let` bindings of the function being checked and their types, which its body must decide
+    lets: Vec<(Token, Types)>,
+    //the variables of the functions around a nested function being checked, which it cannot use
+    outer_locals: Vec<String>,
+    //what the type variable of each integer literal has been unified with, `None` while undecided
+    int_vars: Vec<Option<Types>>,
+    //what the type variable of each use of a generic function or struct has been unified with
+    type_vars: Vec<Option<Types>>,
+    //the traits the types chosen for bounded type parameters must implement, checked once they are decided
+    bounds: Vec<(Types, String, Span)>,
+    //the arguments of `println` and their types, which must be ones a `{}` can show
+    printed: Vec<(Span, Types)>,
+    //integer literals, arithmetic and `as` casts, whose lowering is known once every integer
+    //literal's type is decided
+    literals: Vec<(Token, Types, bool)>,
+    arithmetic: Vec<(usize, Types)>,
+    casts: Vec<(usize, Types, Types)>,
+    //`==` and `!=` and the type of their operands, which decides how they are compared
+    comparisons: Vec<(usize, Types)>,
+    //what code generation needs to know about the types found
+    types: TypeTable,
+}
+
+//A closure being checked. Names declared less deep than its body are captured from outside,
+//`mutates` is the first captured variable it assigns to
 #[derive(Debug)]
-pub struct TypeChecker {
-    env: TypeEnvironment,
+struct ClosureScope {
+    depth: usize,
+    mutates: Option<String>,
 }
 
-impl TypeChecker {
-    pub fn new() -> TypeChecker {
+impl<'a> TypeChecker<'a> {
+    pub fn new(
+        diagnostics: &'a mut Diagnostics,
+        module: &'a str,
+        imports: &'a HashMap<usize, String>,
+        paths: &'a HashMap<usize, (String, usize)>,
+        items: Items,
+    ) -> TypeChecker<'a> {
         TypeChecker {
-            env: TypeEnvironment::new(),
+            diagnostics,
+            module,
+            imports,
+            paths,
+            names: HashMap::new(),
+            enums: items.enums,
+            structs: items.structs,
+            traits: items.traits,
+            exports: items.exports,
+            current_impl: None,
+            generics: Vec::new(),
+            current_return: None,
+            propagates: false,
+            closures: Vec::new(),
+            loops: 0,
+            lets: Vec::new(),
+            outer_locals: Vec::new(),
+            int_vars: Vec::new(),
+            type_vars: Vec::new(),
+            bounds: Vec::new(),
+            printed: Vec::new(),
+            literals: Vec::new(),
+            arithmetic: Vec::new(),
+            casts: Vec::new(),
+            comparisons: Vec::new(),
+            types: TypeTable::default(),
         }
     }
 
-    
+    //Type errors are recorded as they are found, checking carries on so every one of them is shown
+    pub fn check(&mut self, program: &Program) -> TypeTable {
+        let mut global_env = TypeEnvironment::new();
+        for stmt in &program.body {
+            if let Stmt::Item(Item::Use(decl)) = stmt {
+                self.import(decl, &program.body, &mut global_env);
+            }
+        }
+        self.declare_items(&program.body, &mut global_env);
+        self.export(&program.body, &global_env);
+        for stmt in &program.body {
+            self.tc_stmt(stmt, &mut global_env);
+        }
+        self.check_bounds();
+        self.check_printed();
+        self.lower_numbers();
+        self.lower_comparisons();
+        std::mem::take(&mut self.types)
+    }
 
-    //functions get_operand_types() and expect_operator_type() make avaiable binary operators for num and string
-    // make sure strings cannot do * / - , but num can do all four.
-    fn get_operand_types(&self, binary_type: AstType) -> Vec<Types> {
-        let str_type = Types::new(RustScriptType::String);
-        let num_type = Types::new(RustScriptType::Number);
-        let unknown_type = Types::new(RustScriptType::UnKnown);
+    /// What the modules checked so far declare, including this one, for the modules checked after it.
+    pub fn into_items(self) -> Items {
+        Items { enums: self.enums, structs: self.structs, traits: self.traits, exports: self.exports }
+    }
 
-        match binary_type {
-            AstType::Plus => vec![str_type, num_type],
-            AstType::Minus => vec![num_type],
-            AstType::Slash => vec![num_type],
-            AstType::Star => vec![num_type],
-            _ => vec![unknown_type],
+    //The items a `use` declaration names become part of the module, if it can see them
+    fn import(&mut self, decl: &UseDecl, statements: &[Stmt], env: &mut TypeEnvironment) {
+        //a path that does not lead to a module has already been reported
+        let Some(module) = self.imports.get(&decl.keyword.offset) else {
+            return;
+        };
+        for name in &decl.names {
+            let name_str = name.as_string();
+            let declared = statements.iter().flat_map(item_names).any(|item| item.is_named(&name_str));
+            let imported = self.names.contains_key(&name_str) || env.lookup(name_str.clone()).is_ok();
+            if module == self.module || declared || imported {
+                self.error(name, &format!("The name '{}' is defined multiple times", name_str));
+                continue;
+            }
+            let Some((public, export)) = self.exports.get(module).and_then(|items| items.get(&name_str)).cloned() else {
+                let label = format!("no '{}' in '{}'", name_str, display(module));
+                self.report(Diagnostic::new("Unresolved import", name.span()).label(&label));
+                continue;
+            };
+            //a private item is still imported, so its uses are checked and not reported as undefined
+            if !public && !is_within(self.module, module) {
+                let message = format!("'{}' is private", name_str);
+                self.report(Diagnostic::new(&message, name.span()).label("private item"));
+            }
+            match export {
+                Export::Type(path) => {
+                    if let Some(struct_type) = self.structs.get(&path) {
+                        let fields = struct_type.fields.iter().map(|(field, _)| field.clone()).collect();
+                        self.types.imported_fields.insert(name_str.clone(), fields);
+                    }
+                    self.names.insert(name_str, path);
+                }
+                Export::Function(fn_type) => {
+                    env.define(name, fn_type, BindingKind::Function);
+                }
+            }
         }
     }
 
-    fn expect_operator_type(&self, operand_type: &Types, allowed_types: &Vec<Types>, exp: &Vec<AstType>) {
-        if allowed_types.into_iter().any(|t| t.equals(operand_type.clone())) != true {
-            println!("Unexpected type: '{}' in operation '{:?}', allowed  '{:?}'",  operand_type, exp, allowed_types);
+    //The items at the top level of the module, for the modules checked after it to import
+    fn export(&mut self, statements: &[Stmt], env: &TypeEnvironment) {
+        let mut items = HashMap::new();
+        for stmt in statements {
+            let (public, name, export) = match stmt {
+                Stmt::Item(Item::Fn(decl)) => {
+                    let Ok(fn_type) = env.lookup(decl.name.as_string()) else {
+                        continue;
+                    };
+                    (decl.public, &decl.name, Export::Function(fn_type.clone()))
+                }
+                Stmt::Item(Item::Struct(StructDecl { public, name, .. }))
+                | Stmt::Item(Item::Enum(EnumDecl { public, name, .. }))
+                | Stmt::Item(Item::Trait(TraitDecl { public, name, .. })) => {
+                    (*public, name, Export::Type(self.item_path(name)))
+                }
+                _ => continue,
+            };
+            items.insert(name.as_string(), (public, export));
         }
+        self.exports.insert(self.module.to_string(), items);
     }
 
-    fn throw(&self, actual_type: Types, expected_type: Types, value: AstType, exp: Vec<AstType>) {
-        println!("Expected '{}' type for '{:?}' in '{:?}' but got '{}'", expected_type, value, exp, actual_type);
+    //Where the module being checked can use what another module does not make public, the module
+    //an item is in is the start of its path
+    fn can_see_private(&self, path: &str) -> bool {
+        let module = path.rsplit_once("::").map_or("", |(module, _)| module);
+        is_within(self.module, module)
     }
 
-    //Checks if both operands Types match ie str, str and num, num 
-    fn expect(&self, actual_type: Types, expected_type: Types, value: AstType, exp: Vec<AstType>) -> Types {
-        //value, exp for error purposes
-        if actual_type.equals(expected_type.clone()) != true {
-            self.throw(actual_type.clone(), expected_type, value, exp);
+    //The path of a struct, enum or trait declared in the module being checked
+    fn qualify(&self, name: &Token) -> String {
+        match self.module {
+            "" => name.as_string(),
+            module => format!("{}::{}", module, name.as_string()),
         }
-        actual_type
     }
 
-    //Get types for both operands. See binary operations available for these operands match the types
-    //so only the same types can do binary operations on one another ie num + num, str + str
-    fn binary(&mut self, exp: &Vec<AstType>, env: &mut TypeEnvironment) -> Types {
-        let t1 = self.tc(&vec![exp[0].clone()], env);
-        let t2 = self.tc(&vec![exp[2].clone()], env);
+    //The path of a struct, enum or trait named in the module, declared in it or imported
+    fn item_path(&self, name: &Token) -> String {
+        let name = name.as_string();
+        self.names.get(&name).cloned().unwrap_or(name)
+    }
 
-        let allow_types = self.get_operand_types(exp[1].clone());
+    //A variant of the prelude's `Option` or `Result`, unless the program declares an enum of that name
+    fn prelude_path<'p>(&self, path: &'p [Token]) -> Option<(&'static str, &'p Token)> {
+        match path {
+            [enum_name, _] if self.enums.contains_key(&self.item_path(enum_name)) => None,
+            path => prelude_variant(path),
+        }
+    }
 
-        self.expect_operator_type(&t1, &allow_types, &exp);
-        self.expect_operator_type(&t2, &allow_types, &exp);
-        
-        self.expect(t2, t1, exp[2].clone(), exp.to_vec())
+    fn type_param(&self, generic: &GenericParam) -> TypeParam {
+        let bounds = generic.bounds.iter().map(|bound| self.item_path(bound)).collect();
+        TypeParam { name: generic.name.as_string(), bounds }
     }
 
+    //The type `Self` stands for in an impl block, `Pair<A, B>` in `impl<A, B> Pair<A, B>`
+    fn impl_type(&self, decl: &ImplDecl) -> Types {
+        let args = decl
+            .args
+            .iter()
+            .map(|arg| match arg {
+                TypeAnnotation::Named(arg) if is_impl_param(decl, arg) => {
+                    Types::new(RustScriptType::Param(self.impl_param(decl, arg)))
+                }
+                //not a type parameter of the block, which has been reported
+                _ => Types::new(RustScriptType::UnKnown),
+            })
+            .collect();
+        Types::new(RustScriptType::Struct(self.item_path(&decl.name), args))
+    }
 
+    //A type parameter named in the header of an impl block, with the bounds the block declares for it
+    fn impl_param(&self, decl: &ImplDecl, arg: &Token) -> TypeParam {
+        match decl.generics.iter().find(|generic| generic.name.is_named(&arg.as_string())) {
+            Some(generic) => self.type_param(generic),
+            None => TypeParam { name: arg.as_string(), bounds: Vec::new() },
+        }
+    }
 
-    pub fn tc(&mut self, exp: &Vec<AstType>, env: &mut TypeEnvironment) -> Types {
-        if exp.len() == 5 {
-            // println!("We got 5 operands");
-            let mut current_type = self.tc(
-                &vec![exp[0].clone(), 
-                exp[1].clone(), 
-                exp[2].clone()], env
-            );
-            return self.tc(&vec![current_type.type_to_token(), exp[3].clone(), exp[4].clone()], env);
+    //Every type chosen for a type parameter with trait bounds must implement those traits
+    fn check_bounds(&mut self) {
+        for (ty, trait_name, span) in std::mem::take(&mut self.bounds) {
+            if !self.implements(&ty, &trait_name) {
+                let ty = self.resolve(&ty);
+                let message = format!("Type '{}' does not implement trait '{}'", ty, trait_name);
+                let label = format!("the trait bound '{}: {}' is not satisfied", ty, trait_name);
+                self.report(Diagnostic::new(&message, span).label(&label));
+            }
         }
+    }
 
-        if exp.len() == 3 {
-            // println!("We got 3 operands");
-            match exp[1] {
-                AstType::Plus | AstType::Minus | AstType::Star | AstType::Slash => return self.binary(&exp, env),
-                _ => {},
+    //A `{}` shows a number, a bool or a string as Rust's `Display` would. A struct, enum, tuple or
+    //vector would be shown as what Javascript makes of the object, such as `[object Object]`
+    fn check_printed(&mut self) {
+        for (span, ty) in std::mem::take(&mut self.printed) {
+            match self.resolve(&ty).name {
+                RustScriptType::I32
+                | RustScriptType::I64
+                | RustScriptType::F64
+                | RustScriptType::IntVar(_)
+                | RustScriptType::String
+                | RustScriptType::Bool
+                | RustScriptType::Var(_)
+                | RustScriptType::Never
+                | RustScriptType::UnKnown => {}
+                name => {
+                    let message = format!("Type '{}' cannot be printed with '{{}}'", Types::new(name));
+                    let diagnostic = Diagnostic::new(&message, span)
+                        .label("only numbers, 'bool' and 'str' can be printed")
+                        .footer("print its fields or elements one by one");
+                    self.report(diagnostic);
+                }
             }
         }
+    }
 
-       
-        
-        match &exp[0] {
-            AstType::Set => {
-                //variable updating (set x 10)
-                let var_name = &exp[1];
-                let value = &exp[2];
+    //A generic struct may implement a trait only when its type arguments implement other traits,
+    //`impl<T: Shape> Shape for Wrapper<T>`. A type that is still undecided is not checked
+    fn implements(&self, ty: &Types, trait_name: &str) -> bool {
+        match &self.resolve(ty).name {
+            RustScriptType::Param(param) => param.bounds.iter().any(|bound| bound == trait_name),
+            RustScriptType::Struct(name, args) => {
+                let Some(struct_type) = self.structs.get(name) else {
+                    return true;
+                };
+                let Some(requires) = struct_type.traits.get(trait_name) else {
+                    return false;
+                };
+                let substitution = struct_type.substitution(args);
+                requires.iter().all(|param| {
+                    let arg = &substitution[&param.name];
+                    param.bounds.iter().all(|bound| self.implements(arg, bound))
+                })
+            }
+            RustScriptType::Var(_) | RustScriptType::UnKnown => true,
+            _ => false,
+        }
+    }
 
-                //The type of the new value should match to the 
-                //previous type when the variable was defined
+    //Record what code generation needs to know about numbers, now that every integer literal has a type
+    fn lower_numbers(&mut self) {
+        for (token, literal, negative) in std::mem::take(&mut self.literals) {
+            let (min, max) = match self.decided(&literal).name {
+                RustScriptType::I32 => (i32::MIN as i128, i32::MAX as i128),
+                RustScriptType::I64 => {
+                    self.types.bigints.insert(token.offset);
+                    (i64::MIN as i128, i64::MAX as i128)
+                }
+                _ => continue,
+            };
+            let limit = if negative { -min } else { max };
+            if token.as_string().parse::<i128>().is_ok_and(|value| value <= limit) {
+                continue;
+            }
+            let decided = self.decided(&literal);
+            let message = format!("Literal out of range for '{}'", decided);
+            let label = format!("the literal does not fit into '{}', whose range is '{}..={}'", decided, min, max);
+            self.report(Diagnostic::new(&message, token.span()).label(&label));
+        }
+        for (offset, operands) in std::mem::take(&mut self.arithmetic) {
+            if self.decided(&operands).name == RustScriptType::I32 {
+                self.types.int_arithmetic.insert(offset);
+            }
+        }
+        for (offset, from, to) in std::mem::take(&mut self.casts) {
+            if let Some(cast) = Cast::between(&self.decided(&from), &self.decided(&to)) {
+                self.types.casts.insert(offset, cast);
+            }
+        }
+    }
 
-                let value_type = self.tc(&vec![value.to_owned()], env);
-                let var_type = self.tc(&vec![var_name.to_owned()], env);
+    //Values Javascript keeps as objects are equal when their contents are, not only when they are the same
+    //object. The variants of an enum without payloads are shared objects, so `===` compares them
+    fn lower_comparisons(&mut self) {
+        for (offset, operands) in std::mem::take(&mut self.comparisons) {
+            let structural = match self.decided(&operands).name {
+                RustScriptType::I32
+                | RustScriptType::I64
+                | RustScriptType::F64
+                | RustScriptType::String
+                | RustScriptType::Bool
+                | RustScriptType::Unit
+                | RustScriptType::Never
+                | RustScriptType::Function { .. } => false,
+                RustScriptType::Enum(name) => self
+                    .enums
+                    .get(&name)
+                    .is_none_or(|enum_type| enum_type.variants.iter().any(|(_, fields)| !fields.is_empty())),
+                _ => true,
+            };
+            if structural {
+                self.types.structural_comparisons.insert(offset);
+            }
+        }
+    }
 
-                self.expect(value_type, var_type, value.to_owned(), exp.to_owned())
-             
+    //Items of a module, and parameters of a function, are told apart by their names alone
+    fn check_unique(&mut self, names: Vec<&Token>) {
+        let mut seen = HashSet::new();
+        for name in names {
+            let name_str = name.as_string();
+            if !seen.insert(name_str.clone()) {
+                self.error(name, &format!("The name '{}' is defined multiple times", name_str));
             }
-            AstType::Let => {
-                let var_name = match &exp[1] {
-                    AstType::NumberType(vname) => vname.to_owned(),
-                    AstType::StringType(vname) => vname.to_owned(),
-                    _ => "".to_owned()
-                };
+        }
+    }
 
-                let var_value = &exp[2];
+    //An integer literal gets a type variable of its own, a literal with a decimal point is an f64.
+    //A negated literal can be one further from zero, `-2147483648` is an i32
+    fn number_literal(&mut self, token: &Token, negative: bool) -> Types {
+        if token.as_string().contains('.') {
+            return Types::new(RustScriptType::F64);
+        }
+        let literal = Types::new(RustScriptType::IntVar(self.int_vars.len()));
+        self.int_vars.push(None);
+        self.literals.push((token.dup(), literal.clone(), negative));
+        literal
+    }
 
-                //infer value type from value
-                let value_type = self.tc(&vec![var_value.to_owned()], env);
-                //Check if the annotad type :number matches type of inferred value_type, using expect   
+    //A type variable for one use of a type parameter
+    fn fresh_var(&mut self) -> Types {
+        self.type_vars.push(None);
+        Types::new(RustScriptType::Var(self.type_vars.len() - 1))
+    }
 
-               return env.define(var_name.to_owned(), value_type).to_owned()
-            },
-            AstType::String => Types {
-                name: RustScriptType::String,
-            },
-            AstType::Number => Types {
-                name: RustScriptType::Number,
+    //A type variable for one use of `param`, whatever it is decided to be must implement the traits `param` is bound by
+    fn bounded_var(&mut self, param: &TypeParam, token: &Token) -> Types {
+        let var = self.fresh_var();
+        self.require(&var, &param.bounds, token);
+        var
+    }
+
+    fn require(&mut self, ty: &Types, traits: &[String], token: &Token) {
+        for trait_name in traits {
+            self.bounds.push((ty.clone(), trait_name.clone(), token.span()));
+        }
+    }
+
+    //A generic function's type at one of its uses, each type parameter replaced by a new type variable
+    fn instantiate(&mut self, ty: &Types, token: &Token) -> Types {
+        let substitution: HashMap<String, Types> =
+            ty.type_params().into_iter().map(|param| (param.name.clone(), self.bounded_var(&param, token))).collect();
+        ty.substitute(&substitution)
+    }
+
+    //Replace the type variables that have been decided by their types, throughout a type
+    fn resolve(&self, ty: &Types) -> Types {
+        let boxed = |ty: &Types| Box::new(self.resolve(ty));
+        let all = |types: &[Types]| types.iter().map(|ty| self.resolve(ty)).collect();
+        let decided = match &ty.name {
+            RustScriptType::IntVar(var) => Some(&self.int_vars[*var]),
+            RustScriptType::Var(var) => Some(&self.type_vars[*var]),
+            _ => None,
+        };
+        if let Some(decided) = decided {
+            return match decided {
+                Some(decided) => self.resolve(decided),
+                None => ty.clone(),
+            };
+        }
+        let name = match &ty.name {
+            RustScriptType::Tuple(elements) => RustScriptType::Tuple(all(elements)),
+            RustScriptType::Struct(name, args) => RustScriptType::Struct(name.clone(), all(args)),
+            RustScriptType::Vec(element) => RustScriptType::Vec(boxed(element)),
+            RustScriptType::Option(value) => RustScriptType::Option(boxed(value)),
+            RustScriptType::Result(value, error) => RustScriptType::Result(boxed(value), boxed(error)),
+            RustScriptType::Function { params, return_type } => {
+                RustScriptType::Function { params: all(params), return_type: boxed(return_type) }
+            }
+            name => name.clone(),
+        };
+        Types::new(name)
+    }
+
+    //The type of a value once checking is done, integer literals nothing decided are i32 as in Rust
+    fn decided(&self, ty: &Types) -> Types {
+        match self.resolve(ty) {
+            Types { name: RustScriptType::IntVar(_) } => Types::new(RustScriptType::I32),
+            ty => ty,
+        }
+    }
+
+    //Whether two types agree, an undecided type variable on one side takes the type on the other, and
+    //an undecided integer literal takes the numeric type on the other.
+    //Unknown types have already been reported or cannot be checked yet, so they agree with anything
+    fn unify(&mut self, a: &Types, b: &Types) -> bool {
+        let (a, b) = (self.resolve(a), self.resolve(b));
+        match (&a.name, &b.name) {
+            (RustScriptType::IntVar(x), RustScriptType::IntVar(y)) | (RustScriptType::Var(x), RustScriptType::Var(y))
+                if x == y =>
+            {
+                true
+            }
+            (RustScriptType::UnKnown, _) | (_, RustScriptType::UnKnown) => true,
+            //a variable cannot stand for a type that contains it, such as `T` for `Vec<T>`
+            (RustScriptType::Var(var), _) => {
+                let infinite = b.mentions(*var);
+                if !infinite {
+                    self.type_vars[*var] = Some(b.clone());
+                }
+                !infinite
+            }
+            (_, RustScriptType::Var(_)) => self.unify(&b, &a),
+            (RustScriptType::IntVar(var), _) if b.is_numeric() => {
+                self.int_vars[*var] = Some(b.clone());
+                true
+            }
+            (_, RustScriptType::IntVar(var)) if a.is_numeric() => {
+                self.int_vars[*var] = Some(a.clone());
+                true
+            }
+            (RustScriptType::Tuple(elements), RustScriptType::Tuple(others)) => {
+                elements.len() == others.len() && elements.iter().zip(others).all(|(a, b)| self.unify(a, b))
+            }
+            (RustScriptType::Vec(element), RustScriptType::Vec(other))
+            | (RustScriptType::Option(element), RustScriptType::Option(other)) => self.unify(element, other),
+            (RustScriptType::Result(value, error), RustScriptType::Result(other_value, other_error)) => {
+                self.unify(value, other_value) && self.unify(error, other_error)
+            }
+            (RustScriptType::Struct(name, args), RustScriptType::Struct(other, other_args)) => {
+                name == other
+                    && args.len() == other_args.len()
+                    && args.iter().zip(other_args).all(|(a, b)| self.unify(a, b))
+            }
+            (
+                RustScriptType::Function { params, return_type },
+                RustScriptType::Function { params: other_params, return_type: other_return },
+            ) => {
+                params.len() == other_params.len()
+                    && params.iter().zip(other_params).all(|(a, b)| self.unify(a, b))
+                    && self.unify(return_type, other_return)
+            }
+            _ => a.equals(&b),
+        }
+    }
+
+    fn declared(&self) -> Declared<'_> {
+        Declared { enums: &self.enums, structs: &self.structs }
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.report(Diagnostic::new(message, token.span()));
+    }
+
+    fn report(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.error(diagnostic);
+    }
+
+    //Functions, structs, enums and traits can be used before the line they are declared on
+    fn declare_items(&mut self, statements: &[Stmt], env: &mut TypeEnvironment) {
+        self.check_unique(statements.iter().flat_map(item_names).collect());
+        //type names first, so fields and variant payloads can refer to any of them
+        for stmt in statements {
+            if let Stmt::Item(Item::Struct(StructDecl { name, .. }))
+            | Stmt::Item(Item::Enum(EnumDecl { name, .. }))
+            | Stmt::Item(Item::Trait(TraitDecl { name, .. })) = stmt
+            {
+                self.names.insert(name.as_string(), self.qualify(name));
+            }
+        }
+        for stmt in statements {
+            match stmt {
+                Stmt::Item(Item::Enum(decl)) => {
+                    let name = self.qualify(&decl.name);
+                    self.enums.insert(name.clone(), EnumType { name, variants: Vec::new() });
+                }
+                Stmt::Item(Item::Struct(decl)) => {
+                    let name = self.qualify(&decl.name);
+                    let struct_type = StructType {
+                        name: name.clone(),
+                        generics: decl.generics.iter().map(|generic| self.type_param(generic)).collect(),
+                        fields: Vec::new(),
+                        private_fields: decl.fields.iter().filter(|f| !f.public).map(|f| f.name.as_string()).collect(),
+                        methods: HashMap::new(),
+                        traits: HashMap::new(),
+                    };
+                    self.structs.insert(name, struct_type);
+                }
+                Stmt::Item(Item::Trait(decl)) => {
+                    let name = self.qualify(&decl.name);
+                    self.traits.insert(name.clone(), TraitType { name, methods: HashMap::new(), required: Vec::new() });
+                }
+                _ => {}
+            }
+        }
+
+        for stmt in statements {
+            match stmt {
+                Stmt::Item(Item::Fn(decl)) => {
+                    let method = self.method_type(decl);
+                    let fn_type = RustScriptType::Function {
+                        params: method.params,
+                        return_type: Box::new(method.return_type),
+                    };
+                    env.define(&decl.name, Types::new(fn_type), BindingKind::Function);
+                }
+                Stmt::Item(Item::Enum(decl)) => {
+                    let variants = decl
+                        .variants
+                        .iter()
+                        .map(|v| (v.name.as_string(), v.fields.iter().map(|f| self.annotation_type(f)).collect()))
+                        .collect();
+                    let name = self.qualify(&decl.name);
+                    self.enums.insert(name.clone(), EnumType { name, variants });
+                }
+                Stmt::Item(Item::Struct(decl)) => {
+                    self.check_bound_traits(&decl.generics);
+                    let generics = decl.generics.iter().map(|generic| self.type_param(generic)).collect();
+                    let enclosing = std::mem::replace(&mut self.generics, generics);
+                    let fields = decl
+                        .fields
+                        .iter()
+                        .map(|f| (f.name.as_string(), self.annotation_type(&f.annotation)))
+                        .collect();
+                    self.generics = enclosing;
+                    if let Some(struct_type) = self.structs.get_mut(&self.qualify(&decl.name)) {
+                        struct_type.fields = fields;
+                    }
+                }
+                Stmt::Item(Item::Trait(decl)) => self.declare_trait(decl),
+                Stmt::Item(Item::Extern(decl)) => self.declare_extern(decl, env),
+                _ => {}
+            }
+        }
+
+        //impl blocks may come before or after their struct
+        for stmt in statements {
+            if let Stmt::Item(Item::Impl(decl)) = stmt {
+                self.declare_impl(decl);
+            }
+        }
+    }
+
+    //In a trait's methods `Self` is a type parameter bound by the trait, whatever type implements it
+    fn declare_trait(&mut self, decl: &TraitDecl) {
+        let name = self.qualify(&decl.name);
+        self.current_impl = Some(trait_self(&name));
+        let methods = decl
+            .required
+            .iter()
+            .chain(&decl.provided)
+            .map(|method| (method.name.as_string(), self.method_type(method)))
+            .collect();
+        self.current_impl = None;
+        let required = decl.required.iter().map(|method| method.name.as_string()).collect();
+        self.traits.insert(name.clone(), TraitType { name, methods, required });
+    }
+
+    //Nothing checks that Javascript functions take and return what they are declared to, their
+    //signatures are trusted
+    fn declare_extern(&mut self, decl: &ExternDecl, env: &mut TypeEnvironment) {
+        if !decl.abi.is_named("\"js\"") {
+            let message = format!("Invalid ABI: found {}", decl.abi.as_string());
+            let diagnostic = Diagnostic::new(&message, decl.abi.span())
+                .label("invalid ABI")
+                .footer("the only supported ABI is \"js\"");
+            self.report(diagnostic);
+        }
+        self.check_extern_attributes(&decl.attributes, "js_module");
+        for function in &decl.functions {
+            self.check_extern_attributes(&function.attributes, "js_name");
+            let method = self.method_type(function);
+            let fn_type = RustScriptType::Function { params: method.params, return_type: Box::new(method.return_type) };
+            env.define(&function.name, Types::new(fn_type), BindingKind::Function);
+        }
+    }
+
+    //An extern block can say which module its functions are imported from and each function what
+    //it is called in Javascript
+    fn check_extern_attributes(&mut self, attributes: &[Attribute], expected: &str) {
+        for attribute in attributes {
+            let name = attribute.name.as_string();
+            if name != expected {
+                self.error(&attribute.name, &format!("Unknown attribute '{name}'"));
+                continue;
+            }
+            let Some(value) = attribute.string() else {
+                let message = format!("Attribute '{name}' needs a value");
+                let label = format!("expected '#[{name} = \"..\"]'");
+                self.report(Diagnostic::new(&message, attribute.name.span()).label(&label));
+                continue;
+            };
+            if name == "js_name" && !is_js_path(value) {
+                let message = format!("'{value}' is not a Javascript name");
+                let span = attribute.value.as_ref().expect("the value is a string").span();
+                let diagnostic = Diagnostic::new(&message, span)
+                    .label("expected names separated by '.', such as 'console.error'");
+                self.report(diagnostic);
+            }
+        }
+    }
+
+    fn check_bound_traits(&mut self, generics: &[GenericParam]) {
+        for bound in generics.iter().flat_map(|generic| &generic.bounds) {
+            if !self.traits.contains_key(&self.item_path(bound)) {
+                self.error(bound, &format!("Unknown trait '{}'", bound.as_string()));
+            }
+        }
+    }
+
+    //`impl<A, B> Pair<A, B>` names the struct's type parameters, in order, after the ones it declares
+    fn declare_impl(&mut self, decl: &ImplDecl) {
+        let name = decl.name.as_string();
+        let path = self.item_path(&decl.name);
+        let Some(generics) = self.structs.get(&path).map(|struct_type| struct_type.generics.clone()) else {
+            self.error(&decl.name, &format!("Cannot impl '{}', it is not a struct", name));
+            return;
+        };
+        //the methods are part of the struct's Javascript class, which is emitted with the struct
+        if path != self.qualify(&decl.name) {
+            self.error(&decl.name, &format!("Cannot impl '{}' outside of the module it is declared in", name));
+            return;
+        }
+        self.check_bound_traits(&decl.generics);
+        if decl.args.len() != generics.len() {
+            self.wrong_type_arguments(&decl.name, generics.len(), decl.args.len());
+            return;
+        }
+        let mut args = Vec::new();
+        for arg in &decl.args {
+            let TypeAnnotation::Named(arg) = arg else {
+                self.error(arg.token(), "Expected a type parameter declared with 'impl<..>'");
+                return;
+            };
+            args.push(arg);
+            if !is_impl_param(decl, arg) {
+                let param = arg.as_string();
+                let message = format!("Unknown type '{param}', declare it with 'impl<{param}>'");
+                self.error(arg, &message);
+                return;
+            }
+        }
+
+        let self_type = self.impl_type(decl);
+        self.current_impl = Some(self_type.clone());
+        let mut methods: Vec<(String, &Token, MethodType)> = decl
+            .methods
+            .iter()
+            .map(|method| (method.name.as_string(), &method.name, self.method_type(method)))
+            .collect();
+        self.current_impl = None;
+        //errors about the provided methods of a trait point at the trait's name
+        if let Some(trait_name) = &decl.trait_name {
+            let Some(provided) = self.check_trait_impl(decl, trait_name, &self_type, &methods) else {
+                return;
+            };
+            let copied = provided.iter().map(|(name, method)| (name.clone(), method.takes_self)).collect();
+            self.types.provided.insert(trait_name.offset, copied);
+            methods.extend(provided.into_iter().map(|(name, method)| (name, trait_name, method)));
+        }
+
+        //methods are stored with the names the struct declaration gives its type parameters, with the
+        //bounds the impl block adds on them, which calls must satisfy
+        let renamed: HashMap<String, Types> = args
+            .iter()
+            .zip(&generics)
+            .map(|(arg, generic)| (arg.as_string(), Types::new(RustScriptType::Param(generic.clone()))))
+            .collect();
+        let requires: Vec<TypeParam> = args
+            .iter()
+            .zip(&generics)
+            .map(|(arg, generic)| TypeParam { name: generic.name.clone(), bounds: self.impl_param(decl, arg).bounds })
+            .filter(|param| !param.bounds.is_empty())
+            .collect();
+        let trait_path = decl.trait_name.as_ref().map(|trait_name| self.item_path(trait_name));
+        let struct_type = self.structs.get_mut(&path).expect("struct was checked above");
+        if let Some(trait_path) = trait_path {
+            struct_type.traits.insert(trait_path, requires.clone());
+        }
+        let mut duplicates = Vec::new();
+        let mut shadowed = Vec::new();
+        for (method_name, token, mut method_type) in methods {
+            method_type.params = method_type.params.iter().map(|param| param.substitute(&renamed)).collect();
+            method_type.return_type = method_type.return_type.substitute(&renamed);
+            method_type.requires = requires.clone();
+            //the methods of a trait are public wherever the trait is
+            method_type.public |= decl.trait_name.is_some();
+            //the fields are set on each object, where they hide the methods of its class
+            if method_type.takes_self && struct_type.field(&method_name).is_some() {
+                shadowed.push((method_name.clone(), token));
+            }
+            //a struct and the traits it implements share one Javascript class, so a name can only be used once
+            if struct_type.methods.insert(method_name.clone(), method_type).is_some() {
+                duplicates.push((method_name, token));
+            }
+        }
+        for (method_name, token) in duplicates {
+            let message = format!("Duplicate definitions with name '{}' for '{}'", method_name, name);
+            self.error(token, &message);
+        }
+        for (method_name, token) in shadowed {
+            let message = format!("Method '{}' has the same name as a field of '{}'", method_name, name);
+            self.error(token, &message);
+        }
+    }
+
+    //An implementation of a trait defines each of its required methods with the signature the
+    //trait declares, and nothing else. Returns the provided methods it does not define, or `None` when
+    //the trait is already implemented and the block is left out
+    fn check_trait_impl(
+        &mut self,
+        decl: &ImplDecl,
+        trait_name: &Token,
+        self_type: &Types,
+        methods: &[(String, &Token, MethodType)],
+    ) -> Option<Vec<(String, MethodType)>> {
+        let Some(trait_type) = self.traits.get(&self.item_path(trait_name)).cloned() else {
+            self.error(trait_name, &format!("Unknown trait '{}'", trait_name.as_string()));
+            return Some(Vec::new());
+        };
+        if self.structs[&self.item_path(&decl.name)].traits.contains_key(&trait_type.name) {
+            let message =
+                format!("Conflicting implementations of trait '{}' for '{}'", trait_type.name, decl.name.as_string());
+            self.error(&decl.name, &message);
+            return None;
+        }
+
+        for (method_name, token, method_type) in methods {
+            let Some(expected) = trait_type.methods.get(method_name) else {
+                let message = format!("Method '{}' is not a member of trait '{}'", method_name, trait_type.name);
+                self.error(token, &message);
+                continue;
+            };
+            //the method's own type parameters may be named differently than in the trait
+            let mut substitution = HashMap::from([("Self".to_string(), self_type.clone())]);
+            for (generic, own) in expected.generics.iter().zip(&method_type.generics) {
+                substitution.insert(generic.name.clone(), Types::new(RustScriptType::Param(own.clone())));
+            }
+            let expected = MethodType {
+                params: expected.params.iter().map(|param| param.substitute(&substitution)).collect(),
+                return_type: expected.return_type.substitute(&substitution),
+                ..expected.clone()
+            };
+            let matches = expected.takes_self == method_type.takes_self
+                && expected.mutates_self == method_type.mutates_self
+                && expected.generics.len() == method_type.generics.len()
+                && expected.params.len() == method_type.params.len()
+                && expected.params.iter().zip(&method_type.params).all(|(a, b)| a.equals(b))
+                && expected.return_type.equals(&method_type.return_type);
+            if !matches {
+                let message =
+                    format!("Method '{}' has an incompatible type for trait '{}'", method_name, trait_type.name);
+                let label = format!("expected '{}', found '{}'", expected, method_type);
+                self.report(Diagnostic::new(&message, token.span()).label(&label));
+            }
+        }
+
+        let defined = |name: &String| methods.iter().any(|(method_name, ..)| method_name == name);
+        let missing: Vec<String> =
+            trait_type.required.iter().filter(|name| !defined(name)).map(|name| format!("'{name}'")).collect();
+        if !missing.is_empty() {
+            let message = format!("Not all trait items implemented, missing: {}", missing.join(", "));
+            let label = format!("missing {} in implementation", missing.join(", "));
+            self.report(Diagnostic::new(&message, decl.name.span()).label(&label));
+        }
+
+        let substitution = HashMap::from([("Self".to_string(), self_type.clone())]);
+        let mut provided: Vec<(String, MethodType)> = trait_type
+            .methods
+            .iter()
+            .filter(|(name, _)| !trait_type.required.contains(name) && !defined(name))
+            .map(|(name, method)| {
+                let method = MethodType {
+                    params: method.params.iter().map(|param| param.substitute(&substitution)).collect(),
+                    return_type: method.return_type.substitute(&substitution),
+                    ..method.clone()
+                };
+                (name.clone(), method)
+            })
+            .collect();
+        provided.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Some(provided)
+    }
+
+    fn method_type(&mut self, decl: &FnDecl) -> MethodType {
+        self.check_bound_traits(&decl.generics);
+        let generics = self.generics_of(decl);
+        let enclosing = std::mem::replace(&mut self.generics, generics);
+        let method_type = MethodType {
+            generics: decl.generics.iter().map(|generic| self.type_param(generic)).collect(),
+            requires: Vec::new(),
+            public: decl.public,
+            takes_self: decl.self_param.is_some(),
+            mutates_self: decl.self_mutable,
+            params: decl.params.iter().map(|p| self.annotation_type(&p.annotation)).collect(),
+            return_type: match &decl.return_type {
+                Some(annotation) => self.annotation_type(annotation),
+                None => Types::new(RustScriptType::Unit),
             },
-            AstType::NumberType(var_name) | AstType::StringType(var_name)  => {
+        };
+        self.generics = enclosing;
+        method_type
+    }
+
+    //The type parameters a function can name, its own and those of the impl block it is in. A
+    //function declared inside a generic function cannot use that function's type parameters
+    fn generics_of(&self, decl: &FnDecl) -> Vec<TypeParam> {
+        let impl_generics = match &self.current_impl {
+            Some(Types { name: RustScriptType::Struct(_, args) }) => {
+                args.iter().flat_map(|arg| arg.type_params()).collect()
+            }
+            _ => Vec::new(),
+        };
+        let own = decl.generics.iter().map(|generic| self.type_param(generic)).collect();
+        [impl_generics, own].concat()
+    }
+
+    fn wrong_type_arguments(&mut self, name: &Token, expected: usize, given: usize) {
+        let message = format!(
+            "'{}' takes {} type argument{} but {} were given",
+            name.as_string(),
+            expected,
+            if expected == 1 { "" } else { "s" },
+            given
+        );
+        self.error(name, &message);
+    }
 
-                match env.lookup(var_name.clone()) {
-                    Ok(var_type) => var_type.to_owned(),
-                    Err(err) =>{
-                        println!("Undefined variable {} with error {:?}", var_name, err);
-                        Types { name: RustScriptType::UnKnown }
+    //Resolve a written type, a built in keyword, the name of a declared enum or struct or a function type
+    fn annotation_type(&mut self, annotation: &TypeAnnotation) -> Types {
+        match annotation {
+            TypeAnnotation::Named(name) => {
+                //a written type gives a generic struct its type arguments, only values infer them
+                let path = self.item_path(name);
+                let generics = self.structs.get(&path).map_or(0, |struct_type| struct_type.generics.len());
+                let is_param = self.generics.iter().any(|param| name.is_named(&param.name));
+                if generics > 0 && name.is(TokenType::Identifier) && !is_param {
+                    self.wrong_type_arguments(name, generics, 0);
+                    return Types::new(RustScriptType::UnKnown);
+                }
+                self.named_type(name)
+            }
+            TypeAnnotation::Generic { name, args } => {
+                let args: Vec<Types> = args.iter().map(|arg| self.annotation_type(arg)).collect();
+                let type_name = name.as_string();
+                let path = self.item_path(name);
+                //a struct of the program's own hides the built in type of the same name
+                let builtin = match self.structs.contains_key(&path) {
+                    true => None,
+                    false => Some(type_name.as_str()),
+                };
+                let expected = match builtin {
+                    Some("Vec" | "Option") => 1,
+                    Some("Result") => 2,
+                    _ => match self.structs.get(&path) {
+                        Some(struct_type) => struct_type.generics.len(),
+                        None => {
+                            self.error(name, &format!("Unknown type '{}'", type_name));
+                            return Types::new(RustScriptType::UnKnown);
+                        }
                     },
+                };
+                if args.len() != expected {
+                    self.wrong_type_arguments(name, expected, args.len());
+                    return Types::new(RustScriptType::UnKnown);
+                }
+                //a module that names Option or Result hands their values to Javascript, which builds them
+                //with the runtime's objects
+                if matches!(builtin, Some("Option" | "Result")) {
+                    self.types.prelude = true;
+                }
+                let mut args = args.into_iter();
+                let mut arg = || Box::new(args.next().expect("the number of arguments was checked above"));
+                match builtin {
+                    Some("Vec") => Types::new(RustScriptType::Vec(arg())),
+                    Some("Option") => Types::new(RustScriptType::Option(arg())),
+                    Some("Result") => Types::new(RustScriptType::Result(arg(), arg())),
+                    _ => {
+                        let args: Vec<Types> = args.collect();
+                        let generics = self.structs[&path].generics.clone();
+                        for (arg, param) in args.iter().zip(&generics) {
+                            self.require(arg, &param.bounds, name);
+                        }
+                        Types::new(RustScriptType::Struct(path, args))
+                    }
+                }
+            }
+            TypeAnnotation::Array { element, .. } => {
+                Types::new(RustScriptType::Vec(Box::new(self.annotation_type(element))))
+            }
+            TypeAnnotation::Tuple { elements, .. } if elements.is_empty() => Types::new(RustScriptType::Unit),
+            TypeAnnotation::Tuple { elements, .. } => {
+                Types::new(RustScriptType::Tuple(elements.iter().map(|e| self.annotation_type(e)).collect()))
+            }
+            TypeAnnotation::Function { params, return_type, .. } => {
+                let params = params.iter().map(|param| self.annotation_type(param)).collect();
+                let return_type = match return_type {
+                    Some(return_type) => self.annotation_type(return_type),
+                    None => Types::new(RustScriptType::Unit),
+                };
+                Types::new(RustScriptType::Function { params, return_type: Box::new(return_type) })
+            }
+        }
+    }
+
+    fn named_type(&mut self, annotation: &Token) -> Types {
+        let name = annotation.as_string();
+        if annotation.is(TokenType::SelfType) {
+            if let Some(current) = &self.current_impl {
+                return current.clone();
+            }
+            self.error(annotation, "'Self' is only available inside an impl block");
+            return Types::new(RustScriptType::UnKnown);
+        }
+        if annotation.is(TokenType::Identifier) {
+            if matches!(name.as_str(), "i32" | "i64" | "f64") {
+                return Types::from_string(&name);
+            }
+            if let Some(param) = self.generics.iter().find(|param| param.name == name) {
+                return Types::new(RustScriptType::Param(param.clone()));
+            }
+            let path = self.item_path(annotation);
+            if self.enums.contains_key(&path) {
+                return Types::new(RustScriptType::Enum(path));
+            }
+            //the type arguments of a generic struct named without them are inferred
+            if let Some(generics) = self.structs.get(&path).map(|struct_type| struct_type.generics.clone()) {
+                let args = generics.iter().map(|param| self.bounded_var(param, annotation)).collect();
+                return Types::new(RustScriptType::Struct(path, args));
+            }
+            if self.traits.contains_key(&path) {
+                let message = format!("'{}' is a trait, not a type, use a type parameter bound by it", name);
+                self.error(annotation, &message);
+                return Types::new(RustScriptType::UnKnown);
+            }
+            self.error(annotation, &format!("Unknown type '{}'", name));
+            return Types::new(RustScriptType::UnKnown);
+        }
+        Types::from_string(&name)
+    }
+
+    //Check each argument of a call against the parameter types
+    fn tc_args(&mut self, callee: &Token, params: &[Types], args: &[Expr], env: &mut TypeEnvironment) {
+        if args.len() != params.len() {
+            let message = format!(
+                "'{}' expects {} arguments but got {}",
+                callee.as_string(),
+                params.len(),
+                args.len()
+            );
+            self.error(callee, &message);
+        }
+        for (arg, param) in args.iter().zip(params) {
+            let arg_type = self.tc_expected(arg, param, env);
+            self.expect(arg_type, param, arg.token());
+        }
+    }
+
+    //The struct a struct literal, field access or method call is on, reporting when it is not one
+    fn struct_of(&mut self, object_type: &Types, token: &Token) -> Option<StructType> {
+        match &object_type.name {
+            RustScriptType::Struct(name, args) => {
+                self.structs.get(name).map(|struct_type| struct_type.instantiate(args))
+            }
+            RustScriptType::UnKnown => None,
+            _ => {
+                let message = format!(
+                    "Type '{}' has no fields or methods, '{}' is not available",
+                    object_type,
+                    token.as_string()
+                );
+                self.error(token, &message);
+                None
+            }
+        }
+    }
+
+    fn tc_struct_literal(&mut self, name: &Token, fields: &[(Token, Expr)], env: &mut TypeEnvironment) -> Types {
+        let literal_type = self.named_type(name);
+        let Some(struct_type) = self.struct_of(&literal_type, name) else {
+            for (_, value) in fields {
+                self.tc(value, env);
+            }
+            return Types::new(RustScriptType::UnKnown);
+        };
+
+        for (index, (field, value)) in fields.iter().enumerate() {
+            let value_type = self.tc(value, env);
+            if fields[..index].iter().any(|(earlier, _)| earlier.is_named(&field.as_string())) {
+                self.error(field, &format!("Field '{}' specified more than once", field.as_string()));
+            }
+            match struct_type.field(&field.as_string()) {
+                Some(field_type) => {
+                    self.check_field_visible(&struct_type, field);
+                    self.expect(value_type, field_type, value.token());
+                }
+                None => {
+                    let message = format!(
+                        "Struct '{}' has no field named '{}'",
+                        struct_type.name,
+                        field.as_string()
+                    );
+                    self.error(field, &message);
                 }
+            }
+        }
 
-                
+        for (field_name, _) in &struct_type.fields {
+            if !fields.iter().any(|(field, _)| field.is_named(field_name)) {
+                self.error(name, &format!("Missing field '{}' in '{}'", field_name, struct_type.name));
+            }
+        }
+
+        literal_type
+    }
+
+    //Assigning to a field or calling a `&mut self` method mutates the variable the field path starts
+    //from, `p` in `p.a.b = 1` or `p.a[0] = 1`. Records the mutation and returns that variable and where it was
+    //declared when it is not mutable. Temporaries such as call results can be mutated freely
+    fn mutated_root<'e>(&mut self, object: &'e Expr, env: &TypeEnvironment) -> Option<(&'e Token, BindingKind, Span)> {
+        let mut root = object;
+        let variable = loop {
+            match root {
+                Expr::Get { object, .. } | Expr::Index { object, .. } => root = object,
+                Expr::Grouping(inner) => root = inner,
+                Expr::Variable(name) | Expr::SelfValue(name) => break name,
+                _ => return None,
+            }
+        };
+        let (binding, scope) = env.resolve(&variable.as_string()).ok()?;
+        if !binding.is_mutable() {
+            return Some((variable, binding.kind, binding.span));
+        }
+        self.note_mutation(&variable.as_string(), scope, env);
+        None
+    }
+
+    //Closures that capture a variable they assign to are marked as mutating, so calling them
+    //needs a mutable binding
+    fn note_mutation(&mut self, name: &str, scope: Scope, env: &TypeEnvironment) {
+        let depth = env.depth();
+        let declared_at = match scope {
+            Scope::Local => depth,
+            Scope::Enclosing(distance) => depth - distance,
+            Scope::Global => 0,
+        };
+        for closure in self.closures.iter_mut().filter(|closure| declared_at < closure.depth) {
+            closure.mutates.get_or_insert_with(|| name.to_string());
+        }
+    }
+
+    fn immutable_assign(&mut self, name: &Token, kind: BindingKind, declared: Span) {
+        let name_str = name.as_string();
+        let (message, label) = match kind {
+            BindingKind::Variable { .. } | BindingKind::LoopVariable => (
+                format!("Cannot assign twice to immutable variable '{}'", name_str),
+                "cannot assign twice to immutable variable",
+            ),
+            BindingKind::Parameter { .. } => {
+                (format!("Cannot assign to immutable parameter '{}'", name_str), "cannot assign to immutable parameter")
+            }
+            BindingKind::Function => (format!("Cannot assign to function '{}'", name_str), "not a variable"),
+        };
+        let diagnostic = Diagnostic::new(&message, name.span()).label(label).note(declared, &declared_note(name, kind));
+        self.report(diagnostic);
+    }
+
+    //Calling a closure that assigns to what it captured changes its state, so like Rust's `FnMut`
+    //it must be bound with `let mut`
+    fn check_mutating_call(&mut self, name: &Token, env: &TypeEnvironment) {
+        let Ok((binding, scope)) = env.resolve(&name.as_string()) else {
+            return;
+        };
+        let Some(captured) = &binding.mutates else {
+            return;
+        };
+        if binding.is_mutable() {
+            self.note_mutation(&name.as_string(), scope, env);
+            return;
+        }
+        let message = format!("Cannot borrow '{}' as mutable, as it is not declared as mutable", name.as_string());
+        let label = format!("calling '{}' requires a mutable binding, it assigns to '{}'", name.as_string(), captured);
+        let note = declared_note(name, binding.kind);
+        let diagnostic = Diagnostic::new(&message, name.span()).label(&label).note(binding.span, &note);
+        self.report(diagnostic);
+    }
+
+    //`target` describes what is assigned to, such as "field 'x'"
+    fn check_mutable_place(&mut self, object: &Expr, target: &str, token: &Token, env: &TypeEnvironment) {
+        let Some((variable, kind, declared)) = self.mutated_root(object, env) else {
+            return;
+        };
+        let message = format!(
+            "Cannot assign to {} of '{}', as '{}' is not declared as mutable",
+            target,
+            variable.as_string(),
+            variable.as_string()
+        );
+        let note = declared_note(variable, kind);
+        let diagnostic = Diagnostic::new(&message, token.span()).label("cannot assign").note(declared, &note);
+        self.report(diagnostic);
+    }
+
+    //Calling a method that takes `&mut self` mutates the value it is called on
+    fn check_mutable_receiver(&mut self, object: &Expr, method: &Token, env: &TypeEnvironment) {
+        let Some((variable, kind, declared)) = self.mutated_root(object, env) else {
+            return;
+        };
+        let message = format!("Cannot borrow '{}' as mutable, as it is not declared as mutable", variable.as_string());
+        let label = format!("'{}' takes '&mut self'", method.as_string());
+        let note = declared_note(variable, kind);
+        let diagnostic = Diagnostic::new(&message, method.span()).label(&label).note(declared, &note);
+        self.report(diagnostic);
+    }
+
+    //`tuple.0`, the index must be a whole number within the tuple
+    fn tc_tuple_field(&mut self, object_type: Types, index: &Token) -> Types {
+        let elements = match object_type.name {
+            RustScriptType::Tuple(elements) => elements,
+            RustScriptType::UnKnown => return object_type,
+            _ => {
+                let field = index.as_string();
+                let message = format!("Type '{object_type}' is not a tuple, '.{field}' is not available");
+                self.error(index, &message);
+                return Types::new(RustScriptType::UnKnown);
+            }
+        };
+        let element = index.as_string().parse::<usize>().ok().and_then(|i| elements.get(i).cloned());
+        element.unwrap_or_else(|| {
+            let message = format!(
+                "No field '{}' on tuple type '{}'",
+                index.as_string(),
+                Types::new(RustScriptType::Tuple(elements))
+            );
+            self.error(index, &message);
+            Types::new(RustScriptType::UnKnown)
+        })
+    }
+
+    //Every element of an array literal has the type of the first one
+    fn tc_array(&mut self, elements: &[Expr], env: &mut TypeEnvironment) -> Types {
+        //the type of an empty array is inferred from how it is used, like the one `Vec::new()` returns
+        let mut element_type =
+            if elements.is_empty() { self.fresh_var() } else { Types::new(RustScriptType::UnKnown) };
+        let mut first: Option<&Token> = None;
+        for element in elements {
+            let next = self.tc(element, env);
+            match first {
+                _ if next.is_unknown() => {}
+                None => {
+                    element_type = next;
+                    first = Some(element.token());
+                }
+                Some(first) if !self.unify(&next, &element_type) => {
+                    let (element_type, next) = (self.resolve(&element_type), self.resolve(&next));
+                    let message = "Mismatched types in array literal";
+                    let label = format!("expected '{}', found '{}'", element_type, next);
+                    let note = format!("the first element has type '{}'", element_type);
+                    let diagnostic =
+                        Diagnostic::new(message, element.token().span()).label(&label).note(first.span(), &note);
+                    self.report(diagnostic);
+                }
+                Some(_) => {}
+            }
+        }
+        Types::new(RustScriptType::Vec(Box::new(element_type)))
+    }
+
+    fn tc_index(&mut self, object: &Expr, bracket: &Token, index: &Expr, env: &mut TypeEnvironment) -> Types {
+        let object_type = self.tc(object, env);
+        let index_type = self.tc(index, env);
+        self.expect(index_type, &Types::new(RustScriptType::I32), index.token());
+        match object_type.name {
+            RustScriptType::Vec(element) => *element,
+            RustScriptType::UnKnown => object_type,
+            _ => {
+                let message = format!("Cannot index into a value of type '{}'", object_type);
+                self.error(bracket, &message);
+                Types::new(RustScriptType::UnKnown)
+            }
+        }
+    }
+
+    //The methods every vector has, recorded so code generation can lower them to their Javascript equivalents
+    fn tc_vec_method(
+        &mut self,
+        object: &Expr,
+        element: &Types,
+        name: &Token,
+        args: &[Expr],
+        env: &mut TypeEnvironment,
+    ) -> Types {
+        let (builtin, return_type) = match name.as_string().as_str() {
+            "len" => {
+                self.tc_args(name, &[], args, env);
+                (Builtin::Len, Types::new(RustScriptType::I32))
+            }
+            "push" => {
+                self.check_mutable_receiver(object, name, env);
+                self.tc_args(name, std::slice::from_ref(element), args, env);
+                (Builtin::Push, Types::new(RustScriptType::Unit))
+            }
+            _ => {
+                let message = format!("No method '{}' on type 'Vec<{}>'", name.as_string(), element);
+                self.error(name, &message);
+                for arg in args {
+                    self.tc(arg, env);
+                }
+                return Types::new(RustScriptType::UnKnown);
+            }
+        };
+        self.types.builtins.insert(name.offset, builtin);
+        return_type
+    }
+
+    //Outside the module of a struct and the modules inside it, only its `pub` fields can be used
+    fn check_field_visible(&mut self, struct_type: &StructType, field: &Token) {
+        if struct_type.private_fields.contains(&field.as_string()) && !self.can_see_private(&struct_type.name) {
+            let message = format!("Field '{}' of struct '{}' is private", field.as_string(), struct_type.name);
+            self.report(Diagnostic::new(&message, field.span()).label("private field"));
+        }
+    }
+
+    fn check_method_visible(&mut self, struct_type: &StructType, method: &MethodType, name: &Token) {
+        if !method.public && !self.can_see_private(&struct_type.name) {
+            let message = format!("Method '{}' of '{}' is private", name.as_string(), struct_type.name);
+            self.report(Diagnostic::new(&message, name.span()).label("private method"));
+        }
+    }
+
+    fn tc_get(&mut self, object: &Expr, name: &Token, env: &mut TypeEnvironment) -> Types {
+        let object_type = self.tc(object, env);
+        if name.is(TokenType::Number) {
+            return self.tc_tuple_field(object_type, name);
+        }
+        let Some(struct_type) = self.struct_of(&object_type, name) else {
+            return Types::new(RustScriptType::UnKnown);
+        };
+
+        match struct_type.field(&name.as_string()) {
+            Some(field_type) => {
+                self.check_field_visible(&struct_type, name);
+                field_type.clone()
+            }
+            None => {
+                if struct_type.methods.contains_key(&name.as_string()) {
+                    let message = format!(
+                        "'{}' is a method of '{}', call it with '{}()'",
+                        name.as_string(),
+                        struct_type.name,
+                        name.as_string()
+                    );
+                    self.error(name, &message);
+                } else {
+                    let message = format!(
+                        "No field '{}' on type '{}'",
+                        name.as_string(),
+                        struct_type.name
+                    );
+                    self.error(name, &message);
+                }
+                Types::new(RustScriptType::UnKnown)
+            }
+        }
+    }
+
+    //The parameter and return types of a method called as `name`, `substitution` gives the types of
+    //the struct's type parameters or of a trait's `Self`. The method's own type parameters are
+    //inferred from its arguments like a generic function's
+    fn instantiate_method(
+        &mut self,
+        mut substitution: HashMap<String, Types>,
+        method: &MethodType,
+        name: &Token,
+    ) -> (Vec<Types>, Types) {
+        for param in &method.requires {
+            let arg = substitution[&param.name].clone();
+            self.require(&arg, &param.bounds, name);
+        }
+        for generic in &method.generics {
+            let var = self.bounded_var(generic, name);
+            substitution.insert(generic.name.clone(), var);
+        }
+        let params = method.params.iter().map(|param| param.substitute(&substitution)).collect();
+        (params, method.return_type.substitute(&substitution))
+    }
+
+    fn tc_method_call(&mut self, object: &Expr, name: &Token, args: &[Expr], env: &mut TypeEnvironment) -> Types {
+        let object_type = self.tc(object, env);
+        if let RustScriptType::Vec(element) = &object_type.name {
+            return self.tc_vec_method(object, element, name, args, env);
+        }
+        if let RustScriptType::Param(param) = &object_type.name {
+            return self.tc_bound_method_call(object, &object_type, param, name, args, env);
+        }
+        let Some(struct_type) = self.struct_of(&object_type, name) else {
+            for arg in args {
+                self.tc(arg, env);
+            }
+            return Types::new(RustScriptType::UnKnown);
+        };
+
+        if let Some(method) = struct_type.methods.get(&name.as_string()) {
+            self.check_method_visible(&struct_type, method, name);
+        }
+        match struct_type.methods.get(&name.as_string()) {
+            Some(method) if method.takes_self => {
+                if method.mutates_self {
+                    self.check_mutable_receiver(object, name, env);
+                }
+                let RustScriptType::Struct(_, struct_args) = &object_type.name else {
+                    unreachable!("only structs have methods");
+                };
+                let substitution = struct_type.substitution(struct_args);
+                let (params, return_type) = self.instantiate_method(substitution, method, name);
+                self.tc_args(name, &params, args, env);
+                return_type
+            }
+            Some(_) => {
+                let message = format!(
+                    "'{}' is an associated function, call it with '{}::{}()'",
+                    name.as_string(),
+                    struct_type.name,
+                    name.as_string()
+                );
+                self.error(name, &message);
+                Types::new(RustScriptType::UnKnown)
+            }
+            None => {
+                let message = format!(
+                    "No method '{}' on type '{}'",
+                    name.as_string(),
+                    struct_type.name
+                );
+                self.error(name, &message);
+                Types::new(RustScriptType::UnKnown)
+            }
+        }
+    }
+
+    //Nothing is known about a value whose type is a type parameter but the traits it is bound by,
+    //so only their methods can be called on it
+    fn tc_bound_method_call(
+        &mut self,
+        object: &Expr,
+        object_type: &Types,
+        param: &TypeParam,
+        name: &Token,
+        args: &[Expr],
+        env: &mut TypeEnvironment,
+    ) -> Types {
+        let method = param
+            .bounds
+            .iter()
+            .filter_map(|bound| self.traits.get(bound)?.methods.get(&name.as_string()))
+            .find(|method| method.takes_self)
+            .cloned();
+        let Some(method) = method else {
+            let message = format!(
+                "No method '{}' on type '{}', only the methods of the traits it is bound by can be called",
+                name.as_string(),
+                object_type
+            );
+            self.error(name, &message);
+            for arg in args {
+                self.tc(arg, env);
+            }
+            return Types::new(RustScriptType::UnKnown);
+        };
+        if method.mutates_self {
+            self.check_mutable_receiver(object, name, env);
+        }
+        let substitution = HashMap::from([("Self".to_string(), object_type.clone())]);
+        let (params, return_type) = self.instantiate_method(substitution, &method, name);
+        self.tc_args(name, &params, args, env);
+        return_type
+    }
+
+    //Struct::function(..), a call to an associated function
+    fn tc_associated_call(&mut self, path: &[Token], args: &[Expr], env: &mut TypeEnvironment) -> Types {
+        let struct_type = self.structs[&self.item_path(&path[0])].clone();
+        let name = &path[path.len() - 1];
+        if let Some(method) = struct_type.methods.get(&name.as_string()) {
+            self.check_method_visible(&struct_type, method, name);
+        }
+        match struct_type.methods.get(&name.as_string()) {
+            Some(method) if path.len() == 2 => {
+                if method.takes_self {
+                    let message = format!(
+                        "'{}' is a method, call it on a value with '.{}()'",
+                        name.as_string(),
+                        name.as_string()
+                    );
+                    self.error(name, &message);
+                }
+                //the struct's type arguments are inferred too, `Pair::new(1, "a")` is a `Pair<i32, str>`
+                let struct_args: Vec<Types> =
+                    struct_type.generics.iter().map(|param| self.bounded_var(param, name)).collect();
+                let substitution = struct_type.substitution(&struct_args);
+                let (params, return_type) = self.instantiate_method(substitution, method, name);
+                self.tc_args(name, &params, args, env);
+                return_type
+            }
+            _ => {
+                let message = format!(
+                    "No function '{}' in impl of '{}'",
+                    name.as_string(),
+                    struct_type.name
+                );
+                self.error(name, &message);
+                Types::new(RustScriptType::UnKnown)
+            }
+        }
+    }
+
+    //Find the enum an Enum::Variant path names and the variant's payload types
+    fn lookup_variant(&mut self, path: &[Token]) -> Option<(String, Vec<Types>)> {
+        let [enum_name, variant] = path else {
+            //a longer path starts with the modules an item is in, its first segment is not one
+            let label = format!("could not find '{}' in '{}'", path[0].as_string(), display(self.module));
+            self.report(Diagnostic::new("Unresolved path", path[0].span()).label(&label));
+            return None;
+        };
+
+        let Some(enum_type) = self.enums.get(&self.item_path(enum_name)) else {
+            self.error(enum_name, &format!("Undefined enum '{}'", enum_name.as_string()));
+            return None;
+        };
+
+        match enum_type.variant(&variant.as_string()) {
+            Some(fields) => Some((enum_type.name.clone(), fields.clone())),
+            None => {
+                let message = format!(
+                    "No variant '{}' in enum '{}'",
+                    variant.as_string(),
+                    enum_type.name
+                );
+                self.error(variant, &message);
+                None
+            }
+        }
+    }
+
+    //`util::twice`, a function named by the modules it is in instead of imported, which it can be
+    //where a `use` could import it
+    fn tc_module_path(&mut self, segments: &[Token], module: &str, named: usize) -> Types {
+        let name = &segments[named];
+        let name_str = name.as_string();
+        let Some((public, export)) = self.exports.get(module).and_then(|items| items.get(&name_str)).cloned() else {
+            let label = format!("no '{}' in '{}'", name_str, display(module));
+            self.report(Diagnostic::new("Unresolved path", name.span()).label(&label));
+            return Types::new(RustScriptType::UnKnown);
+        };
+        if !public && !is_within(self.module, module) {
+            let message = format!("'{}' is private", name_str);
+            self.report(Diagnostic::new(&message, name.span()).label("private item"));
+            return Types::new(RustScriptType::UnKnown);
+        }
+        match export {
+            Export::Function(fn_type) if segments.len() == named + 1 => self.instantiate(&fn_type, name),
+            _ => {
+                let modules: Vec<String> = segments[..named].iter().map(Token::as_string).collect();
+                let footer = match module == self.module {
+                    true => format!("'{}' can be named without its path here", name_str),
+                    false => format!("import it with `use {}::{};`", modules.join("::"), name_str),
+                };
+                let message = "Only functions can be named through the modules they are in";
+                let diagnostic = Diagnostic::new(message, name.span()).label("not a function")
+                    .footer(&footer);
+                self.report(diagnostic);
+                Types::new(RustScriptType::UnKnown)
+            }
+        }
+    }
+
+    //Enum::Variant, with the payload values when the variant is called like a function
+    fn tc_variant(&mut self, path: &[Token], args: Option<&[Expr]>, env: &mut TypeEnvironment) -> Types {
+        if let Some((enum_name, variant)) = self.prelude_path(path) {
+            return self.tc_prelude_variant(enum_name, variant, args.unwrap_or(&[]), env);
+        }
+        let Some((enum_name, fields)) = self.lookup_variant(path) else {
+            return Types::new(RustScriptType::UnKnown);
+        };
+
+        let args = args.unwrap_or(&[]);
+        if args.len() != fields.len() {
+            let message = format!(
+                "Variant '{}::{}' expects {} values but got {}",
+                enum_name,
+                path[1].as_string(),
+                fields.len(),
+                args.len()
+            );
+            self.error(&path[1], &message);
+        }
+        for (arg, field) in args.iter().zip(&fields) {
+            let arg_type = self.tc(arg, env);
+            self.expect(arg_type, field, arg.token());
+        }
+
+        Types::new(RustScriptType::Enum(enum_name))
+    }
+
+    //`Some(x)`, `None`, `Ok(x)` and `Err(e)`, the type parameters their payload does not decide are inferred
+    //from how the value is used
+    fn tc_prelude_variant(
+        &mut self,
+        enum_name: &str,
+        variant: &Token,
+        args: &[Expr],
+        env: &mut TypeEnvironment,
+    ) -> Types {
+        self.types.prelude = true;
+        let fields = if variant.is_named("None") { 0 } else { 1 };
+        if args.len() != fields {
+            let message = format!(
+                "Variant '{}::{}' expects {} values but got {}",
+                enum_name,
+                variant.as_string(),
+                fields,
+                args.len()
+            );
+            self.error(variant, &message);
+        }
+        let arg_types: Vec<Types> = args.iter().map(|arg| self.tc(arg, env)).collect();
+        let mut inferred = || Box::new(self.fresh_var());
+        let payload = arg_types.into_iter().next().map(Box::new).unwrap_or_else(&mut inferred);
+        match variant.as_string().as_str() {
+            "Some" => Types::new(RustScriptType::Option(payload)),
+            "None" => Types::new(RustScriptType::Option(inferred())),
+            "Ok" => Types::new(RustScriptType::Result(payload, inferred())),
+            _ => Types::new(RustScriptType::Result(inferred(), payload)),
+        }
+    }
+
+    //`value?` gives the payload of a `Some` or `Ok` and returns a `None` or `Err` as it is, so the
+    //enclosing function must return the same kind of enum, with the same error type for a `Result`
+    fn tc_try(&mut self, value: &Expr, question: &Token, env: &mut TypeEnvironment) -> Types {
+        let value_type = self.tc(value, env);
+        let (payload, error) = match value_type.name {
+            RustScriptType::Option(payload) => (*payload, None),
+            RustScriptType::Result(payload, error) => (*payload, Some(*error)),
+            RustScriptType::UnKnown => return value_type,
+            _ => {
+                let message =
+                    format!("The '?' operator can only be applied to 'Option' or 'Result', found '{value_type}'");
+                self.report(Diagnostic::new(&message, question.span()).label("cannot use '?' here"));
+                return Types::new(RustScriptType::UnKnown);
+            }
+        };
+        let Some(return_type) = self.current_return.clone() else {
+            self.error(question, "Cannot use '?' outside of a function");
+            return payload;
+        };
+        self.propagates = true;
+
+        let return_type = self.resolve(&return_type);
+        let (article, enum_name) = if error.is_some() { ("a", "Result") } else { ("an", "Option") };
+        match (&return_type.name, error) {
+            //a closure whose return type is not decided yet returns what `?` hands back
+            (RustScriptType::Var(_), error) => {
+                let returned = match error {
+                    Some(error) => RustScriptType::Result(Box::new(self.fresh_var()), Box::new(error)),
+                    None => RustScriptType::Option(Box::new(self.fresh_var())),
+                };
+                self.unify(&return_type, &Types::new(returned));
+            }
+            (RustScriptType::UnKnown, _) | (RustScriptType::Option(_), None) => {}
+            (RustScriptType::Result(_, return_error), Some(error)) => {
+                if !self.unify(&error, return_error) {
+                    let (error, return_error) = (self.resolve(&error), self.resolve(return_error));
+                    let message =
+                        format!("Mismatched error type for '?': expected '{return_error}' but found '{error}'");
+                    let label = format!("the function returns '{}'", return_type);
+                    self.report(Diagnostic::new(&message, question.span()).label(&label));
+                }
+            }
+            _ => {
+                let message = format!(
+                    "The '?' operator on {} '{}' can only be used in a function that returns '{}'",
+                    article,
+                    enum_name,
+                    enum_name
+                );
+                let label = format!("the function returns '{}'", return_type);
+                self.report(Diagnostic::new(&message, question.span()).label(&label));
+            }
+        }
+        payload
+    }
+
+    //functions get_operand_types() and expect_operator_type() make avaiable binary operators for numbers and string
+    // make sure strings cannot do * / - , but numbers can do all four. `&&` and `||` only take bool.
+    fn get_operand_types(&self, operator: TokenType) -> Vec<Types> {
+        let str_type = Types::new(RustScriptType::String);
+        let bool_type = Types::new(RustScriptType::Bool);
+
+        match operator {
+            TokenType::Plus => [vec![str_type], numeric_types()].concat(),
+            TokenType::Minus | TokenType::Slash | TokenType::Star => numeric_types(),
+            TokenType::Less | TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual => numeric_types(),
+            TokenType::And | TokenType::Or => vec![bool_type],
+            _ => unreachable!("{:?} is not a binary operator", operator),
+        }
+    }
+
+    fn expect_operator_type(&mut self, operand_type: &Types, allowed_types: &[Types], operator: &Token) {
+        //an undecided integer literal can be any of the numeric types allowed
+        let operand_type = &self.resolve(operand_type);
+        let allowed = match operand_type.name {
+            RustScriptType::IntVar(_) => allowed_types.iter().any(|t| t.is_numeric()),
+            //an undecided type variable takes the type of the other operand
+            RustScriptType::Var(_) => true,
+            _ => allowed_types.iter().any(|t| t.equals(operand_type)),
+        };
+        if !operand_type.is_unknown() && !allowed {
+            let allowed: Vec<String> = allowed_types.iter().map(|t| t.to_string()).collect();
+            let allowed = allowed.join(", ");
+            let message = format!(
+                "Unexpected type: '{}' in operation '{}', allowed '{}'",
+                operand_type,
+                operator.as_string(),
+                allowed
+            );
+            self.error(operator, &message);
+        }
+    }
+
+    //Checks if both operands Types match ie str, str and num, num
+    fn expect(&mut self, actual_type: Types, expected_type: &Types, token: &Token) -> Types {
+        if !self.unify(&actual_type, expected_type) {
+            let (actual_type, expected_type) = (self.resolve(&actual_type), self.resolve(expected_type));
+            let message = format!(
+                "Expected '{}' type for '{}' but got '{}'",
+                expected_type,
+                token.as_string(),
+                actual_type
+            );
+            let label = format!("expected '{}', found '{}'", expected_type, actual_type);
+            self.report(Diagnostic::new(&message, token.span()).label(&label));
+        }
+        actual_type
+    }
+
+    //The type of a value that comes from one of two branches. A branch that never finishes, because
+    //it returns or breaks, does not produce a value, so the other branch decides the type
+    fn join(&mut self, current: Types, next: Types, what: &str, token: &Token) -> Types {
+        if current.is_never() {
+            return next;
+        }
+        if current.is_unknown() {
+            return if next.is_never() { current } else { next };
+        }
+        if !next.is_never() && !self.unify(&next, &current) {
+            let (current, next) = (self.resolve(&current), self.resolve(&next));
+            let message = format!(
+                "{} have incompatible types: expected '{}' but got '{}'",
+                what,
+                current,
+                next
+            );
+            self.error(token, &message);
+        }
+        current
+    }
+
+    //Get types for both operands. See binary operations available for these operands match the types
+    //so only the same types can do binary operations on one another ie num + num, str + str
+    fn binary(&mut self, left: &Expr, operator: &Token, right: &Expr, env: &mut TypeEnvironment) -> Types {
+        let t1 = self.tc(left, env);
+        let t2 = self.tc(right, env);
+
+        if operator.is(TokenType::Equals) || operator.is(TokenType::BangEqual) {
+            // any two values of the same type can be compared for equality
+            self.expect(t2, &t1, operator);
+            self.comparisons.push((operator.offset, t1));
+            return Types::new(RustScriptType::Bool);
+        }
+
+        let allow_types = self.get_operand_types(operator.token_type());
+        self.expect_operator_type(&t1, &allow_types, operator);
+        self.expect_operator_type(&t2, &allow_types, operator);
+
+        let result = self.expect(t2, &t1, operator);
+        if matches!(operator.token_type(), TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash) {
+            self.arithmetic.push((operator.offset, result.clone()));
+        }
+        match operator.token_type() {
+            TokenType::Less
+            | TokenType::LessEqual
+            | TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::And
+            | TokenType::Or => Types::new(RustScriptType::Bool),
+            _ => result,
+        }
+    }
+
+    //Conditions must be bool, Javascript's truthiness of numbers, strings and objects does not apply
+    fn tc_condition(&mut self, condition: &Expr, env: &mut TypeEnvironment) {
+        let condition_type = self.tc(condition, env);
+        if !self.unify(&condition_type, &Types::new(RustScriptType::Bool)) {
+            let condition_type = self.resolve(&condition_type);
+            let message = format!("Expected a 'bool' condition but got '{}'", condition_type);
+            let label = format!("expected 'bool', found '{}'", condition_type);
+            self.report(Diagnostic::new(&message, condition.token().span()).label(&label));
+        }
+    }
+
+    //The type of an expression with the type variables decided so far replaced by their types
+    pub fn tc(&mut self, exp: &Expr, env: &mut TypeEnvironment) -> Types {
+        let ty = self.tc_expr(exp, env);
+        self.resolve(&ty)
+    }
+
+    fn tc_expr(&mut self, exp: &Expr, env: &mut TypeEnvironment) -> Types {
+        match exp {
+            Expr::Literal { value, token } => match value {
+                Object::Str(_) => Types::new(RustScriptType::String),
+                Object::Num(_) => self.number_literal(token, false),
+                Object::Bool(_) => Types::new(RustScriptType::Bool),
             },
-            AstType::LeftBrace => {
-                // println!("LEFT BRACE");
-                let mut block_env = TypeEnvironment::branch_env(env.record.clone());
-                self.tc_block(exp.to_owned(), &mut block_env)
+            //each use of a generic function infers its type parameters anew
+            Expr::Variable(name) => match env.resolve(&name.as_string()) {
+                Ok((binding, _)) if binding.kind == BindingKind::Function => {
+                    let fn_type = binding.vtype.clone();
+                    self.instantiate(&fn_type, name)
+                }
+                Ok((binding, _)) => binding.vtype.clone(),
+                Err(_) => {
+                    self.undefined_variable(name);
+                    Types::new(RustScriptType::UnKnown)
+                }
             },
-            _ =>  {
-                println!("Unknown type for expression: {:?}", exp);
-                return Types::new(RustScriptType::UnKnown);
+            Expr::Assign { name, value } => {
+                //The type of the new value should match to the
+                //previous type when the variable was defined
+                let value_type = self.tc(value, env);
+                match env.resolve(&name.as_string()) {
+                    Ok((binding, scope)) => {
+                        if binding.is_mutable() {
+                            self.note_mutation(&name.as_string(), scope, env);
+                        } else {
+                            self.immutable_assign(name, binding.kind, binding.span);
+                        }
+                        if !self.unify(&value_type, &binding.vtype) {
+                            let (value_type, var_type) = (self.resolve(&value_type), self.resolve(&binding.vtype));
+                            let message = format!(
+                                "Cannot assign '{}' to '{}', it was declared as '{}' in the {} scope",
+                                value_type,
+                                name.as_string(),
+                                var_type,
+                                scope
+                            );
+                            self.error(name, &message);
+                        }
+                    }
+                    Err(_) => self.undefined_variable(name),
+                }
+                Types::new(RustScriptType::Unit)
+            }
+            Expr::Binary { left, operator, right } => self.binary(left, operator, right, env),
+            Expr::Unary { operator, right } => {
+                let right_type = match right.as_ref() {
+                    Expr::Literal { value: Object::Num(_), token } if operator.is(TokenType::Minus) => {
+                        self.number_literal(token, true)
+                    }
+                    right => self.tc(right, env),
+                };
+                if operator.is(TokenType::Minus) {
+                    self.expect_operator_type(&right_type, &numeric_types(), operator);
+                    right_type
+                } else {
+                    let bool_type = Types::new(RustScriptType::Bool);
+                    self.expect(right_type, &bool_type, operator);
+                    bool_type
+                }
+            }
+            Expr::Cast { value, keyword, target } => {
+                let value_type = self.tc(value, env);
+                let target_type = self.annotation_type(target);
+                let resolved = self.resolve(&value_type);
+                let castable = |ty: &Types| ty.is_numeric() || ty.is_unknown();
+                if castable(&resolved) && castable(&target_type) {
+                    self.casts.push((keyword.offset, value_type, target_type.clone()));
+                } else {
+                    let message = format!("Cannot cast '{}' as '{}', only numbers can be cast", resolved, target_type);
+                    self.report(Diagnostic::new(&message, keyword.span()).label("invalid cast"));
+                }
+                target_type
+            }
+            Expr::Grouping(expr) => self.tc(expr, env),
+            Expr::Tuple { elements, .. } => {
+                let elements = elements.iter().map(|e| self.tc(e, env)).collect();
+                Types::new(RustScriptType::Tuple(elements))
+            }
+            Expr::Array { elements, .. } => self.tc_array(elements, env),
+            Expr::Index { object, bracket, index } => self.tc_index(object, bracket, index, env),
+            Expr::SetIndex { object, bracket, index, value } => {
+                self.check_mutable_place(object, "an element", bracket, env);
+                let element_type = self.tc_index(object, bracket, index, env);
+                let value_type = self.tc_expected(value, &element_type, env);
+                self.expect(value_type, &element_type, value.token());
+                Types::new(RustScriptType::Unit)
+            }
+            Expr::Match { keyword, scrutinee, arms } => self.tc_match(keyword, scrutinee, arms, env),
+            Expr::Range { start, end, .. } => {
+                //Ranges count over integers, so iterating one yields an integer of the type of its bounds
+                let start_type = self.tc(start, env);
+                let end_type = self.tc(end, env);
+                self.expect(end_type, &start_type, end.token());
+                let range_type = self.resolve(&start_type);
+                if !range_type.is_integer() && !range_type.is_unknown() {
+                    let message = format!("Ranges count over integers, found '{}'", range_type);
+                    self.error(start.token(), &message);
+                }
+                range_type
+            }
+            Expr::SelfValue(keyword) => match env.lookup(keyword.as_string()) {
+                Ok(self_type) => self_type.to_owned(),
+                Err(_) => {
+                    self.error(keyword, "'self' is only available inside methods");
+                    Types::new(RustScriptType::UnKnown)
+                }
+            },
+            Expr::StructLiteral { name, fields } => self.tc_struct_literal(name, fields, env),
+            Expr::Get { object, name } => self.tc_get(object, name, env),
+            Expr::Set { object, name, value } => {
+                self.check_mutable_place(object, &format!("field '{}'", name.as_string()), name, env);
+                let field_type = self.tc_get(object, name, env);
+                let value_type = self.tc(value, env);
+                self.expect(value_type, &field_type, value.token());
+                Types::new(RustScriptType::Unit)
+            }
+            Expr::Path(segments) => match self.paths.get(&segments[0].offset) {
+                Some((module, named)) => self.tc_module_path(segments, module, *named),
+                None => self.tc_variant(segments, None, env),
             },
-        } 
+            Expr::Call { callee, args, .. } => {
+                match callee.as_ref() {
+                    Expr::Path(segments) if self.paths.contains_key(&segments[0].offset) => {}
+                    Expr::Path(segments) if self.structs.contains_key(&self.item_path(&segments[0])) => {
+                        return self.tc_associated_call(segments, args, env);
+                    }
+                    Expr::Path(segments) if is_vec_new(segments) => {
+                        if !args.is_empty() {
+                            self.error(&segments[1], &format!("'Vec::new' expects 0 arguments but got {}", args.len()));
+                        }
+                        return Types::new(RustScriptType::Vec(Box::new(self.fresh_var())));
+                    }
+                    Expr::Path(segments) => return self.tc_variant(segments, Some(args), env),
+                    Expr::Get { object, name } => return self.tc_method_call(object, name, args, env),
+                    Expr::Variable(name) => self.check_mutating_call(name, env),
+                    _ => {}
+                }
+                match self.tc(callee, env).name {
+                    RustScriptType::Function { params, return_type } => {
+                        self.tc_args(callee.token(), &params, args, env);
+                        *return_type
+                    }
+                    callee_type => {
+                        if callee_type != RustScriptType::UnKnown {
+                            let message = format!(
+                                "'{}' is not a function, it has type '{}'",
+                                callee.token().as_string(),
+                                Types::new(callee_type)
+                            );
+                            self.error(callee.token(), &message);
+                        }
+                        for arg in args {
+                            self.tc(arg, env);
+                        }
+                        Types::new(RustScriptType::UnKnown)
+                    }
+                }
+            }
+            Expr::Try { value, question } => self.tc_try(value, question, env),
+            Expr::Closure { pipe, params, return_type, body } => {
+                self.tc_closure(pipe, params, return_type, body, None, env).0
+            }
+        }
     }
 
-    pub fn exec(&mut self, exp: Vec<AstType>) -> Types {
-        let mut global_env = TypeEnvironment::new();
-        self.tc(&exp, &mut global_env)
+    //Check an expression where a value of a known type is expected, closures take the types of
+    //parameters they leave unannotated from it
+    fn tc_expected(&mut self, expr: &Expr, expected: &Types, env: &mut TypeEnvironment) -> Types {
+        match expr {
+            Expr::Closure { pipe, params, return_type, body } => {
+                self.tc_closure(pipe, params, return_type, body, Some(expected), env).0
+            }
+            _ => self.tc(expr, env),
+        }
     }
 
-    pub fn test(&mut self, exp: Vec<AstType>, expected: Types) -> bool {
-        let actual = self.exec(exp.clone());
-        if actual.equals(expected.clone()) {
-            println!("Type Match!");
-            true
-        } else {
-            println!("Expected to return '{:?}' for Expression '{:?}' but got '{:?}'", expected, &exp, actual);
-            false
+    //A closure has a function type. Its body is checked in a scope nested in the one it is written
+    //in, so it can use the variables around it, and assigning to one of them marks the closure as
+    //mutating. Returns the closure's type and the first captured variable it assigns to
+    fn tc_closure(
+        &mut self,
+        pipe: &Token,
+        params: &[ClosureParam],
+        return_type: &Option<TypeAnnotation>,
+        body: &ArmBody,
+        expected: Option<&Types>,
+        env: &mut TypeEnvironment,
+    ) -> (Types, Option<String>) {
+        let expected = expected.map(|expected| self.resolve(expected));
+        let expected_params = match expected.map(|expected| expected.name) {
+            Some(RustScriptType::Function { params: expected, .. }) if expected.len() == params.len() => expected,
+            _ => vec![Types::new(RustScriptType::UnKnown); params.len()],
+        };
+        let param_types: Vec<Types> = params
+            .iter()
+            .zip(expected_params)
+            .map(|(param, expected)| match &param.annotation {
+                Some(annotation) => self.annotation_type(annotation),
+                None => expected,
+            })
+            .collect();
+        let declared_return = return_type.as_ref().map(|annotation| self.annotation_type(annotation));
+        self.check_unique(params.iter().map(|param| &param.name).collect());
+
+        //`return` in the body leaves the closure, without a declared return type the values it
+        //returns and the body's value decide it together
+        let return_type = declared_return.unwrap_or_else(|| self.fresh_var());
+        let enclosing = self.current_return.replace(return_type.clone());
+        let enclosing_propagates = std::mem::replace(&mut self.propagates, false);
+        let enclosing_loops = std::mem::replace(&mut self.loops, 0);
+        let body_type = env.scoped(|closure_env| {
+            for (param, param_type) in params.iter().zip(&param_types) {
+                let kind = BindingKind::Parameter { mutable: param.mutable };
+                closure_env.define(&param.name, param_type.clone(), kind);
+            }
+            self.closures.push(ClosureScope { depth: closure_env.depth(), mutates: None });
+            let body_type = match body {
+                ArmBody::Expr(expr) => self.tc(expr, closure_env),
+                ArmBody::Block(statements) => self.tc_block(statements, closure_env),
+            };
+            (body_type, self.closures.pop().expect("pushed above").mutates)
+        });
+        self.loops = enclosing_loops;
+        self.current_return = enclosing;
+        if std::mem::replace(&mut self.propagates, enclosing_propagates) {
+            self.types.propagates.insert(pipe.offset);
+        }
+        let (body_type, mutates) = body_type;
+
+        if !body_type.is_never() {
+            let token = match body {
+                ArmBody::Block(statements) => match statements.last() {
+                    Some(Stmt::Tail(expr)) => expr.token(),
+                    _ => pipe,
+                },
+                ArmBody::Expr(expr) => expr.token(),
+            };
+            self.expect(body_type, &return_type, token);
         }
+        let closure_type = RustScriptType::Function { params: param_types, return_type: Box::new(return_type) };
+        (Types::new(closure_type), mutates)
     }
 
-    fn tc_block(&mut self, exp: Vec<AstType>, env: &mut TypeEnvironment) -> Types {
-        let mut statements: Vec<Vec<AstType>> = Vec::new();
-        let mut temp: Vec<AstType> = Vec::new();
-        let mut in_branch = 0;
+    //Every arm must produce the same type, and together the patterns must cover every value
+    fn tc_match(&mut self, keyword: &Token, scrutinee: &Expr, arms: &[MatchArm], env: &mut TypeEnvironment) -> Types {
+        let scrutinee_type = self.tc(scrutinee, env);
+        let mut result = match arms.is_empty() {
+            true => Types::new(RustScriptType::UnKnown),
+            false => Types::new(RustScriptType::Never),
+        };
 
-        for operands in exp[1..].iter() {
+        for arm in arms {
+            let arm_type = env.scoped(|arm_env| {
+                self.tc_pattern(&arm.pattern, &scrutinee_type, arm_env);
+                match &arm.body {
+                    ArmBody::Expr(expr) => self.tc(expr, arm_env),
+                    ArmBody::Block(statements) => self.tc_block(statements, arm_env),
+                }
+            });
 
-            //These two ifs should be for storing {{}} without going down
-            if operands == &AstType::LeftBrace {
-                temp.push(operands.clone());
-                in_branch = in_branch + 1;
-                continue
-            }
+            result = self.join(result, arm_type, "Match arms", arm.pattern.token());
+        }
 
-            if in_branch != 0 {
-                //Store everything within {}
-                temp.push(operands.clone());
+        let patterns: Vec<&Pattern> = arms.iter().map(|arm| &arm.pattern).collect();
+        if let Some(missing) = missing_pattern(&patterns, &scrutinee_type, &self.declared()) {
+            let message = format!("Non-exhaustive match: pattern '{}' not covered", missing);
+            let label = format!("pattern '{}' not covered", missing);
+            self.report(Diagnostic::new(&message, keyword.span()).label(&label));
+        }
+
+        result
+    }
 
-                if operands == &AstType::RightBrace {
-                    in_branch = in_branch - 1;
+    //Check a pattern can match a value of the expected type and define the names it binds
+    fn tc_pattern(&mut self, pattern: &Pattern, expected: &Types, env: &mut TypeEnvironment) {
+        let expected = &self.resolve(expected);
+        let unknown = Types::new(RustScriptType::UnKnown);
+        match pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding { name, mutable } => {
+                env.define(name, expected.clone(), BindingKind::Variable { mutable: *mutable });
+            }
+            Pattern::Literal { value, token } => {
+                let literal_type = match value {
+                    Object::Num(value) => self.number_literal(token, *value < 0.0),
+                    Object::Str(_) => Types::new(RustScriptType::String),
+                    Object::Bool(_) => Types::new(RustScriptType::Bool),
+                };
+                self.expect(literal_type, expected, token);
+            }
+            Pattern::Variant { path, fields } => {
+                let (enum_name, field_types) = match self.prelude_path(path) {
+                    Some((enum_name, variant)) => {
+                        let (pattern_type, field_types) = prelude_payload(enum_name, variant, expected);
+                        self.expect(pattern_type, expected, pattern.token());
+                        (enum_name.to_string(), field_types)
+                    }
+                    None => {
+                        let Some((enum_name, field_types)) = self.lookup_variant(path) else {
+                            return;
+                        };
+                        self.expect(Types::new(RustScriptType::Enum(enum_name.clone())), expected, pattern.token());
+                        (enum_name, field_types)
+                    }
+                };
 
-                    if in_branch == 0 {
-                        statements.push(temp);
-                        temp = Vec::new();
+                if fields.len() != field_types.len() {
+                    let message = format!(
+                        "Variant '{}::{}' has {} fields but the pattern has {}",
+                        enum_name,
+                        pattern.token().as_string(),
+                        field_types.len(),
+                        fields.len()
+                    );
+                    self.error(pattern.token(), &message);
+                }
+                for (field, field_type) in fields.iter().zip(&field_types) {
+                    self.tc_pattern(field, field_type, env);
+                }
+            }
+            Pattern::Tuple { paren, elements } => {
+                let element_types = match &expected.name {
+                    RustScriptType::Tuple(types) if types.len() == elements.len() => types.clone(),
+                    RustScriptType::UnKnown => vec![unknown; elements.len()],
+                    _ => {
+                        let message = format!(
+                            "Expected '{}' but found a tuple pattern with {} elements",
+                            expected,
+                            elements.len()
+                        );
+                        self.error(paren, &message);
+                        vec![Types::new(RustScriptType::UnKnown); elements.len()]
                     }
+                };
+                for (element, element_type) in elements.iter().zip(&element_types) {
+                    self.tc_pattern(element, element_type, env);
                 }
+            }
+            Pattern::Struct { name, fields, rest } => self.tc_struct_pattern(name, fields, *rest, expected, env),
+        }
+    }
 
-                continue
+    //Every field named must exist on the struct, and every field must be named unless the pattern ends with `..`
+    fn tc_struct_pattern(
+        &mut self,
+        name: &Token,
+        fields: &[(Token, Pattern)],
+        rest: bool,
+        expected: &Types,
+        env: &mut TypeEnvironment,
+    ) {
+        let struct_type = self.named_type(name);
+        self.expect(struct_type.clone(), expected, name);
+        let Some(struct_type) = self.struct_of(&struct_type, name) else {
+            for (_, field) in fields {
+                self.tc_pattern(field, &Types::new(RustScriptType::UnKnown), env);
             }
+            return;
+        };
+
+        for (field, pattern) in fields {
+            let field_type = match struct_type.field(&field.as_string()) {
+                Some(field_type) => {
+                    self.check_field_visible(&struct_type, field);
+                    field_type.clone()
+                }
+                None => {
+                    let message = format!("Struct '{}' has no field named '{}'", struct_type.name, field.as_string());
+                    self.error(field, &message);
+                    Types::new(RustScriptType::UnKnown)
+                }
+            };
+            self.tc_pattern(pattern, &field_type, env);
+        }
+        if rest {
+            return;
+        }
+        let missing: Vec<String> = struct_type
+            .fields
+            .iter()
+            .filter(|(field_name, _)| !fields.iter().any(|(field, _)| field.is_named(field_name)))
+            .map(|(field_name, _)| format!("'{field_name}'"))
+            .collect();
+        if !missing.is_empty() {
+            let message = format!("Pattern does not mention {}", missing.join(", "));
+            let diagnostic = Diagnostic::new(&message, name.span())
+                .label("missing fields")
+                .footer("add the missing fields, or ignore them with '..'");
+            self.report(diagnostic);
+        }
+    }
+
+    //Check a statement and return the type of the value it leaves behind: the type of a tail
+    //expression, `()` for most statements, or `!` when it always returns before finishing
+    fn tc_stmt(&mut self, stmt: &Stmt, env: &mut TypeEnvironment) -> Types {
+        let unit = Types::new(RustScriptType::Unit);
+        match stmt {
+            Stmt::Item(Item::Fn(decl)) => self.tc_function(decl, env),
+            Stmt::Item(Item::Impl(decl)) => {
+                self.current_impl = Some(self.impl_type(decl));
+                for method in &decl.methods {
+                    self.tc_function(method, env);
+                }
+                self.current_impl = None;
+            }
+            Stmt::Item(Item::Trait(decl)) => {
+                self.current_impl = Some(trait_self(&self.qualify(&decl.name)));
+                for method in &decl.provided {
+                    self.tc_function(method, env);
+                }
+                self.current_impl = None;
+            }
+            Stmt::Item(Item::Struct(_) | Item::Enum(_) | Item::Mod(_) | Item::Use(_) | Item::Extern(_)) => {}
+            Stmt::Let { pattern, annotation, initializer } => {
+                //infer value type from value unless it is declared, remembering whether a closure
+                //assigns to what it captures
+                let declared = annotation.as_ref().map(|annotation| self.annotation_type(annotation));
+                let (mut value_type, mutates) = match initializer {
+                    Expr::Closure { pipe, params, return_type, body } => {
+                        self.tc_closure(pipe, params, return_type, body, declared.as_ref(), env)
+                    }
+                    _ => (self.tc(initializer, env), None),
+                };
+                if let Some(declared) = declared {
+                    self.expect(value_type, &declared, initializer.token());
+                    value_type = declared;
+                }
+                self.lets.push((pattern.token().dup(), value_type.clone()));
+                let errors = self.diagnostics.error_count();
+                self.tc_pattern(pattern, &value_type, env);
+                if let Pattern::Binding { name, .. } = pattern {
+                    if let Some(binding) = env.record.get_mut(&name.as_string()) {
+                        binding.mutates = mutates;
+                    }
+                }
 
-            match operands {
-                AstType::SemiColon => {
-                    statements.push(temp);
-                    temp = Vec::new();
+                //a `let` has no other branch to fall back on, so its pattern must match every value, a
+                //pattern that does not fit the value has already been reported
+                let fits = self.diagnostics.error_count() == errors;
+                if let Some(missing) = missing_pattern(&[pattern], &value_type, &self.declared()).filter(|_| fits) {
+                    let message = "Refutable pattern in local binding";
+                    let label = format!("pattern '{}' not covered", missing);
+                    let diagnostic = Diagnostic::new(message, pattern.token().span())
+                        .label(&label)
+                        .footer("'let' bindings require a pattern that matches every value, use 'match' instead");
+                    self.report(diagnostic);
+                }
+            }
+            Stmt::Expression(expr) => {
+                self.tc(expr, env);
+            }
+            Stmt::Tail(expr) => return self.tc(expr, env),
+            Stmt::Print { keyword, format, args } => {
+                let placeholders = format.matches("{}").count();
+                if placeholders != args.len() {
+                    let message = format!(
+                        "Format string has {} placeholders but {} arguments were given",
+                        placeholders,
+                        args.len()
+                    );
+                    self.error(keyword, &message);
+                }
+                for arg in args {
+                    let arg_type = self.tc(arg, env);
+                    self.printed.push((arg.leftmost().span(), arg_type));
+                }
+            }
+            Stmt::Block(statements) => {
+                return env.scoped(|block_env| self.tc_block(statements, block_env));
+            }
+            Stmt::If { condition, then_branch, else_branch } => {
+                self.tc_condition(condition, env);
+                let then_type = env.scoped(|then_env| self.tc_block(then_branch, then_env));
+                //without an else the body may be skipped, so the if has no value
+                if let Some(else_branch) = else_branch {
+                    let else_type = self.tc_stmt(else_branch, env);
+                    return self.join(then_type, else_type, "If and else", condition.token());
+                }
+            }
+            Stmt::While { condition, body } => {
+                self.tc_condition(condition, env);
+                self.loops += 1;
+                env.scoped(|body_env| self.tc_block(body, body_env));
+                self.loops -= 1;
+            }
+            Stmt::For { variable, iterable, body } => {
+                //ranges have the type of the numbers they count over
+                let iterable_type = self.tc(iterable, env);
+                let element_type = match iterable_type.name {
+                    _ if matches!(iterable, Expr::Range { .. }) => iterable_type,
+                    RustScriptType::Vec(element) => *element,
+                    RustScriptType::UnKnown => iterable_type,
+                    iterable_type => {
+                        let message = format!("'{}' is not iterable", Types::new(iterable_type));
+                        self.error(iterable.token(), &message);
+                        Types::new(RustScriptType::UnKnown)
+                    }
+                };
+                self.loops += 1;
+                env.scoped(|body_env| {
+                    body_env.define(variable, element_type, BindingKind::LoopVariable);
+                    self.tc_block(body, body_env)
+                });
+                self.loops -= 1;
+            }
+            Stmt::Return { keyword, value } => {
+                let value_type = match value {
+                    Some(value) => self.tc(value, env),
+                    None => unit,
+                };
+                match self.current_return.clone() {
+                    Some(return_type) => {
+                        self.expect(value_type, &return_type, keyword);
+                    }
+                    None => self.error(keyword, "Cannot return outside of a function"),
+                }
+                return Types::new(RustScriptType::Never);
+            }
+            Stmt::Break(keyword) => {
+                if self.loops == 0 {
+                    self.error(keyword, "Cannot break outside of a loop");
                 }
-                AstType::RightBrace => {},
-                _ => temp.push(operands.clone()),
+                return Types::new(RustScriptType::Never);
             }
         }
-        let mut result = Types::new(RustScriptType::UnKnown);
+        unit
+    }
 
-        for stmt in statements.iter() {
-            // result = self.tc(stmt, env);
-            println!("{:?}", stmt);
+    fn undefined_variable(&mut self, name: &Token) {
+        let message = format!("Undefined variable '{}'", name.as_string());
+        let mut diagnostic = Diagnostic::new(&message, name.span()).label("not found in this scope");
+        if self.outer_locals.contains(&name.as_string()) {
+            let footer = "a nested function cannot use the variables of the function it is in, a closure can";
+            diagnostic = diagnostic.footer(footer);
         }
+        self.report(diagnostic);
+    }
 
-        result
+    //A function must produce its declared return type on every path, through `return` or its tail.
+    //As in Rust, a function nested in another one sees the items around it but not the variables
+    fn tc_function(&mut self, decl: &FnDecl, env: &mut TypeEnvironment) {
+        let mut items = None;
+        let mut outer_locals = Vec::new();
+        let env = match env.parent.is_some() {
+            true => {
+                outer_locals = env.locals();
+                outer_locals.extend(self.outer_locals.iter().cloned());
+                items.insert(env.items())
+            }
+            false => env,
+        };
+        let enclosing_locals = std::mem::replace(&mut self.outer_locals, outer_locals);
+        self.check_unique(decl.params.iter().map(|param| &param.name).collect());
+        let self_type = match (&decl.self_param, &self.current_impl) {
+            (Some(self_param), Some(current)) => Some((self_param, current.clone())),
+            _ => None,
+        };
+        //inside its body a type parameter is a type of its own, whatever a caller uses it as
+        let generics = self.generics_of(decl);
+        let enclosing_generics = std::mem::replace(&mut self.generics, generics);
+        let params: Vec<(&Param, Types)> =
+            decl.params.iter().map(|param| (param, self.annotation_type(&param.annotation))).collect();
+
+        let return_type = match &decl.return_type {
+            Some(annotation) => self.annotation_type(annotation),
+            None => Types::new(RustScriptType::Unit),
+        };
+        let enclosing = self.current_return.replace(return_type.clone());
+        let enclosing_propagates = std::mem::replace(&mut self.propagates, false);
+        //a loop around a nested function or a closure cannot be broken out of from its body
+        let enclosing_loops = std::mem::replace(&mut self.loops, 0);
+        let enclosing_lets = std::mem::take(&mut self.lets);
+        let body_type = env.scoped(|fn_env| {
+            if let Some((self_param, self_type)) = self_type {
+                fn_env.define(self_param, self_type, BindingKind::Parameter { mutable: decl.self_mutable });
+            }
+            for (param, param_type) in params {
+                fn_env.define(&param.name, param_type, BindingKind::Parameter { mutable: param.mutable });
+            }
+            self.tc_block(&decl.body, fn_env)
+        });
+        self.loops = enclosing_loops;
+        let lets = std::mem::replace(&mut self.lets, enclosing_lets);
+        self.check_inferred(lets);
+        self.current_return = enclosing;
+        self.generics = enclosing_generics;
+        self.outer_locals = enclosing_locals;
+        if std::mem::replace(&mut self.propagates, enclosing_propagates) {
+            self.types.propagates.insert(decl.name.offset);
+        }
+
+        if body_type.is_never() {
+            return;
+        }
+        if body_type.name == RustScriptType::Unit && return_type.name != RustScriptType::Unit {
+            let message = format!(
+                "Function '{}' must return '{}' but not every path returns a value",
+                decl.name.as_string(),
+                return_type
+            );
+            let mut diagnostic = Diagnostic::new(&message, decl.name.span()).label("not every path returns a value");
+            if let Some(annotation) = &decl.return_type {
+                diagnostic = diagnostic.note(annotation.token().span(), "return type declared here");
+            }
+            self.report(diagnostic);
+            return;
+        }
+        let token = match decl.body.last() {
+            Some(Stmt::Tail(expr)) => expr.token(),
+            _ => &decl.name,
+        };
+        self.expect(body_type, &return_type, token);
+    }
+
+    //By the end of its function, what a `let` binds must have a type, `let v = [];` alone does not say
+    //what the vector holds
+    fn check_inferred(&mut self, lets: Vec<(Token, Types)>) {
+        //a type that did not match is left undecided, that error is the one to fix
+        if self.diagnostics.has_errors() {
+            return;
+        }
+        for (token, value_type) in lets {
+            let value_type = self.resolve(&value_type);
+            if !value_type.has_type_var() {
+                continue;
+            }
+            let label = format!("the type '{}' is not fully known", value_type);
+            let diagnostic = Diagnostic::new("Type annotations needed", token.span())
+                .label(&label)
+                .footer("give the binding a type, such as 'let v: Vec<i32> = ..'");
+            self.report(diagnostic);
+        }
+    }
+
+    //The type of a block is the type of its last statement, or `!` if any statement always returns
+    fn tc_block(&mut self, statements: &[Stmt], env: &mut TypeEnvironment) -> Types {
+        self.declare_items(statements, env);
+        let mut block_type = Types::new(RustScriptType::Unit);
+        for stmt in statements {
+            let stmt_type = self.tc_stmt(stmt, env);
+            if !block_type.is_never() {
+                block_type = stmt_type;
+            }
+        }
+        block_type
+    }
+}
+
+fn numeric_types() -> Vec<Types> {
+    vec![Types::new(RustScriptType::I32), Types::new(RustScriptType::I64), Types::new(RustScriptType::F64)]
+}
+
+//Points at the declaration of an immutable name, suggesting `mut` where it can be declared mutable
+fn declared_note(variable: &Token, kind: BindingKind) -> String {
+    match kind {
+        _ if variable.is(TokenType::SelfValue) => "help: consider changing this to '&mut self'".to_string(),
+        BindingKind::Variable { .. } | BindingKind::Parameter { .. } => {
+            format!("help: consider making this binding mutable: 'mut {}'", variable.as_string())
+        }
+        BindingKind::LoopVariable => format!("first assignment to '{}'", variable.as_string()),
+        BindingKind::Function => format!("'{}' is declared here", variable.as_string()),
     }
+}
+
+//The name of the item a statement declares, structs, enums and traits share a namespace with
+//functions as far as imports are concerned
+fn item_names(stmt: &Stmt) -> Vec<&Token> {
+    match stmt {
+        Stmt::Item(Item::Fn(decl)) => vec![&decl.name],
+        Stmt::Item(Item::Struct(StructDecl { name, .. }))
+        | Stmt::Item(Item::Enum(EnumDecl { name, .. }))
+        | Stmt::Item(Item::Trait(TraitDecl { name, .. })) => vec![name],
+        Stmt::Item(Item::Extern(decl)) => decl.functions.iter().map(|function| &function.name).collect(),
+        _ => Vec::new(),
+    }
+}
+
+//Whether the impl block declares `name` as a type parameter, `impl<T>`
+fn is_impl_param(decl: &ImplDecl, name: &Token) -> bool {
+    decl.generics.iter().any(|generic| generic.name.is_named(&name.as_string()))
+}
+
+//`console.error`, an identifier or a property of one, which is what extern functions are called by
+fn is_js_path(path: &str) -> bool {
+    path.split('.').all(|name| {
+        let mut chars = name.chars();
+        chars.next().is_some_and(|first| first.is_alphabetic() || first == '_' || first == '$')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+    })
+}
+
+//The type `Self` stands for in a trait, any type that implements it
+fn trait_self(trait_name: &str) -> Types {
+    Types::new(RustScriptType::Param(TypeParam { name: "Self".to_string(), bounds: vec![trait_name.to_string()] }))
+}
+
+//`Some`, `None`, `Ok` and `Err`, also when written with their enum name as in `Option::Some`.
+//Returns the enum and the variant's token
+pub fn prelude_variant(path: &[Token]) -> Option<(&'static str, &Token)> {
+    let (enum_name, variant) = match path {
+        [variant] => (None, variant),
+        [enum_name, variant] => (Some(enum_name), variant),
+        _ => return None,
+    };
+    let prelude = prelude_enum(&variant.as_string())?;
+    match enum_name {
+        Some(enum_name) if !enum_name.is_named(prelude) => None,
+        _ => Some((prelude, variant)),
+    }
+}
+
+//The type a `Some`, `None`, `Ok` or `Err` pattern matches and the types of its payload, taken from
+//the type of the value matched when that is the same enum
+fn prelude_payload(enum_name: &str, variant: &Token, expected: &Types) -> (Types, Vec<Types>) {
+    let unknown = Box::new(Types::new(RustScriptType::UnKnown));
+    let pattern_type = match (&expected.name, enum_name) {
+        (RustScriptType::Option(_), "Option") | (RustScriptType::Result(..), "Result") => expected.clone(),
+        (_, "Option") => Types::new(RustScriptType::Option(unknown)),
+        _ => Types::new(RustScriptType::Result(unknown.clone(), unknown)),
+    };
+    let fields = match (&pattern_type.name, variant.as_string().as_str()) {
+        (_, "None") => Vec::new(),
+        (RustScriptType::Option(value), _) | (RustScriptType::Result(value, _), "Ok") => vec![*value.clone()],
+        (RustScriptType::Result(_, error), _) => vec![*error.clone()],
+        _ => unreachable!("the pattern type is an option or a result"),
+    };
+    (pattern_type, fields)
+}
 
-    
+//`Vec::new()`, an empty vector whose element type is not known yet
+pub fn is_vec_new(path: &[Token]) -> bool {
+    path.len() == 2 && path[0].is_named("Vec") && path[1].is_named("new")
 }
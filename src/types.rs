@@ -1,11 +1,43 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
-use crate::ast::{AstType};
-
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub enum RustScriptType {
-    Number,
+    /// `i32`, a Javascript number whose division truncates.
+    I32,
+    /// `i64`, a Javascript BigInt.
+    I64,
+    /// `f64`, also written `num`.
+    F64,
+    /// An integer literal whose type is not decided yet. It takes the numeric type it is used as,
+    /// or `i32` when nothing decides it.
+    IntVar(usize),
     String,
+    Bool,
+    Enum(String),
+    /// A struct and its type arguments, empty unless the struct is generic.
+    Struct(String, Vec<Types>),
+    Tuple(Vec<Types>),
+    /// `Vec<T>` or `[T]`, a growable array of elements of one type.
+    Vec(Box<Types>),
+    /// `Option<T>`, either `Some(T)` or `None`.
+    Option(Box<Types>),
+    /// `Result<T, E>`, either `Ok(T)` or `Err(E)`.
+    Result(Box<Types>, Box<Types>),
+    Function {
+        params: Vec<Types>,
+        return_type: Box<Types>,
+    },
+    /// A type parameter of the generic function or impl block being checked, `T` in `fn first<T>`.
+    /// Nothing is known about it but its trait bounds, so it only agrees with itself.
+    Param(TypeParam),
+    /// The type a type parameter stands for at one use of a generic function or struct, decided
+    /// by the types it is used with.
+    Var(usize),
+    /// `()`, the type of functions and blocks without a value.
+    Unit,
+    /// The type of a block that always returns before reaching its end.
+    Never,
     UnKnown,
 }
 
@@ -16,48 +48,338 @@ pub struct Types {
 
 impl fmt::Display for Types {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.name)
+        match &self.name {
+            RustScriptType::I32 => write!(f, "i32"),
+            RustScriptType::I64 => write!(f, "i64"),
+            RustScriptType::F64 => write!(f, "f64"),
+            RustScriptType::IntVar(_) => write!(f, "{{integer}}"),
+            RustScriptType::String => write!(f, "str"),
+            RustScriptType::Bool => write!(f, "bool"),
+            RustScriptType::Enum(name) | RustScriptType::Param(TypeParam { name, .. }) => write!(f, "{name}"),
+            RustScriptType::Struct(name, args) if args.is_empty() => write!(f, "{name}"),
+            RustScriptType::Struct(name, args) => {
+                let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+                write!(f, "{name}<{}>", args.join(", "))
+            }
+            RustScriptType::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                write!(f, "({})", elements.join(", "))
+            }
+            RustScriptType::Vec(element) => write!(f, "Vec<{element}>"),
+            RustScriptType::Option(value) => write!(f, "Option<{value}>"),
+            RustScriptType::Result(value, error) => write!(f, "Result<{value}, {error}>"),
+            RustScriptType::Function { params, return_type } => {
+                let params: Vec<String> = params.iter().map(|p| p.to_string()).collect();
+                write!(f, "fn({})", params.join(", "))?;
+                match return_type.name {
+                    RustScriptType::Unit => Ok(()),
+                    _ => write!(f, " -> {return_type}"),
+                }
+            }
+            RustScriptType::Var(_) => write!(f, "_"),
+            RustScriptType::Unit => write!(f, "()"),
+            RustScriptType::Never => write!(f, "!"),
+            RustScriptType::UnKnown => write!(f, "unknown"),
+        }
     }
 }
 
 impl Types {
-    pub fn new(types: RustScriptType) -> Types{
+    pub fn new(types: RustScriptType) -> Types {
         Types { name: types }
     }
 
-    pub fn self_nb(self) -> Types {
-        self
+    //Unknown parts of a tuple, vector, option, result, struct or function type have already been reported or cannot be
+    //checked yet, so they match anything, like an unknown type on its own
+    pub fn equals(&self, other: &Types) -> bool {
+        match (&self.name, &other.name) {
+            (RustScriptType::Tuple(elements), RustScriptType::Tuple(others)) => {
+                elements.len() == others.len() && elements.iter().zip(others).all(|(a, b)| a.matches(b))
+            }
+            (RustScriptType::Vec(element), RustScriptType::Vec(other))
+            | (RustScriptType::Option(element), RustScriptType::Option(other)) => element.matches(other),
+            (RustScriptType::Result(value, error), RustScriptType::Result(other_value, other_error)) => {
+                value.matches(other_value) && error.matches(other_error)
+            }
+            (RustScriptType::Struct(name, args), RustScriptType::Struct(other, other_args)) => {
+                name == other
+                    && args.len() == other_args.len()
+                    && args.iter().zip(other_args).all(|(a, b)| a.matches(b))
+            }
+            (
+                RustScriptType::Function { params, return_type },
+                RustScriptType::Function { params: other_params, return_type: other_return },
+            ) => {
+                params.len() == other_params.len()
+                    && params.iter().zip(other_params).all(|(a, b)| a.matches(b))
+                    && return_type.matches(other_return)
+            }
+            (name, other) => name == other,
+        }
+    }
+
+    //The types this one is built from, the elements of a tuple or the parameters of a function
+    fn parts(&self) -> Vec<&Types> {
+        match &self.name {
+            RustScriptType::Tuple(elements) | RustScriptType::Struct(_, elements) => elements.iter().collect(),
+            RustScriptType::Vec(element) | RustScriptType::Option(element) => vec![element],
+            RustScriptType::Result(value, error) => vec![value, error],
+            RustScriptType::Function { params, return_type } => {
+                params.iter().chain(std::iter::once(return_type.as_ref())).collect()
+            }
+            _ => Vec::new(),
+        }
     }
 
-    fn get_name(&self) -> RustScriptType {
-        self.name.clone()
+    /// The type parameters this type mentions, each once.
+    pub fn type_params(&self) -> Vec<TypeParam> {
+        let mut params = Vec::new();
+        let mut pending = vec![self];
+        while let Some(ty) = pending.pop() {
+            match &ty.name {
+                RustScriptType::Param(param) if !params.contains(param) => params.push(param.clone()),
+                _ => pending.extend(ty.parts()),
+            }
+        }
+        params
     }
 
-    fn to_string(&self) {
-        self.get_name();
+    /// Whether the type variable `var` appears in this type.
+    pub fn mentions(&self, var: usize) -> bool {
+        self.name == RustScriptType::Var(var) || self.parts().iter().any(|part| part.mentions(var))
     }
-    
-    pub fn equals(&self, other: Types) -> bool {
-        // println!("Type Equality Check: {} {}", &self.get_name(), other.name);
-        self.name == other.name
+
+    /// Whether any type variable appears in this type, once those decided have been replaced.
+    pub fn has_type_var(&self) -> bool {
+        matches!(self.name, RustScriptType::Var(_)) || self.parts().iter().any(|part| part.has_type_var())
     }
-    // meant to convert type annotation 'number'  in code for his langauge to Type.number  
-    pub fn from_string(type_str: RustScriptType) -> Types {
+
+    /// Replace the type parameters named in `substitution` throughout this type.
+    pub fn substitute(&self, substitution: &HashMap<String, Types>) -> Types {
+        let boxed = |ty: &Types| Box::new(ty.substitute(substitution));
+        let all = |types: &[Types]| types.iter().map(|ty| ty.substitute(substitution)).collect();
+        let name = match &self.name {
+            RustScriptType::Param(param) => {
+                return substitution.get(&param.name).cloned().unwrap_or_else(|| self.clone());
+            }
+            RustScriptType::Struct(name, args) => RustScriptType::Struct(name.clone(), all(args)),
+            RustScriptType::Tuple(elements) => RustScriptType::Tuple(all(elements)),
+            RustScriptType::Vec(element) => RustScriptType::Vec(boxed(element)),
+            RustScriptType::Option(value) => RustScriptType::Option(boxed(value)),
+            RustScriptType::Result(value, error) => RustScriptType::Result(boxed(value), boxed(error)),
+            RustScriptType::Function { params, return_type } => {
+                RustScriptType::Function { params: all(params), return_type: boxed(return_type) }
+            }
+            name => name.clone(),
+        };
+        Types::new(name)
+    }
+
+    fn matches(&self, other: &Types) -> bool {
+        self.is_unknown() || other.is_unknown() || self.equals(other)
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        self.name == RustScriptType::UnKnown
+    }
+
+    pub fn is_never(&self) -> bool {
+        self.name == RustScriptType::Never
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(self.name, RustScriptType::I32 | RustScriptType::I64 | RustScriptType::IntVar(_))
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.name == RustScriptType::F64
+    }
+
+    // meant to convert type annotation 'num' in code for his langauge to Type.number
+    pub fn from_string(type_str: &str) -> Types {
         match type_str {
-            RustScriptType::Number => Types { name: RustScriptType::Number },
-            RustScriptType::String => Types { name: RustScriptType::String },
-            RustScriptType::UnKnown =>  {
-                println!("unknown type {:?}", &type_str);
+            "num" | "f64" => Types { name: RustScriptType::F64 },
+            "i32" => Types { name: RustScriptType::I32 },
+            "i64" => Types { name: RustScriptType::I64 },
+            "str" => Types { name: RustScriptType::String },
+            "bool" => Types { name: RustScriptType::Bool },
+            _ => {
+                println!("unknown type {:?}", type_str);
                 Types { name: RustScriptType::UnKnown }
-            },
+            }
+        }
+    }
+}
+
+/// A type parameter, `T` in `fn first<T>`, and the traits the types it stands for must implement.
+#[derive(Debug, PartialEq, PartialOrd, Clone)]
+pub struct TypeParam {
+    pub name: String,
+    pub bounds: Vec<String>,
+}
+
+/// The variants of a user defined enum and the types of their payloads, in declaration order.
+#[derive(Debug, Clone)]
+pub struct EnumType {
+    pub name: String,
+    pub variants: Vec<(String, Vec<Types>)>,
+}
+
+impl EnumType {
+    pub fn variant(&self, name: &str) -> Option<&Vec<Types>> {
+        self.variants.iter().find(|(variant, _)| variant == name).map(|(_, fields)| fields)
+    }
+}
+
+/// A function in an impl block. Methods take `self`, associated functions are called on the type.
+#[derive(Debug, Clone)]
+pub struct MethodType {
+    /// The method's own type parameters, the impl block's are those of the struct.
+    pub generics: Vec<TypeParam>,
+    /// The traits the struct's type arguments must implement for the method to be available, the
+    /// bounds its impl block puts on the struct's type parameters.
+    pub requires: Vec<TypeParam>,
+    /// `pub fn`, code outside the struct's module can call it.
+    pub public: bool,
+    pub takes_self: bool,
+    /// `&mut self` or `mut self`, the receiver must be mutable.
+    pub mutates_self: bool,
+    pub params: Vec<Types>,
+    pub return_type: Types,
+}
+
+impl fmt::Display for MethodType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let receiver = match (self.takes_self, self.mutates_self) {
+            (false, _) => None,
+            (true, false) => Some("&self".to_string()),
+            (true, true) => Some("&mut self".to_string()),
+        };
+        let params: Vec<String> = receiver.into_iter().chain(self.params.iter().map(|p| p.to_string())).collect();
+        write!(f, "fn({})", params.join(", "))?;
+        match self.return_type.name {
+            RustScriptType::Unit => Ok(()),
+            _ => write!(f, " -> {}", self.return_type),
         }
     }
+}
 
-    pub fn type_to_token(&self) -> AstType {
-        match self {
-            Types { name: RustScriptType::Number } => AstType::Number,
-            Types { name: RustScriptType::String }  => AstType::String,
-            Types { name: RustScriptType::UnKnown }  => AstType::UnKnown,
+/// A user defined struct, its fields in declaration order and the functions of its impl blocks.
+/// The types of fields and methods refer to the struct's type parameters by the names in `generics`.
+#[derive(Debug, Clone)]
+pub struct StructType {
+    pub name: String,
+    pub generics: Vec<TypeParam>,
+    pub fields: Vec<(String, Types)>,
+    /// The fields not declared `pub`, only the struct's module and the modules inside it can use them.
+    pub private_fields: Vec<String>,
+    pub methods: HashMap<String, MethodType>,
+    /// The traits the struct implements, each with the bounds its impl block puts on the struct's
+    /// type parameters.
+    pub traits: HashMap<String, Vec<TypeParam>>,
+}
+
+impl StructType {
+    pub fn field(&self, name: &str) -> Option<&Types> {
+        self.fields.iter().find(|(field, _)| field == name).map(|(_, field_type)| field_type)
+    }
+
+    /// What each of the struct's type parameters stands for in `Struct(name, args)`.
+    pub fn substitution(&self, args: &[Types]) -> HashMap<String, Types> {
+        self.generics.iter().map(|param| param.name.clone()).zip(args.iter().cloned()).collect()
+    }
+
+    /// The struct with the types of its fields given for the type arguments `args`. Methods keep
+    /// their declared types, they are instantiated where they are called.
+    pub fn instantiate(&self, args: &[Types]) -> StructType {
+        let substitution = self.substitution(args);
+        let fields = self.fields.iter().map(|(name, ty)| (name.clone(), ty.substitute(&substitution))).collect();
+        StructType { fields, ..self.clone() }
+    }
+}
+
+/// A trait, the methods its implementations have. Their types refer to the implementing type as
+/// the type parameter `Self`.
+#[derive(Debug, Clone)]
+pub struct TraitType {
+    pub name: String,
+    pub methods: HashMap<String, MethodType>,
+    /// The methods without a default body, which every implementation must define.
+    pub required: Vec<String>,
+}
+
+/// A method built into a type rather than declared in an impl block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Builtin {
+    /// `v.len()`, the number of elements of a vector.
+    Len,
+    /// `v.push(x)`, appends to a vector.
+    Push,
+}
+
+/// The built in enum a variant that can be used without its enum name belongs to, `Option` for
+/// `Some` and `None`, `Result` for `Ok` and `Err`.
+pub fn prelude_enum(variant: &str) -> Option<&'static str> {
+    match variant {
+        "Some" | "None" => Some("Option"),
+        "Ok" | "Err" => Some("Result"),
+        _ => None,
+    }
+}
+
+/// How an `as` cast between numeric types is lowered, casts that are not listed leave the value as it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cast {
+    /// `f64 as i32`, `Math.trunc(x)`.
+    Truncate,
+    /// `i32 as i64`, `BigInt(x)`.
+    ToBigInt,
+    /// `f64 as i64`, `BigInt(Math.trunc(x))`.
+    TruncateToBigInt,
+    /// `i64 as i32`, `Number(BigInt.asIntN(32, x))`, wrapping around like Rust.
+    WrapToI32,
+    /// `i64 as f64`, `Number(x)`.
+    ToNumber,
+}
+
+impl Cast {
+    pub fn between(from: &Types, to: &Types) -> Option<Cast> {
+        match (&from.name, &to.name) {
+            (RustScriptType::F64, RustScriptType::I32) => Some(Cast::Truncate),
+            (RustScriptType::I32, RustScriptType::I64) => Some(Cast::ToBigInt),
+            (RustScriptType::F64, RustScriptType::I64) => Some(Cast::TruncateToBigInt),
+            (RustScriptType::I64, RustScriptType::I32) => Some(Cast::WrapToI32),
+            (RustScriptType::I64, RustScriptType::F64) => Some(Cast::ToNumber),
+            _ => None,
         }
     }
-}
\ No newline at end of file
+}
+
+/// What code generation needs to know from type checking, keyed by the byte offset of the token
+/// that identifies an expression.
+#[derive(Debug, Default)]
+pub struct TypeTable {
+    /// Calls of built in methods, keyed by the method name.
+    pub builtins: HashMap<usize, Builtin>,
+    /// Whether `Option` or `Result` values are built or their types named, their runtime definitions
+    /// are then emitted.
+    pub prelude: bool,
+    /// The functions and closures that use `?`, keyed by the function name or the closure's `|`.
+    pub propagates: HashSet<usize>,
+    /// Integer literals of type `i64`, emitted as BigInt literals.
+    pub bigints: HashSet<usize>,
+    /// `+`, `-`, `*` and `/` of `i32` values, keyed by the operator. Like Rust's `wrapping_` methods
+    /// the result is kept to 32 bits, and a division is truncated towards zero.
+    pub int_arithmetic: HashSet<usize>,
+    /// `==` and `!=` between values Javascript keeps as objects, keyed by the operator, compared by
+    /// their contents like Rust.
+    pub structural_comparisons: HashSet<usize>,
+    /// `as` casts that change the Javascript representation, keyed by the `as` keyword.
+    pub casts: HashMap<usize, Cast>,
+    /// The provided methods of a trait that an impl block leaves out, keyed by the trait's name in the
+    /// header, and whether each takes `self`. They are copied from the trait onto the struct's class.
+    pub provided: HashMap<usize, Vec<(String, bool)>>,
+    /// The fields of the structs imported from other modules in declaration order, the order their
+    /// constructors take them.
+    pub imported_fields: HashMap<String, Vec<String>>,
+}
@@ -1,14 +0,0 @@
-//defer function usage: //let _d = defer(|| self.add_token(AstType::Main));
-struct Defer<F: FnOnce()>(Option<F>);
-
-impl<F: FnOnce()> Drop for Defer<F> {
-    fn drop(&mut self) {
-        self.0.take().map(|f| f());
-    }
-}
-
-/// Defer execution of a closure until the return value is dropped.
-pub fn defer<F: FnOnce()>(f: F) -> impl Drop {
-    Defer(Some(f))
-}
-
@@ -0,0 +1,124 @@
+//! Each directory in `tests/programs` is a program, `main.rst` and the modules it declares. It is
+//! compiled by the rustscript binary in a directory of its own, with the flags in its `args` file.
+//! The files in its `expected` directory are compared with the ones written to `target/`, and its
+//! `stderr` file with the diagnostics. Without a `stderr` file the program must compile.
+//!
+//! `BLESS=1 cargo test` writes what the compiler does now as what is expected.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[test]
+fn programs() {
+    let programs = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/programs");
+    let mut names: Vec<PathBuf> = fs::read_dir(&programs)
+        .expect("tests/programs is readable")
+        .map(|entry| entry.expect("tests/programs is readable").path())
+        .collect();
+    names.sort();
+
+    let failures: Vec<String> = names.iter().filter_map(|program| check(program).err()).collect();
+    if !failures.is_empty() {
+        panic!("{} of {} programs failed\n\n{}", failures.len(), names.len(), failures.join("\n"));
+    }
+}
+
+//Compile a copy of the program, so the output and the paths in diagnostics do not depend on where it is
+fn check(program: &Path) -> Result<(), String> {
+    let name = program.file_name().expect("programs are directories").to_string_lossy().to_string();
+    let dir = std::env::temp_dir().join(format!("rustscript-tests-{}", std::process::id())).join(&name);
+    let _ = fs::remove_dir_all(&dir);
+    copy_sources(program, &dir);
+
+    let args = fs::read_to_string(program.join("args")).unwrap_or_default();
+    let output = Command::new(env!("CARGO_BIN_EXE_rustscript"))
+        .args(args.split_whitespace())
+        .arg("main.rst")
+        .current_dir(&dir)
+        .output()
+        .expect("the compiler runs");
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    let mut failures = Vec::new();
+    let bless = std::env::var_os("BLESS").is_some();
+    match fs::read_to_string(program.join("stderr")) {
+        _ if bless && stderr.is_empty() => {
+            let _ = fs::remove_file(program.join("stderr"));
+        }
+        _ if bless => write(&program.join("stderr"), &stderr),
+        Ok(expected) if expected != stderr => failures.push(difference("stderr", &expected, &stderr)),
+        Ok(_) => {}
+        Err(_) if !output.status.success() => failures.push(format!("it does not compile:\n{stderr}")),
+        Err(_) => {}
+    }
+
+    let expected = program.join("expected");
+    if bless && output.status.success() {
+        let _ = fs::remove_dir_all(&expected);
+        for file in files(&dir.join("target")) {
+            let written = fs::read_to_string(dir.join("target").join(&file)).expect("output is readable");
+            write(&expected.join(&file), &written);
+        }
+    }
+    for file in files(&expected) {
+        let wanted = fs::read_to_string(expected.join(&file)).expect("expected output is readable");
+        match fs::read_to_string(dir.join("target").join(&file)) {
+            Ok(written) if written == wanted => {}
+            Ok(written) => failures.push(difference(&file, &wanted, &written)),
+            Err(_) => failures.push(format!("{file} was not written")),
+        }
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+    match failures.is_empty() {
+        true => Ok(()),
+        false => Err(format!("{name}:\n{}\n", failures.join("\n"))),
+    }
+}
+
+//The `.rst` files of the program, in the directories they are in
+fn copy_sources(program: &Path, dir: &Path) {
+    for file in files(program).into_iter().filter(|file| file.ends_with(".rst")) {
+        write(&dir.join(&file), &fs::read_to_string(program.join(&file)).expect("sources are readable"));
+    }
+}
+
+//The paths of the files under `dir`, relative to it and sorted
+fn files(dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else {
+            continue;
+        };
+        for entry in entries {
+            let path = entry.expect("directories are readable").path();
+            if path.is_dir() {
+                pending.push(path);
+            } else {
+                let relative = path.strip_prefix(dir).expect("the file is under the directory");
+                files.push(relative.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn write(path: &Path, contents: &str) {
+    fs::create_dir_all(path.parent().expect("files are in a directory")).expect("the directory can be created");
+    fs::write(path, contents).expect("the file can be written");
+}
+
+//The first line that differs, with the whole of what was written
+fn difference(what: &str, expected: &str, actual: &str) -> String {
+    let line = expected.lines().zip(actual.lines()).position(|(expected, actual)| expected != actual);
+    let line = line.unwrap_or_else(|| expected.lines().count().min(actual.lines().count()));
+    format!(
+        "{what} differs at line {}\n  expected: {:?}\n  actual:   {:?}\n--- actual {what} ---\n{actual}",
+        line + 1,
+        expected.lines().nth(line).unwrap_or("<end>"),
+        actual.lines().nth(line).unwrap_or("<end>"),
+    )
+}
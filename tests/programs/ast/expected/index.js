@@ -0,0 +1,10 @@
+function add(a, b) {
+	return a + b;
+}
+(function main() {
+	const sum = add(add(1, 2), 3);
+	{
+		const inner = sum * 2;
+		console.log(`${sum} ${inner}`);
+	}
+})();
@@ -0,0 +1,10 @@
+function between(value, low, high) {
+	return value >= low && value <= high;
+}
+(function main() {
+	const inside = between(5, 1, 10);
+	const outside = !inside || between(0, 1, 10);
+	if (inside && !outside) {
+		console.log(`${inside} ${outside}`);
+	}
+})();
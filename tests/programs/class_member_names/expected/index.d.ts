@@ -0,0 +1,7 @@
+declare class Shape {
+	constructor(prototype: number);
+	prototype$: number;
+	static constructor$(size: number): Shape;
+	static prototype$(): number;
+	__proto__$(): number;
+}
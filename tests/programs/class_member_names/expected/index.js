@@ -0,0 +1,19 @@
+class Shape {
+	constructor(prototype) {
+		this.prototype$ = prototype;
+	}
+	static constructor$(size) {
+		return new Shape(size);
+	}
+	static prototype$() {
+		return 1;
+	}
+	__proto__$() {
+		return this.prototype$;
+	}
+}
+(function main() {
+	const shape = Shape.constructor$(3);
+	const { prototype$: prototype } = shape;
+	console.log(`${Shape.prototype$()} ${shape.__proto__$()} ${prototype}`);
+})();
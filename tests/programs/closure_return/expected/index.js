@@ -0,0 +1,9 @@
+(function main() {
+	const clamp = (x) => {
+		if (x > 10) {
+			return 10;
+		}
+		return x;
+	};
+	console.log(`${(clamp(4) + clamp(40) | 0)}`);
+})();
@@ -0,0 +1,9 @@
+type Shape =
+	| { readonly tag: "Circle"; readonly values: readonly [number] }
+	| { readonly tag: "Rect"; readonly values: readonly [number, number] }
+	| { readonly tag: "Empty" };
+declare const Shape: {
+	Circle(arg0: number): Shape;
+	Rect(arg0: number, arg1: number): Shape;
+	readonly Empty: Shape;
+};
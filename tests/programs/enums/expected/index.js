@@ -0,0 +1,23 @@
+const Shape = Object.freeze({
+	Circle: (...values) => Object.freeze({ tag: "Circle", values }),
+	Rect: (...values) => Object.freeze({ tag: "Rect", values }),
+	Empty: Object.freeze({ tag: "Empty" }),
+});
+function area(shape) {
+	const $match = shape;
+	if ($match.tag === "Circle") {
+		const radius = $match.values[0];
+		return 3 * radius * radius;
+	} else if ($match.tag === "Rect") {
+		const width = $match.values[0];
+		const height = $match.values[1];
+		return width * height;
+	} else if ($match.tag === "Empty") {
+		return 0;
+	}
+}
+(function main() {
+	console.log(`${area(Shape.Circle(2))}`);
+	console.log(`${area(Shape.Rect(2, 1.5))}`);
+	console.log(`${area(Shape.Empty)}`);
+})();
@@ -0,0 +1,5 @@
+import { basename, posix } from "node:path";
+(function main() {
+	const posix$1 = 1;
+	console.log(`${Math.min(2.5, 1.5)} ${(parseInt("42") + posix$1 | 0)} ${basename(posix.join("a", "b.rst"))}`);
+})();
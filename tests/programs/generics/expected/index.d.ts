@@ -0,0 +1,19 @@
+type Option<T> =
+	| { readonly tag: "Some"; readonly values: readonly [T] }
+	| { readonly tag: "None" };
+declare const Option: {
+	Some<T>(value: T): Option<T>;
+	readonly None: Option<never>;
+};
+type Result<T, E> =
+	| { readonly tag: "Ok"; readonly values: readonly [T] }
+	| { readonly tag: "Err"; readonly values: readonly [E] };
+declare const Result: {
+	Ok<T>(value: T): Result<T, never>;
+	Err<E>(error: E): Result<never, E>;
+};
+declare class Pair<A, B> {
+	constructor(left: A, right: B);
+	private left: A;
+	private right: B;
+}
@@ -0,0 +1,35 @@
+const Option = Object.freeze({
+	Some: (...values) => Object.freeze({ tag: "Some", values }),
+	None: Object.freeze({ tag: "None" }),
+});
+const Result = Object.freeze({
+	Ok: (...values) => Object.freeze({ tag: "Ok", values }),
+	Err: (...values) => Object.freeze({ tag: "Err", values }),
+});
+class Pair {
+	constructor(left, right) {
+		this.left = left;
+		this.right = right;
+	}
+}
+function first(values) {
+	if (values.length > 0) {
+		return Option.Some(values[0]);
+	}
+	return Option.None;
+}
+function flip(pair) {
+	return new Pair(pair.right, pair.left);
+}
+(function main() {
+	const flipped = flip(new Pair(1, "one"));
+	console.log(`${flipped.left} ${flipped.right}`);
+	{
+		const $match = first(["a", "b"]);
+		if ($match.tag === "Some") {
+			const word = $match.values[0];
+			console.log(`${word}`);
+		} else if ($match.tag === "None") {
+		}
+	}
+})();
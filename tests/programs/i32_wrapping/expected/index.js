@@ -0,0 +1,8 @@
+(function main() {
+	const max = 2147483647;
+	const big = 123456789;
+	console.log(`${(max + 1 | 0)}`);
+	console.log(`${(-max - 2 | 0)}`);
+	console.log(`${Math.imul(big, 987654321)}`);
+	console.log(`${Math.trunc(Math.imul(big, 20) / 3)}`);
+})();
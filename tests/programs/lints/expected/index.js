@@ -0,0 +1,14 @@
+function unused_function() {
+}
+function allowed_function() {
+}
+function scale(value, factor) {
+	return Math.imul(value, 2);
+	return Math.imul(value, factor);
+}
+(function main() {
+	const total = 1;
+	const total$1 = (total + 1 | 0);
+	const spare = 3;
+	console.log(`${scale(total$1, 2)}`);
+})();
@@ -0,0 +1,37 @@
+function describe(point) {
+	const $match = point;
+	if ($match[0] === 0 && $match[1] === 0) {
+		return "origin";
+	} else if ($match[0] === 0) {
+		return "on the y axis";
+	} else if ($match[1] === 0) {
+		return "on the x axis";
+	} else if (true) {
+		return "elsewhere";
+	}
+}
+(function main() {
+	console.log(`${describe([0, 0])}`);
+	console.log(`${describe([0, 3])}`);
+	console.log(`${describe([2, 0])}`);
+	console.log(`${describe([1, 1])}`);
+	let $value1;
+	{
+		const $match = 5 > 0;
+		if ($match === true) {
+			$value1 = 1;
+		} else if ($match === false) {
+			$value1 = -1;
+		}
+	}
+	const sign = $value1;
+	{
+		const $match = sign;
+		if ($match === 1) {
+			console.log(`positive`);
+		} else if (true) {
+			const other = $match;
+			console.log(`${other}`);
+		}
+	}
+})();
@@ -0,0 +1,34 @@
+const $counter = (() => {
+	function next(value) {
+		return step(value);
+	}
+	function step(value) {
+		return (value + 1 | 0);
+	}
+	return { next };
+})();
+const $shapes$square = (() => {
+	const { next } = $counter;
+	class Square {
+		constructor(side) {
+			this.side = side;
+		}
+		area() {
+			return Math.imul(this.side, this.side);
+		}
+	}
+	function unit() {
+		return new Square(next(0));
+	}
+	return { Square, unit };
+})();
+const { next } = $counter;
+const { Square } = $shapes$square;
+(function main() {
+	console.log(`${next(1)}`);
+	console.log(`${new Square(3).area()}`);
+	console.log(`${$shapes$square.unit().side}`);
+})();
+const $shapes = (() => {
+	return {};
+})();
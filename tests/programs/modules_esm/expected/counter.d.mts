@@ -0,0 +1,2 @@
+export declare function next(value: number): number;
+export {};
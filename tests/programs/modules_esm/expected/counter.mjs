@@ -0,0 +1,6 @@
+export function next(value) {
+	return step(value);
+}
+function step(value) {
+	return (value + 1 | 0);
+}
@@ -0,0 +1,8 @@
+import { next } from "./counter.mjs";
+import { Square } from "./shapes/square.mjs";
+import * as $shapes$square from "./shapes/square.mjs";
+(function main() {
+	console.log(`${next(1)}`);
+	console.log(`${new Square(3).area()}`);
+	console.log(`${$shapes$square.unit().side}`);
+})();
@@ -0,0 +1,7 @@
+export declare class Square {
+	constructor(side: number);
+	side: number;
+	area(): number;
+}
+export declare function unit(): Square;
+export {};
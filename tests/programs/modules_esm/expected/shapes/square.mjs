@@ -0,0 +1,12 @@
+import { next } from "../counter.mjs";
+export class Square {
+	constructor(side) {
+		this.side = side;
+	}
+	area() {
+		return Math.imul(this.side, this.side);
+	}
+}
+export function unit() {
+	return new Square(next(0));
+}
@@ -0,0 +1,7 @@
+(function main() {
+	const step = 2;
+	let total = 0;
+	total = (total + step | 0);
+	total = (total + step | 0);
+	console.log(`${total}`);
+})();
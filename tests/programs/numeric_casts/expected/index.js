@@ -0,0 +1,9 @@
+(function main() {
+	const big = 3000000000n;
+	const ratio = 2.75;
+	console.log(`${big * 2n}`);
+	console.log(`${Math.trunc(ratio)}`);
+	console.log(`${Number(BigInt.asIntN(32, big))}`);
+	console.log(`${(BigInt(Math.trunc(ratio))) + big}`);
+	console.log(`${Number(big) / 2}`);
+})();
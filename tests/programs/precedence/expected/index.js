@@ -0,0 +1,13 @@
+function double(x) {
+	return Math.imul(x, 2);
+}
+(function main() {
+	const a = 10;
+	const b = 4;
+	console.log(`${((a - b | 0) - 3 | 0)}`);
+	console.log(`${(a - (b - 3 | 0) | 0)}`);
+	console.log(`${(2 + Math.imul(double(b), 3) | 0)}`);
+	console.log(`${(-a + b | 0)}`);
+	console.log(`${a > b && !(b > 5) || false}`);
+	console.log(`${Math.imul(Math.trunc(a / b), 2)}`);
+})();
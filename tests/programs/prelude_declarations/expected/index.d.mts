@@ -0,0 +1,17 @@
+export type Option<T> =
+	| { readonly tag: "Some"; readonly values: readonly [T] }
+	| { readonly tag: "None" };
+export declare const Option: {
+	Some<T>(value: T): Option<T>;
+	readonly None: Option<never>;
+};
+export type Result<T, E> =
+	| { readonly tag: "Ok"; readonly values: readonly [T] }
+	| { readonly tag: "Err"; readonly values: readonly [E] };
+export declare const Result: {
+	Ok<T>(value: T): Result<T, never>;
+	Err<E>(error: E): Result<never, E>;
+};
+export declare function positive(value: number): Option<number>;
+export declare function main(): void;
+export {};
@@ -0,0 +1,25 @@
+export const Option = Object.freeze({
+	Some: (...values) => Object.freeze({ tag: "Some", values }),
+	None: Object.freeze({ tag: "None" }),
+});
+export const Result = Object.freeze({
+	Ok: (...values) => Object.freeze({ tag: "Ok", values }),
+	Err: (...values) => Object.freeze({ tag: "Err", values }),
+});
+export function positive(value) {
+	if (value > 0) {
+		return Option.Some(value);
+	}
+	return Option.None;
+}
+(function main() {
+	{
+		const $match = positive(3);
+		if ($match.tag === "Some") {
+			const value = $match.values[0];
+			console.log(`${value}`);
+		} else if ($match.tag === "None") {
+			console.log(`not positive`);
+		}
+	}
+})();
@@ -0,0 +1,29 @@
+export type Option<T> =
+	| { readonly tag: "Some"; readonly values: readonly [T] }
+	| { readonly tag: "None" };
+export declare const Option: {
+	Some<T>(value: T): Option<T>;
+	readonly None: Option<never>;
+};
+export type Result<T, E> =
+	| { readonly tag: "Ok"; readonly values: readonly [T] }
+	| { readonly tag: "Err"; readonly values: readonly [E] };
+export declare const Result: {
+	Ok<T>(value: T): Result<T, never>;
+	Err<E>(error: E): Result<never, E>;
+};
+export declare class Result$<T> {
+	constructor(value: T);
+	private value: T;
+}
+export type Option$ =
+	| { readonly tag: "Some"; readonly values: readonly [number] }
+	| { readonly tag: "Empty" };
+export declare const Option$: {
+	Some(arg0: number): Option$;
+	readonly Empty: Option$;
+};
+export declare function wrap(value: number): Result$<number>;
+export declare function first(values: number[]): Option$;
+export declare function main(): void;
+export {};
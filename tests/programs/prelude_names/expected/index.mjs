@@ -0,0 +1,46 @@
+export const Option = Object.freeze({
+	Some: (...values) => Object.freeze({ tag: "Some", values }),
+	None: Object.freeze({ tag: "None" }),
+});
+export const Result = Object.freeze({
+	Ok: (...values) => Object.freeze({ tag: "Ok", values }),
+	Err: (...values) => Object.freeze({ tag: "Err", values }),
+});
+export class Result$ {
+	constructor(value) {
+		this.value = value;
+	}
+}
+export const Option$ = Object.freeze({
+	Some: (...values) => Object.freeze({ tag: "Some", values }),
+	Empty: Object.freeze({ tag: "Empty" }),
+});
+export function wrap(value) {
+	return new Result$(value);
+}
+export function first(values) {
+	if (values.length > 0) {
+		return Option$.Some(values[0]);
+	}
+	return Option$.Empty;
+}
+(function main() {
+	const wrapped = wrap(4);
+	{
+		const $match = first([wrapped.value]);
+		if ($match.tag === "Some") {
+			const value = $match.values[0];
+			console.log(`${value}`);
+		} else if ($match.tag === "Empty") {
+			console.log(`empty`);
+		}
+	}
+	{
+		const $match = Option.Some(2);
+		if ($match.tag === "Some") {
+			const value = $match.values[0];
+			console.log(`${value}`);
+		} else if ($match.tag === "None") {
+		}
+	}
+})();
@@ -0,0 +1,12 @@
+(function main() {
+	let n = 3;
+	let count = 0;
+	for (let i = 0, $end = n; i < $end; i++) {
+		n = (n + 1 | 0);
+		count = (count + i | 0);
+	}
+	for (let i = 0; i <= 2; i++) {
+		count = (count + i | 0);
+	}
+	console.log(`${n} ${count}`);
+})();
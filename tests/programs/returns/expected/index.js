@@ -0,0 +1,17 @@
+function clamp(value, max) {
+	if (value > max) {
+		return max;
+	}
+	return value;
+}
+function sign(value) {
+	if (value < 0) {
+		return "negative";
+	} else {
+		return "not negative";
+	}
+}
+(function main() {
+	console.log(`${clamp(12, 10)} ${clamp(3, 10)}`);
+	console.log(`${sign(-2)}`);
+})();
@@ -0,0 +1,13 @@
+interface Named {
+	name(): string;
+	greet(): string;
+}
+declare const Named: {
+	greet(this: Named): string;
+};
+declare class Dog {
+	constructor(age: number);
+	private age: number;
+	name(): string;
+	greet(): string;
+}
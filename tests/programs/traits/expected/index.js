@@ -0,0 +1,24 @@
+const Named = {
+	greet() {
+		return "hello " + this.name();
+	},
+};
+class Dog {
+	constructor(age) {
+		this.age = age;
+	}
+	name() {
+		return "dog";
+	}
+}
+Dog.prototype.greet = Named.greet;
+function greet_all(values) {
+	for (const value of values) {
+		console.log(`${value.greet()}`);
+	}
+}
+(function main() {
+	const dog = new Dog(3);
+	console.log(`${dog.name()} ${dog.age}`);
+	greet_all([dog]);
+})();
@@ -0,0 +1,58 @@
+const Option = Object.freeze({
+	Some: (...values) => Object.freeze({ tag: "Some", values }),
+	None: Object.freeze({ tag: "None" }),
+});
+const Result = Object.freeze({
+	Ok: (...values) => Object.freeze({ tag: "Ok", values }),
+	Err: (...values) => Object.freeze({ tag: "Err", values }),
+});
+class $Propagate {
+	constructor(residual) {
+		this.residual = residual;
+	}
+}
+function $try(value) {
+	if (value.tag === "None" || value.tag === "Err") {
+		throw new $Propagate(value);
+	}
+	return value.values[0];
+}
+function half(x) {
+	if (x > 0) {
+		return Result.Ok(Math.trunc(x / 2));
+	} else {
+		return Result.Err("not positive");
+	}
+}
+(function main() {
+	const quarter = (y) => {
+		try {
+			return Result.Ok($try(half($try(half(y)))));
+		} catch ($e) {
+			if ($e instanceof $Propagate) {
+				return $e.residual;
+			}
+			throw $e;
+		}
+	};
+	{
+		const $match = quarter(8);
+		if ($match.tag === "Ok") {
+			const value = $match.values[0];
+			console.log(`${value}`);
+		} else if ($match.tag === "Err") {
+			const message = $match.values[0];
+			console.log(`${message}`);
+		}
+	}
+	{
+		const $match = quarter(-4);
+		if ($match.tag === "Ok") {
+			const value = $match.values[0];
+			console.log(`${value}`);
+		} else if ($match.tag === "Err") {
+			const message = $match.values[0];
+			console.log(`${message}`);
+		}
+	}
+})();
@@ -0,0 +1,5 @@
+declare class Point {
+	constructor(x: number, y: number);
+	private x: number;
+	private y: number;
+}
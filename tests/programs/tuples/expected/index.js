@@ -0,0 +1,15 @@
+class Point {
+	constructor(x, y) {
+		this.x = x;
+		this.y = y;
+	}
+}
+function swap(pair) {
+	return [pair[1], pair[0]];
+}
+(function main() {
+	const [name, count] = swap([2, "apples"]);
+	const { x, y } = new Point(3, 4);
+	const nested = [[1, 2], 3];
+	console.log(`${name} ${count} ${x} ${y} ${(nested[0])[1]}`);
+})();
@@ -0,0 +1,16 @@
+function sum(values) {
+	let total = 0;
+	for (const value of values) {
+		total = (total + value | 0);
+	}
+	return total;
+}
+(function main() {
+	let values = [3, 4];
+	values.push(5);
+	values[0] = 1;
+	console.log(`${values.length} ${values[2]} ${sum(values)}`);
+	let words = [];
+	words.push("one");
+	console.log(`${words[0]}`);
+})();